@@ -0,0 +1,165 @@
+//! A seam for handing big dense operations to something faster than the plain loops here, like
+//! an OpenCL device. This library doesn't ship such a backend, since it would need bindings and
+//! a build setup it doesn't have, but anything implementing `Backend` can be plugged into an
+//! `Offload`. That sends the backend only the operations big enough to be worth the transfer,
+//! and does the small ones, and any the backend turns down, on the CPU as usual.
+
+use matrix::{Mat2, Shape};
+
+/// Something that can do some of the big dense operations itself. Each method returns `None` to
+/// hand the operation back to the CPU, e.g. if the device is out of memory or doesn't support
+/// it; all but `gemm` do that by default.
+pub trait Backend {
+    /// The product `a * b`. The shapes have already been checked.
+    fn gemm(&self, a: &Mat2<f64>, b: &Mat2<f64>) -> Option<Mat2<f64>>;
+
+    /// The elementwise sum `a + b`. The shapes have already been checked.
+    fn add(&self, _a: &Mat2<f64>, _b: &Mat2<f64>) -> Option<Mat2<f64>> {
+        None
+    }
+
+    /// The elementwise product of `a` and `b`. The shapes have already been checked.
+    fn elem_mul(&self, _a: &Mat2<f64>, _b: &Mat2<f64>) -> Option<Mat2<f64>> {
+        None
+    }
+
+    /// The sum of all the elements of `a`.
+    fn sum(&self, _a: &Mat2<f64>) -> Option<f64> {
+        None
+    }
+}
+
+/// The plain CPU implementations, as a `Backend`. Plugging it into an `Offload` changes nothing,
+/// which makes it a stand-in for when there's no device.
+pub struct CpuBackend;
+
+impl Backend for CpuBackend {
+    fn gemm(&self, a: &Mat2<f64>, b: &Mat2<f64>) -> Option<Mat2<f64>> {
+        Some(*a * *b)
+    }
+
+    fn add(&self, a: &Mat2<f64>, b: &Mat2<f64>) -> Option<Mat2<f64>> {
+        Some(*a + *b)
+    }
+
+    fn elem_mul(&self, a: &Mat2<f64>, b: &Mat2<f64>) -> Option<Mat2<f64>> {
+        Some(a.elem_mul(b))
+    }
+
+    fn sum(&self, a: &Mat2<f64>) -> Option<f64> {
+        Some(a.sum())
+    }
+}
+
+/// Dispatches operations between a `Backend` and the CPU by size: an operation goes to the
+/// backend once its work (the number of elements, or of multiply-adds for `mul`) is at least
+/// `threshold`, and falls back to the CPU if the backend returns `None`.
+pub struct Offload<B> {
+    priv backend: B,
+    priv threshold: uint,
+}
+
+impl<B: Backend> Offload<B> {
+    /// Offload to `backend` everything with at least `threshold` work.
+    pub fn new(backend: B, threshold: uint) -> Offload<B> {
+        Offload { backend: backend, threshold: threshold }
+    }
+
+    /// The amount of work an operation needs before it goes to the backend.
+    pub fn threshold(&self) -> uint {
+        self.threshold
+    }
+
+    /// Change the threshold, e.g. after timing both sides on the machine at hand.
+    pub fn set_threshold(&mut self, threshold: uint) {
+        self.threshold = threshold;
+    }
+
+    /// The product `a * b`. Fails if `a` doesn't have a column per row of `b`.
+    pub fn mul(&self, a: &Mat2<f64>, b: &Mat2<f64>) -> Mat2<f64> {
+        let Shape { rows: n, cols: k } = a.get_dimension();
+        let Shape { rows: bn, cols: m } = b.get_dimension();
+        assert!(k == bn, "Offload::mul: a has {} columns but b has {} rows", k, bn);
+        if n * k * m >= self.threshold {
+            match self.backend.gemm(a, b) {
+                Some(c) => return c,
+                None => {}
+            }
+        }
+        *a * *b
+    }
+
+    /// The elementwise sum `a + b`. Fails if the matrices aren't the same shape.
+    pub fn add(&self, a: &Mat2<f64>, b: &Mat2<f64>) -> Mat2<f64> {
+        assert!(a.get_dimension() == b.get_dimension(), "Offload::add: shapes differ");
+        if self.big(a) {
+            match self.backend.add(a, b) {
+                Some(c) => return c,
+                None => {}
+            }
+        }
+        *a + *b
+    }
+
+    /// The elementwise product of `a` and `b`. Fails if the matrices aren't the same shape.
+    pub fn elem_mul(&self, a: &Mat2<f64>, b: &Mat2<f64>) -> Mat2<f64> {
+        assert!(a.get_dimension() == b.get_dimension(), "Offload::elem_mul: shapes differ");
+        if self.big(a) {
+            match self.backend.elem_mul(a, b) {
+                Some(c) => return c,
+                None => {}
+            }
+        }
+        a.elem_mul(b)
+    }
+
+    /// The sum of all the elements of `a`.
+    pub fn sum(&self, a: &Mat2<f64>) -> f64 {
+        if self.big(a) {
+            match self.backend.sum(a) {
+                Some(s) => return s,
+                None => {}
+            }
+        }
+        a.sum()
+    }
+
+    fn big(&self, a: &Mat2<f64>) -> bool {
+        let Shape { rows: n, cols: m } = a.get_dimension();
+        n * m >= self.threshold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matrix::Mat2;
+
+    // answers every gemm with zeros, so it's easy to tell when it was used, and turns down the
+    // rest
+    struct Zeros;
+
+    impl Backend for Zeros {
+        fn gemm(&self, a: &Mat2<f64>, b: &Mat2<f64>) -> Option<Mat2<f64>> {
+            Some(Mat2::zeros(a.get_dimension().rows, b.get_dimension().cols))
+        }
+    }
+
+    #[test]
+    fn test_offload() {
+        let a = Mat2::from_vec(~[~[1.0, 2.0], ~[3.0, 4.0]]).unwrap();
+        let small = Mat2::from_vec(~[~[1.0]]).unwrap();
+
+        // 2x2 times 2x2 is 8 multiply-adds, so it goes to the backend; 1x1 doesn't
+        let off = Offload::new(Zeros, 8);
+        assert_eq!(off.mul(&a, &a), Mat2::zeros(2, 2));
+        assert_eq!(off.mul(&small, &small), small);
+        // the backend turns down sums, so they fall back to the CPU
+        assert_eq!(off.sum(&a), 10.0);
+        assert_eq!(off.add(&a, &a), a.map(|x| *x * 2.0));
+
+        let cpu = Offload::new(CpuBackend, 0);
+        assert_eq!(cpu.mul(&a, &a), a * a);
+        assert_eq!(cpu.elem_mul(&a, &a), a.elem_mul(&a));
+    }
+}
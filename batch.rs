@@ -0,0 +1,38 @@
+//! Operations over whole batches of small fixed-size matrices, for when there are thousands of
+//! them (one per mesh element, per joint, per vertex...). Each call makes a single exactly-sized
+//! allocation for its results and otherwise works entirely with values on the stack.
+
+use fixed::Mat3x3;
+
+/// Multiply `a[i] * b[i]` for every `i`. Fails if the batches are different lengths.
+pub fn batch_mul<T: Add<T, T> + Mul<T, T>>(a: &[Mat3x3<T>], b: &[Mat3x3<T>]) -> ~[Mat3x3<T>] {
+    assert_eq!(a.len(), b.len());
+    a.iter().zip(b.iter()).map(|(x, y)| *x * *y).to_owned_vec()
+}
+
+/// The determinant of every matrix in the batch.
+pub fn batch_det<T: Num>(a: &[Mat3x3<T>]) -> ~[T] {
+    a.iter().map(|x| x.det()).to_owned_vec()
+}
+
+/// The inverse of every matrix in the batch, with `None` for the singular ones.
+pub fn batch_inverse<T: Num>(a: &[Mat3x3<T>]) -> ~[Option<Mat3x3<T>>] {
+    a.iter().map(|x| x.inverse()).to_owned_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fixed::Mat3x3;
+
+    #[test]
+    fn test_batch() {
+        let id = Mat3x3::new(1i, 0, 0, 0, 1, 0, 0, 0, 1);
+        let a = Mat3x3::new(2i, 0, 1, 1, 3, 0, 0, 1, 4);
+        let s = Mat3x3::new(1i, 2, 3, 2, 4, 6, 0, 0, 1);
+
+        assert_eq!(batch_mul(&[id, a, s], &[a, id, id]), ~[a, a, s]);
+        assert_eq!(batch_det(&[id, a, s]), ~[1, 25, 0]);
+        assert_eq!(batch_inverse(&[id, s]), ~[Some(id), None]);
+    }
+}
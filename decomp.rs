@@ -0,0 +1,345 @@
+//! Matrix factorizations: writing a matrix as a product of simpler ones, which can then be
+//! reused to solve lots of systems cheaply. The solvers that use them live in `system`.
+
+use std::num::{zero, one};
+use std::vec;
+use matrix::{Mat2, Shape};
+use permutation::Permutation;
+use system::{solve_lu, Workspace, Pivoting, PartialPivoting};
+
+/// An LU factorization `P A Qᵀ = L U`, where `P` and `Q` are permutations of the rows and
+/// columns, `L` is lower triangular with ones on the diagonal, and `U` is upper triangular. `Q`
+/// is the identity unless it was made with `FullPivoting` or `RookPivoting`. Made by `Mat2::lu`
+/// or `Mat2::lu_opts`, and used by `system::solve_lu`.
+#[deriving(Clone, Eq)]
+pub struct LU<T> {
+    // L below the diagonal (its unit diagonal isn't stored), U on and above it
+    priv lu: Mat2<T>,
+    priv perm: Permutation,
+    priv col_perm: Permutation,
+}
+
+impl<T: Num + Clone> LU<T> {
+    /// `L` and `U` packed into one matrix, the way LAPACK does it: `U` on and above the
+    /// diagonal, and `L` below it, leaving out its diagonal of ones.
+    pub fn packed<'a>(&'a self) -> &'a Mat2<T> {
+        &self.lu
+    }
+
+    /// The row permutation: row `i` of `P A` is row `perm()[i]` of `A`.
+    pub fn perm<'a>(&'a self) -> &'a [uint] {
+        self.perm.as_slice()
+    }
+
+    /// The row permutation `P`, for applying without writing it out as a matrix.
+    pub fn permutation<'a>(&'a self) -> &'a Permutation {
+        &self.perm
+    }
+
+    /// The column permutation: column `j` of `A Qᵀ` is column `col_perm()[j]` of `A`.
+    pub fn col_perm<'a>(&'a self) -> &'a [uint] {
+        self.col_perm.as_slice()
+    }
+
+    /// The column permutation `Q`, so `col_permutation().apply_cols(&a)` is `A Qᵀ`.
+    pub fn col_permutation<'a>(&'a self) -> &'a Permutation {
+        &self.col_perm
+    }
+
+    /// The unit lower triangular factor `L`.
+    pub fn l(&self) -> Mat2<T> {
+        let n = self.perm.len();
+        Mat2::new_with(n, n, |i, j| {
+            if i > j { self.lu.get(i, j).clone() } else if i == j { one() } else { zero() }
+        })
+    }
+
+    /// The upper triangular factor `U`.
+    pub fn u(&self) -> Mat2<T> {
+        let n = self.perm.len();
+        Mat2::new_with(n, n, |i, j| if i <= j { self.lu.get(i, j).clone() } else { zero() })
+    }
+
+    /// The permutation as a matrix `P`, with a one at `(i, perm()[i])` in each row `i`.
+    pub fn p(&self) -> Mat2<T> {
+        self.perm.to_mat()
+    }
+}
+
+impl<T: Num + Signed + Ord + Clone> Mat2<T> {
+    /// Factor the matrix as `P A = L U` by Gaussian elimination with partial pivoting. Singular
+    /// matrices still get a factorization, just with a zero somewhere on the diagonal of `U`.
+    /// Fails if the matrix isn't square.
+    pub fn lu(&self) -> LU<T> {
+        self.lu_opts(PartialPivoting, zero())
+    }
+
+    /// Factor the matrix as `P A Qᵀ = L U`, choosing pivots by `pivoting`. Pivots no bigger than
+    /// `tol` in absolute value count as zero: the rest of their column is zeroed out and skipped,
+    /// so `U` ends up with a zero on its diagonal and `solve_lu` reports the matrix singular.
+    /// With a `tol` of zero that only happens to exact zeros, which is what exact element types
+    /// want; with floats, something like `1e-12 * a.norm_one()` catches matrices that are only
+    /// singular up to rounding. Fails if the matrix isn't square.
+    pub fn lu_opts(&self, pivoting: Pivoting, tol: T) -> LU<T> {
+        let mut lu = LU {
+            lu: Mat2::new_with(0, 0, |_, _| zero()),
+            perm: Permutation::identity(0),
+            col_perm: Permutation::identity(0),
+        };
+        self.lu_into(pivoting, tol, &mut lu);
+        lu
+    }
+
+    /// `lu_opts`, but writing the factorization over `lu` and reusing its storage, which saves
+    /// the allocations when factoring lots of same-sized matrices one after another.
+    pub fn lu_into(&self, pivoting: Pivoting, tol: T, lu: &mut LU<T>) {
+        let Shape { rows: n, cols: m } = self.get_dimension();
+        assert!(m == n, "lu: matrix must be square");
+
+        let (a, perm, col_perm) = (&mut lu.lu, &mut lu.perm, &mut lu.col_perm);
+        a.refill(n, n, |i, j| self.at(i, j));
+        perm.reset(n);
+        col_perm.reset(n);
+        for j in range(0, n) {
+            let (p, pc) = pivoting.choose(j, j, n, n, |i, c| a.at(i, c));
+            // nothing (big enough) to eliminate in this column
+            if a.get(p, pc).abs() <= tol {
+                for i in range(j, n) {
+                    a.set(i, j, zero());
+                }
+                continue;
+            }
+            a.swap_rows(j, p);
+            perm.swap(j, p);
+            a.swap_cols(j, pc);
+            col_perm.swap(j, pc);
+
+            for i in range(j + 1, n) {
+                let f = *a.get(i, j) / *a.get(j, j);
+                for c in range(j + 1, n) {
+                    let v = *a.get(i, c) - f * *a.get(j, c);
+                    a.set(i, c, v);
+                }
+                a.set(i, j, f);
+            }
+        }
+    }
+}
+
+impl Mat2<f64> {
+    /// Factor an (n x m) matrix as `A = Q R` with Householder reflections, where `Q` is an
+    /// (n x n) orthogonal matrix and `R` is (n x m) and upper triangular. Each reflection zeroes
+    /// the part of a column below the diagonal in one go, which is much better behaved than
+    /// Gram-Schmidt when the columns are nearly dependent.
+    pub fn qr(&self) -> (Mat2<f64>, Mat2<f64>) {
+        let (mut q, mut r) = (Mat2::zeros(0, 0), Mat2::zeros(0, 0));
+        self.qr_into(&mut q, &mut r, &mut Workspace::new());
+        (q, r)
+    }
+
+    /// `qr`, but writing `Q` and `R` over `q` and `r` and reusing their storage, with the
+    /// Householder vectors in the scratch space of `ws`. That saves all the allocations when
+    /// factoring lots of same-sized matrices one after another.
+    pub fn qr_into(&self, q: &mut Mat2<f64>, r: &mut Mat2<f64>, ws: &mut Workspace<f64>) {
+        let Shape { rows: n, cols: m } = self.get_dimension();
+        r.refill(n, m, |i, j| *self.get(i, j));
+        q.refill(n, n, |i, j| if i == j { 1.0 } else { 0.0 });
+        let buf = ws.scratch(n, 0.0);
+
+        for k in range(0, m.min(&n)) {
+            // the reflection I - 2 v vᵀ maps x = r[k.., k] to (alpha, 0, ..., 0)
+            let v = buf.mut_slice(0, n - k);
+            for i in range(k, n) {
+                v[i - k] = *r.get(i, k);
+            }
+            let norm_x = v.iter().fold(0.0, |s, x| s + *x * *x).sqrt();
+            let alpha = if v[0] > 0.0 { -norm_x } else { norm_x };
+            v[0] -= alpha;
+            let norm_v = v.iter().fold(0.0, |s, x| s + *x * *x).sqrt();
+            if norm_v == 0.0 { continue; }
+            for x in v.mut_iter() { *x /= norm_v; }
+
+            for j in range(k, m) {
+                let d = range(k, n).fold(0.0, |s, i| s + v[i - k] * *r.get(i, j));
+                for i in range(k, n) {
+                    *r.get_mut(i, j) -= 2.0 * d * v[i - k];
+                }
+            }
+            for i in range(k + 1, n) {
+                r.set(i, k, 0.0);
+            }
+            for i in range(0, n) {
+                let d = range(k, n).fold(0.0, |s, j| s + *q.get(i, j) * v[j - k]);
+                for j in range(k, n) {
+                    *q.get_mut(i, j) -= 2.0 * d * v[j - k];
+                }
+            }
+        }
+    }
+
+    /// The 1-norm condition number `||A||_1 ||A⁻¹||_1`, with the inverse worked out one column at
+    /// a time from the LU factorization. It's infinite if the matrix is singular. This is cheaper
+    /// than `cond`, which needs the singular values, and usually within a small factor of it.
+    /// Fails if the matrix isn't square.
+    pub fn cond_one(&self) -> f64 {
+        let n = self.get_dimension().rows;
+        let lu = self.lu();
+        let mut inv_norm = 0.0f64;
+        for j in range(0, n) {
+            let e = vec::from_fn(n, |i| if i == j { 1.0 } else { 0.0 });
+            match solve_lu(&lu, e) {
+                Some(col) => inv_norm = inv_norm.max(&col.iter().fold(0.0, |s, x| s + x.abs())),
+                None => return 1.0 / 0.0,
+            }
+        }
+        self.norm_one() * inv_norm
+    }
+
+    /// Factor a symmetric positive definite matrix as `A = L Lᵀ`, and return the lower
+    /// triangular `L`. Returns `None` if the matrix isn't symmetric positive definite, including
+    /// if it isn't (exactly) symmetric. Fails if it isn't square.
+    pub fn cholesky(&self) -> Option<Mat2<f64>> {
+        let Shape { rows: n, cols: m } = self.get_dimension();
+        assert!(m == n, "cholesky: matrix must be square");
+        for i in range(0, n) {
+            for j in range(0, i) {
+                if *self.get(i, j) != *self.get(j, i) { return None; }
+            }
+        }
+
+        let mut l = Mat2::new_with(n, n, |_, _| 0.0);
+        for j in range(0, n) {
+            let d = range(0, j).fold(*self.get(j, j), |s, k| s - *l.get(j, k) * *l.get(j, k));
+            // catches NaN as well
+            if !(d > 0.0) { return None; }
+            let ljj = d.sqrt();
+            l.set(j, j, ljj);
+            for i in range(j + 1, n) {
+                let s = range(0, j).fold(*self.get(i, j), |s, k| s - *l.get(i, k) * *l.get(j, k));
+                l.set(i, j, s / ljj);
+            }
+        }
+        Some(l)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use matrix::Mat2;
+    use system::{Workspace, PartialPivoting, FullPivoting, RookPivoting};
+
+    #[test]
+    fn test_lu() {
+        let a = Mat2::from_vec(
+            ~[
+                ~[1.0, 2.0, 0.0],
+                ~[3.0, 1.0, 4.0],
+                ~[2.0, -2.0, 1.0]
+            ]).unwrap();
+        let lu = a.lu();
+        assert_eq!(lu.perm(), &[1, 2, 0]);
+        assert_mat_eq_eps!(lu.p() * a, lu.l() * lu.u(), 1e-12);
+        assert_mat_eq_eps!(lu.permutation().apply_rows(&a), lu.l() * lu.u(), 1e-12);
+
+        // singular matrices factor too
+        let s = Mat2::from_vec(~[~[1.0, 2.0], ~[2.0, 4.0]]).unwrap();
+        let lu = s.lu();
+        assert_eq!(*lu.u().get(1, 1), 0.0);
+        assert_mat_eq_eps!(lu.p() * s, lu.l() * lu.u(), 1e-12);
+    }
+
+    #[test]
+    fn test_lu_opts() {
+        let a = Mat2::from_vec(
+            ~[
+                ~[1.0, 2.0, 0.0],
+                ~[3.0, 1.0, 4.0],
+                ~[2.0, -2.0, 1.0]
+            ]).unwrap();
+        let lu = a.lu_opts(FullPivoting, 0.0);
+        // the 4 is the biggest entry, so it's the first pivot
+        assert_eq!(*lu.u().get(0, 0), 4.0);
+        assert_eq!(lu.col_perm()[0], 2);
+        let pa = lu.col_permutation().apply_cols(&lu.permutation().apply_rows(&a));
+        assert_mat_eq_eps!(pa, lu.l() * lu.u(), 1e-12);
+        assert_eq!(a.lu_opts(PartialPivoting, 0.0), a.lu());
+        // the 4 is also the biggest in both its row and column, so rook pivoting finds it
+        let lu = a.lu_opts(RookPivoting, 0.0);
+        assert_eq!(*lu.u().get(0, 0), 4.0);
+        let pa = lu.col_permutation().apply_cols(&lu.permutation().apply_rows(&a));
+        assert_mat_eq_eps!(pa, lu.l() * lu.u(), 1e-12);
+
+        // singular except for rounding, which a tolerance catches
+        let s = Mat2::from_vec(~[~[1.0, 2.0], ~[2.0, 4.0 + 1e-14]]).unwrap();
+        assert!(*s.lu().u().get(1, 1) != 0.0);
+        assert_eq!(*s.lu_opts(PartialPivoting, 1e-10).u().get(1, 1), 0.0);
+    }
+
+    #[test]
+    fn test_into() {
+        let a = Mat2::from_vec(~[~[2.0, 1.0, 1.0], ~[4.0, -6.0, 0.0], ~[-2.0, 7.0, 2.0]]);
+        let a = a.unwrap();
+        let b = Mat2::from_vec(~[~[1.0, 3.0], ~[2.0, 5.0]]).unwrap();
+
+        // reusing storage for smaller and bigger matrices gives what the allocating versions do
+        let mut lu = b.lu();
+        a.lu_into(PartialPivoting, 0.0, &mut lu);
+        assert_eq!(lu, a.lu());
+        b.lu_into(FullPivoting, 0.0, &mut lu);
+        assert_eq!(lu, b.lu_opts(FullPivoting, 0.0));
+
+        let mut ws = Workspace::new();
+        let (mut q, mut r) = (Mat2::zeros(0, 0), Mat2::zeros(0, 0));
+        for m in [&a, &b, &a].iter() {
+            m.qr_into(&mut q, &mut r, &mut ws);
+            assert_eq!((q.clone(), r.clone()), m.qr());
+        }
+    }
+
+    #[test]
+    fn test_cholesky() {
+        let a = Mat2::from_vec(
+            ~[
+                ~[4.0, 12.0, -16.0],
+                ~[12.0, 37.0, -43.0],
+                ~[-16.0, -43.0, 98.0]
+            ]).unwrap();
+        let l = a.cholesky().unwrap();
+        let e = Mat2::from_vec(~[~[2.0, 0.0, 0.0], ~[6.0, 1.0, 0.0], ~[-8.0, 5.0, 3.0]]).unwrap();
+        assert_mat_eq_eps!(l, e, 1e-12);
+
+        let indefinite = Mat2::from_vec(~[~[1.0, 2.0], ~[2.0, 1.0]]).unwrap();
+        assert!(indefinite.cholesky().is_none());
+        let unsymmetric = Mat2::from_vec(~[~[2.0, 1.0], ~[0.0, 2.0]]).unwrap();
+        assert!(unsymmetric.cholesky().is_none());
+    }
+
+    #[test]
+    fn test_cond_one() {
+        let a = Mat2::from_vec(~[~[4.0, 1.0], ~[2.0, 3.0]]).unwrap();
+        // A⁻¹ = [3 -1; -2 4] / 10
+        assert!((a.cond_one() - 6.0 * 0.5).abs() < 1e-12);
+        let s = Mat2::from_vec(~[~[1.0, 2.0], ~[2.0, 4.0]]).unwrap();
+        assert_eq!(s.cond_one(), 1.0 / 0.0);
+    }
+
+    #[test]
+    fn test_qr() {
+        let a = Mat2::from_vec(
+            ~[
+                ~[12.0, -51.0, 4.0],
+                ~[6.0, 167.0, -68.0],
+                ~[-4.0, 24.0, -41.0],
+                ~[1.0, 0.0, 2.0]
+            ]).unwrap();
+        let (q, r) = a.qr();
+        assert_mat_eq_eps!(q * r, a, 1e-10);
+        assert_mat_eq_eps!(q.transpose() * q,
+                           Mat2::new_with(4, 4, |i, j| if i == j { 1.0 } else { 0.0 }), 1e-12);
+        for i in range(0, 4) {
+            for j in range(0, i.min(&3)) {
+                assert_eq!(*r.get(i, j), 0.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,317 @@
+//! Differentiation matrices: finite-difference operators for things sampled on an evenly spaced
+//! grid, and spectral ones for Chebyshev and Fourier node sets.
+
+use std::vec;
+use std::f64::consts::PI;
+use matrix::{Mat2, Shape};
+use sparse::{SparseMat, SparseBuilder};
+
+/// Which stencil to use when approximating a first derivative.
+#[deriving(Eq, Clone)]
+pub enum Difference {
+    /// `(u[i+1] - u[i]) / h`
+    Forward,
+    /// `(u[i] - u[i-1]) / h`
+    Backward,
+    /// `(u[i+1] - u[i-1]) / 2h`
+    Central,
+}
+
+/// The (lo, hi, divisor) of the stencil used in row `i` of an `n` point operator. Rows where the
+/// requested stencil would fall off the grid use whichever one-sided stencil fits.
+fn stencil(n: uint, h: f64, kind: Difference, i: uint) -> (uint, uint, f64) {
+    match kind {
+        Forward if i + 1 < n => (i, i + 1, h),
+        Backward if i > 0 => (i - 1, i, h),
+        Central if i > 0 && i + 1 < n => (i - 1, i + 1, 2.0 * h),
+        _ if i == 0 => (0, 1, h),
+        _ => (i - 1, i, h),
+    }
+}
+
+fn entry(n: uint, h: f64, kind: Difference, i: uint, j: uint) -> f64 {
+    let (lo, hi, d) = stencil(n, h, kind, i);
+    if j == hi {
+        1.0 / d
+    } else if j == lo {
+        -1.0 / d
+    } else {
+        0.0
+    }
+}
+
+/// Build the (n x n) matrix approximating d/dx over `n` points spaced `h` apart. The boundary
+/// rows fall back to a one-sided stencil, so every row is a usable estimate. Fails if `n < 2`.
+pub fn diff_matrix(n: uint, h: f64, kind: Difference) -> Mat2<f64> {
+    assert!(n >= 2, "diff_matrix: need at least two points");
+    Mat2::new_with(n, n, |i, j| entry(n, h, kind, i, j))
+}
+
+/// Build the pair (d/dx, d/dy) of operators on an `nx` by `ny` grid, spaced `hx` and `hy` apart.
+/// They act on the field flattened in row-major order, i.e. the value at row `y`, column `x` of
+/// the grid lives at index `y * nx + x`. Fails if either side of the grid is shorter than 2.
+pub fn diff_matrix_2d(nx: uint, ny: uint, hx: f64, hy: f64,
+                      kind: Difference) -> (Mat2<f64>, Mat2<f64>) {
+    assert!(nx >= 2 && ny >= 2, "diff_matrix_2d: need at least two points along each axis");
+    let len = nx * ny;
+    let dx = Mat2::new_with(len, len, |p, q| {
+        if p / nx == q / nx { entry(nx, hx, kind, p % nx, q % nx) } else { 0.0 }
+    });
+    let dy = Mat2::new_with(len, len, |p, q| {
+        if p % nx == q % nx { entry(ny, hy, kind, p / nx, q / nx) } else { 0.0 }
+    });
+    (dx, dy)
+}
+
+fn sign(k: uint) -> f64 { if k % 2 == 0 { 1.0 } else { -1.0 } }
+
+/// Build the Chebyshev differentiation matrix on the `n + 1` Chebyshev points
+/// `x_j = cos(pi j / n)`, which run from 1 down to -1. Returns the matrix and the points. This is
+/// the construction from Trefethen's "Spectral Methods in MATLAB", with the diagonal taken as the
+/// negative sum of each row to keep rounding errors down.
+pub fn cheb_diff(n: uint) -> (Mat2<f64>, ~[f64]) {
+    if n == 0 {
+        return (Mat2::new_with(1, 1, |_,_| 0.0), ~[1.0]);
+    }
+
+    let x = vec::from_fn(n + 1, |j| (PI * j as f64 / n as f64).cos());
+    let c = vec::from_fn(n + 1, |i| sign(i) * if i == 0 || i == n { 2.0 } else { 1.0 });
+
+    let off = Mat2::new_with(n + 1, n + 1, |i, j| {
+        if i == j { 0.0 } else { c[i] / c[j] / (x[i] - x[j]) }
+    });
+    let d = Mat2::new_with(n + 1, n + 1, |i, j| {
+        if i == j {
+            -off.get_row(i).iter().fold(0.0, |a, b| a + *b)
+        } else {
+            *off.get(i, j)
+        }
+    });
+    (d, x)
+}
+
+/// Build the Fourier (periodic) differentiation matrix on the `n` equispaced points
+/// `x_j = 2 pi j / n` in [0, 2 pi). Returns the matrix and the points. Works for both even and odd
+/// `n`. Fails if `n` is zero.
+pub fn fourier_diff(n: uint) -> (Mat2<f64>, ~[f64]) {
+    assert!(n > 0, "fourier_diff: need at least one point");
+    let h = 2.0 * PI / n as f64;
+    let x = vec::from_fn(n, |j| h * j as f64);
+
+    let d = Mat2::new_with(n, n, |i, j| {
+        if i == j {
+            0.0
+        } else {
+            let k = if i > j { i - j } else { j - i };
+            let t = (x[i] - x[j]) / 2.0;
+            if n % 2 == 0 {
+                0.5 * sign(k) / t.tan()
+            } else {
+                0.5 * sign(k) / t.sin()
+            }
+        }
+    });
+    (d, x)
+}
+
+/// What lies beyond the edge of the grid for `laplacian_2d`.
+#[deriving(Eq, Clone)]
+pub enum Boundary {
+    /// The field is zero outside the grid.
+    Dirichlet,
+    /// The field has zero normal derivative at the edge (the matrix is singular).
+    Neumann,
+    /// The grid wraps around in both directions (the matrix is singular).
+    Periodic,
+}
+
+fn wrap(v: int, n: uint) -> uint {
+    ((v + n as int) % n as int) as uint
+}
+
+/// Build the 5-point stencil matrix for -∇² on an `nx` by `ny` grid with unit spacing (divide by
+/// h² for a spacing of h). It acts on the field flattened in row-major order, like
+/// `diff_matrix_2d`. With Dirichlet boundaries it is symmetric positive definite, which makes it
+/// the standard test problem for the solvers. Fails if either side of the grid is empty.
+pub fn laplacian_2d(nx: uint, ny: uint, boundary: Boundary) -> Mat2<f64> {
+    laplacian_2d_sparse(nx, ny, boundary).to_mat()
+}
+
+/// `laplacian_2d`, but sparse: there are at most five entries per row, so this is the one to use
+/// once the grid gets big. Fails if either side of the grid is empty.
+pub fn laplacian_2d_sparse(nx: uint, ny: uint, boundary: Boundary) -> SparseMat<f64> {
+    assert!(nx > 0 && ny > 0, "laplacian_2d: empty grid");
+    let len = nx * ny;
+    let mut b = SparseBuilder::new(len, len);
+
+    for y in range(0, ny) {
+        for x in range(0, nx) {
+            let p = y * nx + x;
+            let (x, y) = (x as int, y as int);
+            for &(qx, qy) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)].iter() {
+                let inside = qx >= 0 && qy >= 0 && qx < nx as int && qy < ny as int;
+                let q = wrap(qy, ny) * nx + wrap(qx, nx);
+                match boundary {
+                    Periodic => {
+                        b.push(p, p, 1.0);
+                        b.push(p, q, -1.0);
+                    }
+                    Dirichlet => {
+                        b.push(p, p, 1.0);
+                        if inside { b.push(p, q, -1.0); }
+                    }
+                    Neumann => {
+                        if inside {
+                            b.push(p, p, 1.0);
+                            b.push(p, q, -1.0);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    b.build()
+}
+
+impl Mat2<f64> {
+    /// Estimate the gradient of a field sampled on a grid, returning (d/dx, d/dy) where x runs
+    /// along the columns and y down the rows. Interior points use central differences and the
+    /// edges use one-sided ones. `spacing` is (hx, hy) and defaults to 1 in both directions. An
+    /// axis with only one sample has a derivative of zero along it.
+    pub fn gradient(&self, spacing: Option<(f64, f64)>) -> (Mat2<f64>, Mat2<f64>) {
+        let Shape { rows: n, cols: m } = self.get_dimension();
+        let (hx, hy) = spacing.unwrap_or((1.0, 1.0));
+
+        let gx = Mat2::new_with(n, m, |i, j| {
+            if m < 2 {
+                0.0
+            } else {
+                let (lo, hi, d) = stencil(m, hx, Central, j);
+                (*self.get(i, hi) - *self.get(i, lo)) / d
+            }
+        });
+        let gy = Mat2::new_with(n, m, |i, j| {
+            if n < 2 {
+                0.0
+            } else {
+                let (lo, hi, d) = stencil(n, hy, Central, i);
+                (*self.get(hi, j) - *self.get(lo, j)) / d
+            }
+        });
+        (gx, gy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matrix::Mat2;
+    use system::substitute;
+
+    fn close(a: f64, b: f64) -> bool { (a - b).abs() < 1e-9 }
+
+    #[test]
+    fn test_diff_matrix() {
+        // derivative of 3x + 1 sampled at x = 0, 0.5, ..., 2
+        let xs = &[1.0, 2.5, 4.0, 5.5, 7.0];
+        for &kind in [Forward, Backward, Central].iter() {
+            let d = diff_matrix(5, 0.5, kind);
+            let r = substitute(&d, xs);
+            assert!(r.column_iter(0).all(|v| close(*v, 3.0)));
+        }
+
+        let d = diff_matrix(3, 1.0, Central);
+        assert!(d.get_row(0) == &[-1.0, 1.0, 0.0]);
+        assert!(d.get_row(1) == &[-0.5, 0.0, 0.5]);
+        assert!(d.get_row(2) == &[0.0, -1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_diff_matrix_2d() {
+        // f(x, y) = 2x + 3y on a 3 (wide) by 2 (tall) grid
+        let f = &[0.0, 2.0, 4.0, 3.0, 5.0, 7.0];
+        let (dx, dy) = diff_matrix_2d(3, 2, 1.0, 1.0, Central);
+        assert!(substitute(&dx, f).column_iter(0).all(|v| close(*v, 2.0)));
+        assert!(substitute(&dy, f).column_iter(0).all(|v| close(*v, 3.0)));
+    }
+
+    #[test]
+    fn test_gradient() {
+        // f(x, y) = x^2 + 5y, sampled with hx = 0.5 and hy = 2
+        let f = Mat2::new_with(3, 4, |i, j| {
+            let (x, y) = (j as f64 * 0.5, i as f64 * 2.0);
+            x * x + 5.0 * y
+        });
+        let (gx, gy) = f.gradient(Some((0.5, 2.0)));
+        assert!(gy.row_iter().all(|r| r.iter().all(|v| close(*v, 5.0))));
+        // central differences are exact for quadratics, the edges are one-sided
+        assert!(gx.get_row(1) == &[0.5, 1.0, 2.0, 2.5]);
+
+        let line = Mat2::from_vec(~[~[1.0, 2.0, 4.0]]).unwrap();
+        let (gx, gy) = line.gradient(None);
+        assert!(gx.get_row(0) == &[1.0, 1.5, 2.0]);
+        assert!(gy.get_row(0) == &[0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_cheb_diff() {
+        let (d, x) = cheb_diff(1);
+        assert_eq!(x, ~[1.0, -1.0]);
+        assert!(d.get_row(0) == &[0.5, -0.5]);
+        assert!(d.get_row(1) == &[0.5, -0.5]);
+
+        // exact for polynomials of degree <= n
+        let (d, x) = cheb_diff(5);
+        let f = x.iter().map(|x| *x * *x * *x).to_owned_vec();
+        let r = substitute(&d, f);
+        for (v, x) in r.column_iter(0).zip(x.iter()) {
+            assert!(close(*v, 3.0 * *x * *x));
+        }
+    }
+
+    #[test]
+    fn test_fourier_diff() {
+        for &n in [16u, 15].iter() {
+            let (d, x) = fourier_diff(n);
+            let f = x.iter().map(|x| x.sin()).to_owned_vec();
+            let r = substitute(&d, f);
+            for (v, x) in r.column_iter(0).zip(x.iter()) {
+                assert!(close(*v, x.cos()));
+            }
+        }
+    }
+
+    #[test]
+    fn test_laplacian_2d() {
+        let l = laplacian_2d(2, 2, Dirichlet);
+        let e = Mat2::from_vec(
+            ~[
+                ~[4.0, -1.0, -1.0, 0.0],
+                ~[-1.0, 4.0, 0.0, -1.0],
+                ~[-1.0, 0.0, 4.0, -1.0],
+                ~[0.0, -1.0, -1.0, 4.0]
+            ]).unwrap();
+        assert_eq!(l, e);
+
+        for &b in [Neumann, Periodic].iter() {
+            let l = laplacian_2d(3, 4, b);
+            // constants are in the null space
+            assert!(l.row_iter().all(|r| r.iter().fold(0.0, |a, b| a + *b) == 0.0));
+            for i in range(0u, 12) {
+                for j in range(0u, 12) {
+                    assert_eq!(l.get(i, j), l.get(j, i));
+                }
+            }
+        }
+        assert!(laplacian_2d(3, 3, Periodic).get(4, 4) == &4.0);
+        assert!(laplacian_2d(3, 3, Neumann).get(0, 0) == &2.0);
+
+        let s = laplacian_2d_sparse(3, 4, Dirichlet);
+        // 12 diagonal entries and both halves of the 17 neighbouring pairs
+        assert_eq!(s.nnz(), 46);
+        assert_eq!(s.get(0, 0), Some(&4.0));
+        assert_eq!(s.get(0, 1), Some(&-1.0));
+        assert_eq!(s.get(0, 4), None);
+    }
+}
@@ -0,0 +1,682 @@
+//! Eigenvalues and eigenvectors, and the bits and pieces that go with computing them.
+
+use std::vec;
+use std::cmp::{Less, Equal, Greater};
+use std::num::{zero, one};
+use extra::complex::{Cmplx, Complex64};
+use matrix::{Mat2, Shape};
+use operator::LinearOperator;
+use system::{solve, Workspace};
+use vector::{dot, norm};
+
+/// How fast the iterates of power iteration grow, on average: the geometric mean of `||A v||`
+/// over the second half of `iters` iterations, after the first half has let the dominant part
+/// of the spectrum take over. This tends to `ρ(A)` even when there are several eigenvalues of
+/// that size, just slowly.
+fn growth_rate(a: &Mat2<f64>, iters: uint) -> f64 {
+    let n = a.get_dimension().rows;
+    let mut v = vec::from_fn(n, |i| 1.0 + i as f64 / n as f64);
+    let mut log_sum = 0.0;
+    for k in range(0, iters) {
+        let w = a.apply(v);
+        let nw = norm(w);
+        if nw == 0.0 { return 0.0; }
+        if k >= iters / 2 { log_sum += nw.ln(); }
+        v = w.map(|x| *x / nw);
+    }
+    (log_sum / (iters - iters / 2) as f64).exp()
+}
+
+// machine epsilon for f64
+static EPS: f64 = 2.220446049250313e-16;
+
+/// Reduce a square matrix (as a list of rows) to upper Hessenberg form in place, with Householder
+/// reflections applied from both sides, so the eigenvalues don't change.
+fn hessenberg(a: &mut [~[f64]]) {
+    let n = a.len();
+    for k in range(0, n.max(&2) - 2) {
+        // the reflection I - 2 v vᵀ zeroes column k below the subdiagonal
+        let mut v = range(k + 1, n).map(|i| a[i][k]).to_owned_vec();
+        let nx = norm(v);
+        let alpha = if v[0] > 0.0 { -nx } else { nx };
+        v[0] -= alpha;
+        let nv = norm(v);
+        if nv == 0.0 { continue; }
+        for x in v.mut_iter() { *x /= nv; }
+
+        for j in range(k, n) {
+            let d = range(k + 1, n).fold(0.0, |s, i| s + v[i - k - 1] * a[i][j]);
+            for i in range(k + 1, n) {
+                a[i][j] -= 2.0 * d * v[i - k - 1];
+            }
+        }
+        for i in range(0, n) {
+            let d = range(k + 1, n).fold(0.0, |s, j| s + a[i][j] * v[j - k - 1]);
+            for j in range(k + 1, n) {
+                a[i][j] -= 2.0 * d * v[j - k - 1];
+            }
+        }
+    }
+}
+
+/// `|a|` with the sign of `b`.
+fn sign(a: f64, b: f64) -> f64 {
+    if b >= 0.0 { a.abs() } else { -a.abs() }
+}
+
+/// All the eigenvalues of an upper Hessenberg matrix, by the Francis double shift QR algorithm,
+/// which keeps to real arithmetic by taking the shifts as a complex conjugate pair. This is
+/// `hqr` from Numerical Recipes. The entry for each eigenvalue is at the position on the
+/// diagonal where it turned up. Returns `None` if some eigenvalue takes more than 30 iterations,
+/// which about never happens. Destroys `a`.
+fn hqr(a: &mut [~[f64]]) -> Option<~[Complex64]> {
+    let n = a.len();
+    let mut wri = vec::from_elem(n, Cmplx::new(0.0, 0.0));
+    let mut anorm = 0.0;
+    for i in range(0, n) {
+        for j in range(if i > 0 { i - 1 } else { 0 }, n) {
+            anorm += a[i][j].abs();
+        }
+    }
+
+    // the eigenvalues at the bottom of the active block, above row `top`, come off one or two at
+    // a time. `t` accumulates the exceptional shifts.
+    let mut top = n;
+    let mut t = 0.0;
+    while top > 0 {
+        let nn = top - 1;
+        let mut its = 0;
+        loop {
+            // look for a negligible subdiagonal entry, splitting off the block l..nn
+            let mut l = nn;
+            while l > 0 {
+                let mut s = a[l - 1][l - 1].abs() + a[l][l].abs();
+                if s == 0.0 { s = anorm; }
+                if a[l][l - 1].abs() <= EPS * s {
+                    a[l][l - 1] = 0.0;
+                    break;
+                }
+                l -= 1;
+            }
+
+            let mut x = a[nn][nn];
+            if l == nn {
+                // one root
+                wri[nn] = Cmplx::new(x + t, 0.0);
+                top -= 1;
+                break;
+            }
+            let mut y = a[nn - 1][nn - 1];
+            let mut w = a[nn][nn - 1] * a[nn - 1][nn];
+            if l == nn - 1 {
+                // two roots, from the trailing 2x2 block
+                let p = 0.5 * (y - x);
+                let q = p * p + w;
+                let z = q.abs().sqrt();
+                x += t;
+                if q >= 0.0 {
+                    let z = p + sign(z, p);
+                    wri[nn - 1] = Cmplx::new(x + z, 0.0);
+                    wri[nn] = Cmplx::new(if z != 0.0 { x - w / z } else { x + z }, 0.0);
+                } else {
+                    wri[nn - 1] = Cmplx::new(x + p, z);
+                    wri[nn] = Cmplx::new(x + p, -z);
+                }
+                top -= 2;
+                break;
+            }
+
+            if its == 30 { return None; }
+            if its == 10 || its == 20 {
+                // exceptional shift, to get out of a cycle
+                t += x;
+                for i in range(0, nn + 1) {
+                    a[i][i] -= x;
+                }
+                let s = a[nn][nn - 1].abs() + a[nn - 1][nn - 2].abs();
+                x = 0.75 * s;
+                y = x;
+                w = -0.4375 * s * s;
+            }
+            its += 1;
+
+            // look for two consecutive small subdiagonal entries, and form the first column of
+            // the shifted matrix there
+            let (mut p, mut q, mut r) = (0.0, 0.0, 0.0);
+            let mut m = nn - 2;
+            loop {
+                let z = a[m][m];
+                r = x - z;
+                let s = y - z;
+                p = (r * s - w) / a[m + 1][m] + a[m][m + 1];
+                q = a[m + 1][m + 1] - z - r - s;
+                r = a[m + 2][m + 1];
+                let s = p.abs() + q.abs() + r.abs();
+                p /= s;
+                q /= s;
+                r /= s;
+                if m == l { break; }
+                let u = a[m][m - 1].abs() * (q.abs() + r.abs());
+                let v = p.abs() * (a[m - 1][m - 1].abs() + z.abs() + a[m + 1][m + 1].abs());
+                if u <= EPS * v { break; }
+                m -= 1;
+            }
+            for i in range(m, nn - 1) {
+                a[i + 2][i] = 0.0;
+                if i != m { a[i + 2][i - 1] = 0.0; }
+            }
+
+            // chase the bulge down the matrix
+            for k in range(m, nn) {
+                if k != m {
+                    p = a[k][k - 1];
+                    q = a[k + 1][k - 1];
+                    r = if k + 1 != nn { a[k + 2][k - 1] } else { 0.0 };
+                    x = p.abs() + q.abs() + r.abs();
+                    if x != 0.0 {
+                        p /= x;
+                        q /= x;
+                        r /= x;
+                    }
+                }
+                let s = sign((p * p + q * q + r * r).sqrt(), p);
+                if s == 0.0 { continue; }
+                if k == m {
+                    if l != m { a[k][k - 1] = -a[k][k - 1]; }
+                } else {
+                    a[k][k - 1] = -s * x;
+                }
+                p += s;
+                x = p / s;
+                y = q / s;
+                let z = r / s;
+                q /= p;
+                r /= p;
+                for j in range(k, nn + 1) {
+                    let mut p = a[k][j] + q * a[k + 1][j];
+                    if k + 1 != nn {
+                        p += r * a[k + 2][j];
+                        a[k + 2][j] -= p * z;
+                    }
+                    a[k + 1][j] -= p * y;
+                    a[k][j] -= p * x;
+                }
+                for i in range(l, nn.min(&(k + 3)) + 1) {
+                    let mut p = x * a[i][k] + y * a[i][k + 1];
+                    if k + 1 != nn {
+                        p += z * a[i][k + 2];
+                        a[i][k + 2] -= p * r;
+                    }
+                    a[i][k + 1] -= p * q;
+                    a[i][k] -= p;
+                }
+            }
+        }
+    }
+    Some(wri)
+}
+
+impl<T: Num + Clone> Mat2<T> {
+    /// The coefficients of the characteristic polynomial `det(λI - A)`, lowest degree first, so
+    /// `c[i]` goes with `λⁱ` and `c[n]` is one. Uses the Faddeev-LeVerrier recursion, which only
+    /// multiplies matrices and divides by `1, 2, ..., n`, so for an integer matrix it's exact.
+    /// That makes it good for small matrices and exact types, but for floats the roots of the
+    /// polynomial are far more sensitive than the eigenvalues themselves, so use `eigenvalues`
+    /// for those. Fails if the matrix isn't square.
+    pub fn char_poly(&self) -> ~[T] {
+        let Shape { rows: n, cols: m } = self.get_dimension();
+        assert!(m == n, "char_poly: matrix must be square");
+
+        // M_k = A M_{k-1} + c_{n-k+1} I, and c_{n-k} = -tr(A M_k) / k
+        let mut c = vec::from_fn(n + 1, |_| zero::<T>());
+        c[n] = one();
+        let mut am = Mat2::new_with(n, n, |_, _| zero::<T>());
+        let mut k = zero::<T>();
+        for i in range(1, n + 1) {
+            let mut mk = am;
+            for j in range(0, n) {
+                let d = *mk.get(j, j) + c[n - i + 1];
+                mk.set(j, j, d);
+            }
+            am = *self * mk;
+            k = k + one();
+            c[n - i] = (zero::<T>() - am.trace()) / k;
+        }
+        c
+    }
+}
+
+// Parlett and Reinsch's balancing algorithm on a square matrix given as a list of rows: keep
+// scaling row i down and column i up (or the other way around) by powers of two until their
+// off-diagonal norms are within a factor of 2. The scale factors are multiplied into `d`.
+fn balance_rows(b: &mut [~[f64]], mut d: Option<&mut [f64]>) {
+    let n = b.len();
+    let mut done = false;
+    while !done {
+        done = true;
+        for i in range(0, n) {
+            let (mut c, mut r) = (0.0f64, 0.0f64);
+            for j in range(0, n) {
+                if j != i {
+                    c += b[j][i].abs();
+                    r += b[i][j].abs();
+                }
+            }
+            if c == 0.0 || r == 0.0 { continue; }
+
+            let s = c + r;
+            let mut f = 1.0;
+            while c < r / 2.0 {
+                f *= 2.0;
+                c *= 4.0;
+            }
+            while c >= r * 2.0 {
+                f /= 2.0;
+                c /= 4.0;
+            }
+
+            if (c + r) / f < 0.95 * s {
+                done = false;
+                match d {
+                    Some(ref mut d) => d[i] *= f,
+                    None => {}
+                }
+                for j in range(0, n) {
+                    b[i][j] /= f;
+                    b[j][i] *= f;
+                }
+            }
+        }
+    }
+}
+
+impl Mat2<f64> {
+    /// Balance the matrix: find a diagonal `D` such that `B = D⁻¹ A D` has rows and columns of
+    /// roughly equal size, and return `(B, d)`, where `d` is the diagonal of `D`. `B` has the
+    /// same eigenvalues as `A`, but they can be computed much more accurately when `A` is badly
+    /// scaled. The entries of `d` are powers of two, so no rounding error is introduced. Use
+    /// `unbalance` to turn eigenvectors of `B` back into eigenvectors of `A`. Fails if the matrix
+    /// isn't square.
+    pub fn balance(&self) -> (Mat2<f64>, ~[f64]) {
+        let Shape { rows: n, cols: m } = self.get_dimension();
+        assert!(m == n, "balance: matrix must be square");
+
+        let mut b = vec::from_fn(n, |i| self.get_row(i).to_owned());
+        let mut d = vec::from_elem(n, 1.0f64);
+        balance_rows(b.as_mut_slice(), Some(d.as_mut_slice()));
+        (Mat2::new_with(n, n, |i, j| b[i][j]), d)
+    }
+}
+
+impl Mat2<f64> {
+    /// All the eigenvalues of the matrix, counted with multiplicity. The matrix is balanced and
+    /// reduced to Hessenberg form, then the shifted QR algorithm finds the eigenvalues, keeping
+    /// to real arithmetic throughout. Complex eigenvalues come in conjugate pairs. They're sorted
+    /// by real part, biggest first, with ties going to the bigger imaginary part. Returns `None`
+    /// in the unlikely event that the QR iterations don't converge. Fails if the matrix isn't
+    /// square.
+    pub fn eigenvalues(&self) -> Option<~[Complex64]> {
+        self.eigenvalues_ws(&mut Workspace::new())
+    }
+
+    /// `eigenvalues`, doing the balancing, Hessenberg reduction and QR iterations in the scratch
+    /// space of `ws`, so only the result is allocated.
+    pub fn eigenvalues_ws(&self, ws: &mut Workspace<f64>) -> Option<~[Complex64]> {
+        let Shape { rows: n, cols: m } = self.get_dimension();
+        assert!(m == n, "eigenvalues: matrix must be square");
+
+        let a = ws.load_rows(self);
+        balance_rows(a, None);
+        hessenberg(a);
+        let mut vals = match hqr(a) {
+            Some(vals) => vals,
+            None => return None,
+        };
+        vals.sort_by(|x, y| {
+            let (a, b) = if x.re != y.re { (y.re, x.re) } else { (y.im, x.im) };
+            if a < b { Less } else if a > b { Greater } else { Equal }
+        });
+        Some(vals)
+    }
+
+    /// The eigenvalues, as from `eigenvalues`, each with a unit eigenvector if it's real (and
+    /// `None` if it isn't). The eigenvectors come from a few steps of inverse iteration with a
+    /// slightly perturbed shift, which gets each one to about working precision, but a repeated
+    /// eigenvalue gets the same eigenvector each time rather than a basis for its eigenspace.
+    pub fn eigen(&self) -> Option<(~[Complex64], ~[Option<~[f64]>])> {
+        let n = self.get_dimension().rows;
+        let vals = match self.eigenvalues() {
+            Some(vals) => vals,
+            None => return None,
+        };
+        let mut scale = 1.0f64;
+        for i in range(0, n) {
+            for j in range(0, n) {
+                scale = scale.max(&self.get(i, j).abs());
+            }
+        }
+
+        let vecs = vals.iter().map(|lambda| {
+            if lambda.im != 0.0 { return None; }
+            // exactly at the eigenvalue `A - μ I` could be exactly singular
+            let mu = lambda.re + 1e-10 * scale;
+            let shifted = Mat2::new_with(n, n, |i, j| {
+                if i == j { *self.get(i, j) - mu } else { *self.get(i, j) }
+            });
+            let mut v = vec::from_fn(n, |i| 1.0 + i as f64 / n as f64);
+            for _ in range(0, 3) {
+                v = match solve(&shifted, v).unique() {
+                    Some(w) => w,
+                    None => return None,
+                };
+                let nv = norm(v);
+                for x in v.mut_iter() { *x /= nv; }
+            }
+            // pick the sign that makes the biggest entry positive
+            let big = v.iter().fold(0.0f64, |b, x| if x.abs() > b.abs() { *x } else { b });
+            if big < 0.0 {
+                for x in v.mut_iter() { *x = -*x; }
+            }
+            Some(v)
+        }).to_owned_vec();
+
+        Some((vals, vecs))
+    }
+}
+
+impl Mat2<f64> {
+    /// Find the eigenvalue that's largest in absolute value, along with a unit eigenvector for it,
+    /// by power iteration. This only needs matrix-vector products, so it's the thing to use on big
+    /// matrices when only the dominant eigenpair matters, like the stationary vector of a Markov
+    /// chain in PageRank. Stops once `||A v - λ v|| <= tol * ||A v||`, or returns `None` if that
+    /// hasn't happened after `max_iters` iterations, which is what happens when there's no single
+    /// dominant eigenvalue. Fails if the matrix isn't square.
+    pub fn power_iteration(&self, tol: f64, max_iters: uint) -> Option<(f64, ~[f64])> {
+        let Shape { rows: n, cols: m } = self.get_dimension();
+        assert!(m == n, "power_iteration: matrix must be square");
+        // not all ones, which is orthogonal to the dominant eigenvector of some nice matrices
+        let mut v = vec::from_fn(n, |i| 1.0 + i as f64 / n as f64);
+        let nv = norm(v);
+        for x in v.mut_iter() { *x /= nv; }
+
+        for _ in range(0, max_iters) {
+            let w = self.apply(v);
+            let lambda = dot(v, w);
+            let nw = norm(w);
+            let res = norm(w.iter().zip(v.iter()).map(|(w, v)| *w - lambda * *v).to_owned_vec());
+            if res <= tol * nw {
+                return Some((lambda, v));
+            }
+            v = w.map(|x| *x / nw);
+        }
+        None
+    }
+
+    /// Hotelling deflation: given an eigenpair `(lambda, v)` of a symmetric matrix `A`, return
+    /// `A - lambda v vᵀ / (vᵀ v)`, which has the same eigenvectors, but with `lambda` swapped for
+    /// 0. The next power iteration then finds the next biggest eigenvalue. Don't use this on
+    /// nonsymmetric matrices, where it mixes up the other eigenvalues. Fails if `v` is the wrong
+    /// length.
+    pub fn deflate(&self, lambda: f64, v: &[f64]) -> Mat2<f64> {
+        let mut a = self.clone();
+        a.rank1_update(-lambda / dot(v, v), v, v);
+        a
+    }
+
+    /// Estimate the spectral radius `ρ(A)`, the biggest absolute value of any eigenvalue. This
+    /// is power iteration, stopping once the eigenvector residual is below `tol`. If there's no
+    /// single dominant eigenvalue for it to converge to (e.g. the biggest ones are a complex
+    /// pair, or `λ` and `-λ`), it falls back on a rougher estimate from the average growth of the
+    /// iterates. Fails if the matrix isn't square.
+    pub fn spectral_radius(&self, tol: f64) -> f64 {
+        let Shape { rows: n, cols: m } = self.get_dimension();
+        assert!(m == n, "spectral_radius: matrix must be square");
+        if n == 0 { return 0.0; }
+
+        match self.power_iteration(tol, 1000) {
+            Some((lambda, _)) => lambda.abs(),
+            None => growth_rate(self, 2000),
+        }
+    }
+
+    /// Find the `k` eigenvalues of a symmetric matrix that are largest in absolute value, each
+    /// with a unit eigenvector, by alternately running power iteration and deflating away what it
+    /// found. Each power iteration stops once `||A v - λ v|| <= tol * ||A v||`, and gives up
+    /// after `max_iter` iterations, in which case fewer than `k` pairs come back.
+    pub fn peel_eigenpairs(&self, k: uint, tol: f64, max_iter: uint) -> ~[(f64, ~[f64])] {
+        let mut a = self.clone();
+        let mut pairs = ~[];
+        while pairs.len() < k {
+            match a.power_iteration(tol, max_iter) {
+                Some((lambda, v)) => {
+                    a = a.deflate(lambda, v);
+                    pairs.push((lambda, v));
+                }
+                None => break
+            }
+        }
+        pairs
+    }
+}
+
+impl Mat2<f64> {
+    /// All the eigenvalues of a symmetric matrix, biggest first, along with an orthogonal matrix
+    /// whose columns are the corresponding unit eigenvectors, by the cyclic Jacobi method. Each
+    /// rotation zeroes one off-diagonal pair, and sweeping over all of them again and again
+    /// drives the matrix to diagonal. It's slower than QR iterations on big matrices, but simple
+    /// and very accurate, even for tiny eigenvalues. Only the upper triangle is read, so the
+    /// matrix is taken to be symmetric. Fails if it isn't square.
+    pub fn jacobi_eigen(&self) -> (~[f64], Mat2<f64>) {
+        let Shape { rows: n, cols: m } = self.get_dimension();
+        assert!(m == n, "jacobi_eigen: matrix must be square");
+
+        let mut a = Mat2::new_with(n, n, |i, j| *self.get(i.min(&j), i.max(&j)));
+        let mut v = Mat2::identity(n);
+        let total = range(0, n).fold(0.0, |s, i| {
+            range(0, n).fold(s, |s, j| s + *a.get(i, j) * *a.get(i, j))
+        });
+
+        for _ in range(0, 100) {
+            let mut off = 0.0;
+            for p in range(0, n) {
+                for q in range(p + 1, n) {
+                    off += *a.get(p, q) * *a.get(p, q);
+                }
+            }
+            if off <= EPS * EPS * total { break; }
+
+            for p in range(0, n) {
+                for q in range(p + 1, n) {
+                    let apq = *a.get(p, q);
+                    if apq == 0.0 { continue; }
+                    // the rotation [c s; -s c] in the (p, q) plane that zeroes a[p, q]
+                    let theta = (*a.get(q, q) - *a.get(p, p)) / (2.0 * apq);
+                    let t = sign(1.0, theta) / (theta.abs() + (theta * theta + 1.0).sqrt());
+                    let c = 1.0 / (t * t + 1.0).sqrt();
+                    let s = t * c;
+                    for k in range(0, n) {
+                        let (akp, akq) = (*a.get(k, p), *a.get(k, q));
+                        a.set(k, p, c * akp - s * akq);
+                        a.set(k, q, s * akp + c * akq);
+                        let (vkp, vkq) = (*v.get(k, p), *v.get(k, q));
+                        v.set(k, p, c * vkp - s * vkq);
+                        v.set(k, q, s * vkp + c * vkq);
+                    }
+                    for k in range(0, n) {
+                        let (apk, aqk) = (*a.get(p, k), *a.get(q, k));
+                        a.set(p, k, c * apk - s * aqk);
+                        a.set(q, k, s * apk + c * aqk);
+                    }
+                }
+            }
+        }
+
+        let mut order = range(0, n).to_owned_vec();
+        order.sort_by(|&i, &j| {
+            let (x, y) = (*a.get(i, i), *a.get(j, j));
+            if x > y { Less } else if x < y { Greater } else { Equal }
+        });
+        let vals = order.map(|&i| *a.get(i, i));
+        let vecs = Mat2::new_with(n, n, |i, j| *v.get(i, order[j]));
+        (vals, vecs)
+    }
+}
+
+/// Undo `balance` on eigenvectors: if the columns of `vecs` are eigenvectors of the balanced
+/// matrix, and `d` is the scaling `balance` returned along with it, this turns them into
+/// eigenvectors of the original matrix. Fails if `d` doesn't have one entry per row of `vecs`.
+pub fn unbalance(vecs: &mut Mat2<f64>, d: &[f64]) {
+    vecs.scale_rows_by(d);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matrix::Mat2;
+    use operator::LinearOperator;
+    use system::Workspace;
+
+    #[test]
+    fn test_peel_eigenpairs() {
+        let a = Mat2::from_vec(~[~[4.0, 1.0, 0.0], ~[1.0, 3.0, 0.0], ~[0.0, 0.0, 1.0]]).unwrap();
+        let pairs = a.peel_eigenpairs(3, 1e-12, 1000);
+        assert_eq!(pairs.len(), 3);
+        let expected = [3.5 + 1.25f64.sqrt(), 3.5 - 1.25f64.sqrt(), 1.0];
+        for (&(lambda, ref v), e) in pairs.iter().zip(expected.iter()) {
+            assert!((lambda - *e).abs() < 1e-9);
+            let av = a.apply(v.as_slice());
+            assert_vec_eq_eps!(av, v.map(|x| lambda * *x), 1e-9);
+        }
+
+        // deflating the dominant eigenpair zeroes its eigenvalue
+        let (lambda, ref v) = pairs[0];
+        let dv = a.deflate(lambda, v.as_slice()).apply(v.as_slice());
+        assert_vec_eq_eps!(dv, ~[0.0, 0.0, 0.0], 1e-9);
+    }
+
+    #[test]
+    fn test_char_poly() {
+        let a = Mat2::from_vec(~[~[2i, 1], ~[1, 3]]).unwrap();
+        assert_eq!(a.char_poly(), ~[5, -5, 1]);
+        let b = Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6], ~[7, 8, 10]]).unwrap();
+        // the constant term is (-1)ⁿ det(A)
+        assert_eq!(b.char_poly(), ~[3, -12, -16, 1]);
+        let t = Mat2::from_vec(~[~[2.0, -1.0, 0.0], ~[-1.0, 2.0, -1.0], ~[0.0, -1.0, 2.0]]);
+        assert_eq!(t.unwrap().char_poly(), ~[-4.0, 10.0, -6.0, 1.0]);
+    }
+
+    #[test]
+    fn test_power_iteration() {
+        // a column stochastic link matrix: the dominant eigenvalue is 1, and its eigenvector is
+        // the stationary distribution
+        let a = Mat2::from_vec(~[~[0.0, 0.5, 0.5], ~[0.5, 0.0, 0.5], ~[0.5, 0.5, 0.0]]).unwrap();
+        let (lambda, v) = a.power_iteration(1e-12, 1000).unwrap();
+        assert!((lambda - 1.0).abs() < 1e-9);
+        let s = 1.0 / 3.0f64.sqrt();
+        assert_vec_eq_eps!(v, ~[s, s, s], 1e-9);
+
+        // ±1 are equally dominant, so there's nothing to converge to
+        let swap = Mat2::from_vec(~[~[0.0, 1.0], ~[1.0, 0.0]]).unwrap();
+        assert!(swap.power_iteration(1e-12, 1000).is_none());
+    }
+
+    #[test]
+    fn test_spectral_radius() {
+        let a = Mat2::from_vec(~[~[0.9, 0.0], ~[0.0, -0.3]]).unwrap();
+        assert!((a.spectral_radius(1e-12) - 0.9).abs() < 1e-9);
+
+        // eigenvalues ±1 and ±0.5i: no single dominant one
+        let swap = Mat2::from_vec(~[~[0.0, 1.0], ~[1.0, 0.0]]).unwrap();
+        assert!((swap.spectral_radius(1e-12) - 1.0).abs() < 1e-9);
+        let rot = Mat2::from_vec(~[~[0.0, -0.5], ~[0.5, 0.0]]).unwrap();
+        assert!((rot.spectral_radius(1e-12) - 0.5).abs() < 1e-9);
+
+        let nilpotent = Mat2::from_vec(~[~[0.0, 1.0], ~[0.0, 0.0]]).unwrap();
+        assert_eq!(nilpotent.spectral_radius(1e-12), 0.0);
+    }
+
+    #[test]
+    fn test_balance() {
+        let a = Mat2::from_vec(~[~[1.0, 1e4], ~[1e-4, 1.0]]).unwrap();
+        let (b, d) = a.balance();
+        // the off-diagonal entries end up within a factor of 4 of each other
+        let (p, q) = (*b.get(0, 1), *b.get(1, 0));
+        assert!(p / q < 4.0 && q / p < 4.0);
+        for i in range(0, 2) {
+            for j in range(0, 2) {
+                assert_eq!(*b.get(i, j), *a.get(i, j) * d[j] / d[i]);
+            }
+        }
+
+        // (p, 1) is an eigenvector of b for the eigenvalue 2, since p q = 1
+        let mut v = Mat2::from_vec(~[~[p], ~[1.0]]).unwrap();
+        unbalance(&mut v, d);
+        let u = ~[*v.get(0, 0), *v.get(1, 0)];
+        let au = a.apply(u);
+        assert_vec_eq_eps!(au, u.map(|x| 2.0 * *x), 1e-9);
+
+        // already balanced matrices are left alone
+        let c = Mat2::from_vec(~[~[1.0, 2.0], ~[3.0, 4.0]]).unwrap();
+        let (cb, cd) = c.balance();
+        assert_eq!(cb, c);
+        assert_eq!(cd, ~[1.0, 1.0]);
+    }
+
+    #[test]
+    fn test_eigenvalues() {
+        fn check(a: Mat2<f64>, expected: &[(f64, f64)]) {
+            let vals = a.eigenvalues().unwrap();
+            assert_eq!(vals.len(), expected.len());
+            for (v, &(re, im)) in vals.iter().zip(expected.iter()) {
+                assert!((v.re - re).abs() < 1e-9 && (v.im - im).abs() < 1e-9);
+            }
+        }
+
+        check(Mat2::from_vec(~[~[2.0, 0.0], ~[0.0, 3.0]]).unwrap(), [(3.0, 0.0), (2.0, 0.0)]);
+        check(Mat2::from_vec(~[~[0.0, -1.0], ~[1.0, 0.0]]).unwrap(), [(0.0, 1.0), (0.0, -1.0)]);
+        // companion matrix of (x - 1)(x - 2)(x - 3)
+        check(Mat2::from_vec(~[~[6.0, -11.0, 6.0], ~[1.0, 0.0, 0.0], ~[0.0, 1.0, 0.0]]).unwrap(),
+              [(3.0, 0.0), (2.0, 0.0), (1.0, 0.0)]);
+        let a = Mat2::from_vec(
+            ~[
+                ~[1.0, -2.0, 0.0, 1.0],
+                ~[2.0, 1.0, 0.0, 0.0],
+                ~[0.0, 0.0, 3.0, 0.0],
+                ~[0.0, 0.0, 1.0, -1.0]
+            ]).unwrap();
+        check(a.clone(), [(3.0, 0.0), (1.0, 2.0), (1.0, -2.0), (-1.0, 0.0)]);
+
+        let mut ws = Workspace::new();
+        let b = Mat2::from_vec(~[~[2.0, 0.0], ~[0.0, 3.0]]).unwrap();
+        for m in [&a, &b, &a].iter() {
+            assert_eq!(m.eigenvalues_ws(&mut ws), m.eigenvalues());
+        }
+    }
+
+    #[test]
+    fn test_jacobi_eigen() {
+        let a = Mat2::from_vec(~[~[4.0, 1.0, 0.0], ~[1.0, 3.0, 0.0], ~[0.0, 0.0, 1.0]]).unwrap();
+        let (vals, v) = a.jacobi_eigen();
+        let expected = ~[3.5 + 1.25f64.sqrt(), 3.5 - 1.25f64.sqrt(), 1.0];
+        assert_vec_eq_eps!(vals, expected, 1e-12);
+        assert_mat_eq_eps!(v.transpose() * v,
+                           Mat2::new_with(3, 3, |i, j| if i == j { 1.0 } else { 0.0 }), 1e-12);
+        let d = Mat2::new_with(3, 3, |i, j| if i == j { vals[i] } else { 0.0 });
+        assert_mat_eq_eps!(a * v, v * d, 1e-12);
+    }
+
+    #[test]
+    fn test_eigen() {
+        let a = Mat2::from_vec(~[~[2.0, 1.0, 0.0], ~[1.0, 3.0, 1.0], ~[0.0, 1.0, 4.0]]).unwrap();
+        let (vals, vecs) = a.eigen().unwrap();
+        for (lambda, v) in vals.iter().zip(vecs.iter()) {
+            let v = v.clone().unwrap();
+            let av = a.apply(v);
+            assert_vec_eq_eps!(av, v.map(|x| lambda.re * *x), 1e-9);
+        }
+
+        let rot = Mat2::from_vec(~[~[0.0, -1.0], ~[1.0, 0.0]]).unwrap();
+        let (_, vecs) = rot.eigen().unwrap();
+        assert!(vecs.iter().all(|v| v.is_none()));
+    }
+}
@@ -0,0 +1,53 @@
+//! The error type for the `try_*` versions of operations that otherwise fail on bad indices or
+//! mismatched sizes.
+
+use std::fmt;
+use matrix::Shape;
+
+/// What went wrong.
+#[deriving(Eq, Clone)]
+pub enum LinalgError {
+    /// An index (the first number) wasn't less than the bound it had to be under (the second)
+    OutOfBounds(uint, uint),
+    /// A vector was the wrong length: it had to have the first number of entries, and had the
+    /// second
+    WrongLength(uint, uint),
+    /// Two matrices that had to be the same shape weren't
+    ShapeMismatch(Shape, Shape),
+    /// A matrix that had to be square wasn't
+    NotSquare(Shape),
+    /// A matrix that had to be invertible wasn't
+    Singular,
+}
+
+/// The result of an operation that can fail with a `LinalgError`.
+pub type LinalgResult<T> = Result<T, LinalgError>;
+
+impl fmt::Default for LinalgError {
+    fn fmt(e: &LinalgError, f: &mut fmt::Formatter) {
+        let text = match *e {
+            OutOfBounds(i, n) => format!("index {} out of bounds (must be less than {})", i, n),
+            WrongLength(want, got) => format!("expected {} entries, got {}", want, got),
+            ShapeMismatch(a, b) => format!("shapes differ: {}x{} vs {}x{}",
+                                           a.rows, a.cols, b.rows, b.cols),
+            NotSquare(s) => format!("{}x{} matrix isn't square", s.rows, s.cols),
+            Singular => ~"matrix is singular",
+        };
+        f.buf.write(text.as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matrix::Shape;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(format!("{}", OutOfBounds(3, 2)),
+                   ~"index 3 out of bounds (must be less than 2)");
+        assert_eq!(format!("{}", ShapeMismatch(Shape::new(1, 2), Shape::new(2, 1))),
+                   ~"shapes differ: 1x2 vs 2x1");
+        assert_eq!(format!("{}", Singular), ~"matrix is singular");
+    }
+}
@@ -0,0 +1,680 @@
+//! Small matrices of a fixed size, stored inline rather than on the heap. Entries are named
+//! `mij` for row `i`, column `j` (both starting at 0, like `Mat2::get`), and everything is
+//! written out longhand so there are no loops or bounds checks in the way.
+//!
+//! The transformation constructors work in homogeneous coordinates, with a 3x3 matrix for 2D and
+//! a 4x4 matrix for 3D. They act on column vectors, so the translation is in the last column and
+//! `a * b` applies `b` first.
+
+use std::num::{zero, one};
+use matrix::{Mat2, Shape};
+
+/// A 2x2 matrix.
+#[deriving(Clone, Eq)]
+pub struct Mat2x2<T> {
+    m00: T, m01: T,
+    m10: T, m11: T,
+}
+
+impl<T> Mat2x2<T> {
+    /// Create a matrix from its entries, in row-major order.
+    pub fn new(m00: T, m01: T,
+               m10: T, m11: T) -> Mat2x2<T> {
+        Mat2x2 {
+            m00: m00, m01: m01,
+            m10: m10, m11: m11,
+        }
+    }
+}
+
+impl<T: Num> Mat2x2<T> {
+    /// The determinant.
+    pub fn det(&self) -> T {
+        self.m00 * self.m11 - self.m01 * self.m10
+    }
+
+    /// The inverse, as the adjugate divided by the determinant. Returns `None` if the matrix is
+    /// singular.
+    pub fn inverse(&self) -> Option<Mat2x2<T>> {
+        let d = self.det();
+        if d == zero() { return None; }
+
+        Some(Mat2x2::new(
+            self.m11 / d, (zero::<T>() - self.m01) / d,
+            (zero::<T>() - self.m10) / d, self.m00 / d))
+    }
+}
+
+impl<T: Add<T, T> + Mul<T, T>> Mul<Mat2x2<T>, Mat2x2<T>> for Mat2x2<T> {
+    fn mul(&self, o: &Mat2x2<T>) -> Mat2x2<T> {
+        Mat2x2::new(
+            self.m00 * o.m00 + self.m01 * o.m10,
+            self.m00 * o.m01 + self.m01 * o.m11,
+            self.m10 * o.m00 + self.m11 * o.m10,
+            self.m10 * o.m01 + self.m11 * o.m11)
+    }
+}
+
+impl<T: Clone> Mat2x2<T> {
+    /// Copy into a heap-allocated `Mat2`.
+    pub fn to_mat(&self) -> Mat2<T> {
+        Mat2::from_vec(~[~[self.m00.clone(), self.m01.clone()],
+                         ~[self.m10.clone(), self.m11.clone()]]).unwrap()
+    }
+
+    /// Copy out of a `Mat2`, or return `None` if it isn't 2x2.
+    pub fn from_mat(a: &Mat2<T>) -> Option<Mat2x2<T>> {
+        if a.get_dimension() != Shape::new(2, 2) { return None; }
+        Some(Mat2x2::new(a.at(0, 0), a.at(0, 1),
+                         a.at(1, 0), a.at(1, 1)))
+    }
+}
+
+/// A 3x3 matrix.
+#[deriving(Clone, Eq)]
+pub struct Mat3x3<T> {
+    m00: T, m01: T, m02: T,
+    m10: T, m11: T, m12: T,
+    m20: T, m21: T, m22: T,
+}
+
+impl<T> Mat3x3<T> {
+    /// Create a matrix from its entries, in row-major order.
+    pub fn new(m00: T, m01: T, m02: T,
+               m10: T, m11: T, m12: T,
+               m20: T, m21: T, m22: T) -> Mat3x3<T> {
+        Mat3x3 {
+            m00: m00, m01: m01, m02: m02,
+            m10: m10, m11: m11, m12: m12,
+            m20: m20, m21: m21, m22: m22,
+        }
+    }
+}
+
+impl<T: Num> Mat3x3<T> {
+    /// The determinant, by cofactor expansion along the first row.
+    pub fn det(&self) -> T {
+        self.m00 * (self.m11 * self.m22 - self.m12 * self.m21)
+            - self.m01 * (self.m10 * self.m22 - self.m12 * self.m20)
+            + self.m02 * (self.m10 * self.m21 - self.m11 * self.m20)
+    }
+
+    /// The inverse, as the adjugate divided by the determinant. Returns `None` if the matrix is
+    /// singular.
+    pub fn inverse(&self) -> Option<Mat3x3<T>> {
+        let d = self.det();
+        if d == zero() { return None; }
+
+        Some(Mat3x3::new(
+            (self.m11 * self.m22 - self.m12 * self.m21) / d,
+            (self.m02 * self.m21 - self.m01 * self.m22) / d,
+            (self.m01 * self.m12 - self.m02 * self.m11) / d,
+            (self.m12 * self.m20 - self.m10 * self.m22) / d,
+            (self.m00 * self.m22 - self.m02 * self.m20) / d,
+            (self.m02 * self.m10 - self.m00 * self.m12) / d,
+            (self.m10 * self.m21 - self.m11 * self.m20) / d,
+            (self.m01 * self.m20 - self.m00 * self.m21) / d,
+            (self.m00 * self.m11 - self.m01 * self.m10) / d))
+    }
+}
+
+impl<T: Add<T, T> + Mul<T, T>> Mul<Mat3x3<T>, Mat3x3<T>> for Mat3x3<T> {
+    fn mul(&self, o: &Mat3x3<T>) -> Mat3x3<T> {
+        Mat3x3::new(
+            self.m00 * o.m00 + self.m01 * o.m10 + self.m02 * o.m20,
+            self.m00 * o.m01 + self.m01 * o.m11 + self.m02 * o.m21,
+            self.m00 * o.m02 + self.m01 * o.m12 + self.m02 * o.m22,
+            self.m10 * o.m00 + self.m11 * o.m10 + self.m12 * o.m20,
+            self.m10 * o.m01 + self.m11 * o.m11 + self.m12 * o.m21,
+            self.m10 * o.m02 + self.m11 * o.m12 + self.m12 * o.m22,
+            self.m20 * o.m00 + self.m21 * o.m10 + self.m22 * o.m20,
+            self.m20 * o.m01 + self.m21 * o.m11 + self.m22 * o.m21,
+            self.m20 * o.m02 + self.m21 * o.m12 + self.m22 * o.m22)
+    }
+}
+
+impl<T: Clone> Mat3x3<T> {
+    /// Copy into a heap-allocated `Mat2`.
+    pub fn to_mat(&self) -> Mat2<T> {
+        Mat2::from_vec(~[~[self.m00.clone(), self.m01.clone(), self.m02.clone()],
+                         ~[self.m10.clone(), self.m11.clone(), self.m12.clone()],
+                         ~[self.m20.clone(), self.m21.clone(), self.m22.clone()]]).unwrap()
+    }
+
+    /// Copy out of a `Mat2`, or return `None` if it isn't 3x3.
+    pub fn from_mat(a: &Mat2<T>) -> Option<Mat3x3<T>> {
+        if a.get_dimension() != Shape::new(3, 3) { return None; }
+        Some(Mat3x3::new(a.at(0, 0), a.at(0, 1), a.at(0, 2),
+                         a.at(1, 0), a.at(1, 1), a.at(1, 2),
+                         a.at(2, 0), a.at(2, 1), a.at(2, 2)))
+    }
+}
+
+impl<T: Zero + One> Mat3x3<T> {
+    /// The identity matrix.
+    pub fn identity() -> Mat3x3<T> {
+        Mat3x3::new(one(), zero(), zero(),
+                    zero(), one(), zero(),
+                    zero(), zero(), one())
+    }
+}
+
+impl Mat3x3<f64> {
+    /// Translate 2D points by `(x, y)`.
+    pub fn translation(x: f64, y: f64) -> Mat3x3<f64> {
+        Mat3x3::new(1.0, 0.0, x,
+                    0.0, 1.0, y,
+                    0.0, 0.0, 1.0)
+    }
+
+    /// Rotate 2D points counterclockwise about the origin by `theta` radians.
+    pub fn rotation(theta: f64) -> Mat3x3<f64> {
+        let (s, c) = (theta.sin(), theta.cos());
+        Mat3x3::new(c, -s, 0.0,
+                    s, c, 0.0,
+                    0.0, 0.0, 1.0)
+    }
+
+    /// Scale 2D points by `sx` along the x axis and `sy` along the y axis.
+    pub fn scaling(sx: f64, sy: f64) -> Mat3x3<f64> {
+        Mat3x3::new(sx, 0.0, 0.0,
+                    0.0, sy, 0.0,
+                    0.0, 0.0, 1.0)
+    }
+
+    /// Shear 2D points, sending `(x, y)` to `(x + kx y, y + ky x)`.
+    pub fn shear(kx: f64, ky: f64) -> Mat3x3<f64> {
+        Mat3x3::new(1.0, kx, 0.0,
+                    ky, 1.0, 0.0,
+                    0.0, 0.0, 1.0)
+    }
+
+    /// Apply the transformation to the 2D point `(x, y)`, dividing through by the homogeneous
+    /// coordinate at the end.
+    pub fn transform_point(&self, x: f64, y: f64) -> (f64, f64) {
+        let w = self.m20 * x + self.m21 * y + self.m22;
+        ((self.m00 * x + self.m01 * y + self.m02) / w,
+         (self.m10 * x + self.m11 * y + self.m12) / w)
+    }
+}
+
+/// A 4x4 matrix.
+#[deriving(Clone, Eq)]
+pub struct Mat4x4<T> {
+    m00: T, m01: T, m02: T, m03: T,
+    m10: T, m11: T, m12: T, m13: T,
+    m20: T, m21: T, m22: T, m23: T,
+    m30: T, m31: T, m32: T, m33: T,
+}
+
+impl<T> Mat4x4<T> {
+    /// Create a matrix from its entries, in row-major order.
+    pub fn new(m00: T, m01: T, m02: T, m03: T,
+               m10: T, m11: T, m12: T, m13: T,
+               m20: T, m21: T, m22: T, m23: T,
+               m30: T, m31: T, m32: T, m33: T) -> Mat4x4<T> {
+        Mat4x4 {
+            m00: m00, m01: m01, m02: m02, m03: m03,
+            m10: m10, m11: m11, m12: m12, m13: m13,
+            m20: m20, m21: m21, m22: m22, m23: m23,
+            m30: m30, m31: m31, m32: m32, m33: m33,
+        }
+    }
+}
+
+impl<T: Num> Mat4x4<T> {
+    // The 2x2 determinants of the top two rows (`s`) and the bottom two rows (`c`), taking the
+    // columns pairwise. Both the determinant and the inverse are built out of these.
+    fn minors(&self) -> ([T, ..6], [T, ..6]) {
+        ([self.m00 * self.m11 - self.m10 * self.m01,
+          self.m00 * self.m12 - self.m10 * self.m02,
+          self.m00 * self.m13 - self.m10 * self.m03,
+          self.m01 * self.m12 - self.m11 * self.m02,
+          self.m01 * self.m13 - self.m11 * self.m03,
+          self.m02 * self.m13 - self.m12 * self.m03],
+         [self.m20 * self.m31 - self.m30 * self.m21,
+          self.m20 * self.m32 - self.m30 * self.m22,
+          self.m20 * self.m33 - self.m30 * self.m23,
+          self.m21 * self.m32 - self.m31 * self.m22,
+          self.m21 * self.m33 - self.m31 * self.m23,
+          self.m22 * self.m33 - self.m32 * self.m23])
+    }
+
+    /// The determinant, by Laplace expansion along the top two rows.
+    pub fn det(&self) -> T {
+        let (s, c) = self.minors();
+        s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0]
+    }
+
+    /// The inverse, as the adjugate divided by the determinant. Returns `None` if the matrix is
+    /// singular.
+    pub fn inverse(&self) -> Option<Mat4x4<T>> {
+        let d = self.det();
+        if d == zero() { return None; }
+        let (s, c) = self.minors();
+
+        Some(Mat4x4::new(
+            (self.m11 * c[5] + self.m13 * c[3] - self.m12 * c[4]) / d,
+            (self.m02 * c[4] - self.m01 * c[5] - self.m03 * c[3]) / d,
+            (self.m31 * s[5] + self.m33 * s[3] - self.m32 * s[4]) / d,
+            (self.m22 * s[4] - self.m21 * s[5] - self.m23 * s[3]) / d,
+            (self.m12 * c[2] - self.m10 * c[5] - self.m13 * c[1]) / d,
+            (self.m00 * c[5] + self.m03 * c[1] - self.m02 * c[2]) / d,
+            (self.m32 * s[2] - self.m30 * s[5] - self.m33 * s[1]) / d,
+            (self.m20 * s[5] + self.m23 * s[1] - self.m22 * s[2]) / d,
+            (self.m10 * c[4] + self.m13 * c[0] - self.m11 * c[2]) / d,
+            (self.m01 * c[2] - self.m00 * c[4] - self.m03 * c[0]) / d,
+            (self.m30 * s[4] + self.m33 * s[0] - self.m31 * s[2]) / d,
+            (self.m21 * s[2] - self.m20 * s[4] - self.m23 * s[0]) / d,
+            (self.m11 * c[1] - self.m10 * c[3] - self.m12 * c[0]) / d,
+            (self.m00 * c[3] + self.m02 * c[0] - self.m01 * c[1]) / d,
+            (self.m31 * s[1] - self.m30 * s[3] - self.m32 * s[0]) / d,
+            (self.m20 * s[3] + self.m22 * s[0] - self.m21 * s[1]) / d))
+    }
+}
+
+impl<T: Zero + One> Mat4x4<T> {
+    /// The identity matrix.
+    pub fn identity() -> Mat4x4<T> {
+        Mat4x4::new(one(), zero(), zero(), zero(),
+                    zero(), one(), zero(), zero(),
+                    zero(), zero(), one(), zero(),
+                    zero(), zero(), zero(), one())
+    }
+}
+
+impl Mat4x4<f64> {
+    /// Translate 3D points by `(x, y, z)`.
+    pub fn translation(x: f64, y: f64, z: f64) -> Mat4x4<f64> {
+        Mat4x4::new(1.0, 0.0, 0.0, x,
+                    0.0, 1.0, 0.0, y,
+                    0.0, 0.0, 1.0, z,
+                    0.0, 0.0, 0.0, 1.0)
+    }
+
+    /// Rotate 3D points by `theta` radians about the x axis, counterclockwise when looking down
+    /// the axis towards the origin.
+    pub fn rotation_x(theta: f64) -> Mat4x4<f64> {
+        let (s, c) = (theta.sin(), theta.cos());
+        Mat4x4::new(1.0, 0.0, 0.0, 0.0,
+                    0.0, c, -s, 0.0,
+                    0.0, s, c, 0.0,
+                    0.0, 0.0, 0.0, 1.0)
+    }
+
+    /// Rotate 3D points by `theta` radians about the y axis.
+    pub fn rotation_y(theta: f64) -> Mat4x4<f64> {
+        let (s, c) = (theta.sin(), theta.cos());
+        Mat4x4::new(c, 0.0, s, 0.0,
+                    0.0, 1.0, 0.0, 0.0,
+                    -s, 0.0, c, 0.0,
+                    0.0, 0.0, 0.0, 1.0)
+    }
+
+    /// Rotate 3D points by `theta` radians about the z axis.
+    pub fn rotation_z(theta: f64) -> Mat4x4<f64> {
+        let (s, c) = (theta.sin(), theta.cos());
+        Mat4x4::new(c, -s, 0.0, 0.0,
+                    s, c, 0.0, 0.0,
+                    0.0, 0.0, 1.0, 0.0,
+                    0.0, 0.0, 0.0, 1.0)
+    }
+
+    /// Rotate 3D points by `theta` radians about the axis through the origin in the direction
+    /// `(x, y, z)`, which doesn't need to be a unit vector (Rodrigues' formula). Fails if the
+    /// axis is zero.
+    pub fn rotation_axis(x: f64, y: f64, z: f64, theta: f64) -> Mat4x4<f64> {
+        let len = (x * x + y * y + z * z).sqrt();
+        assert!(len > 0.0, "rotation_axis: axis must be nonzero");
+        let (x, y, z) = (x / len, y / len, z / len);
+        let (s, c) = (theta.sin(), theta.cos());
+        let t = 1.0 - c;
+        Mat4x4::new(t * x * x + c, t * x * y - s * z, t * x * z + s * y, 0.0,
+                    t * x * y + s * z, t * y * y + c, t * y * z - s * x, 0.0,
+                    t * x * z - s * y, t * y * z + s * x, t * z * z + c, 0.0,
+                    0.0, 0.0, 0.0, 1.0)
+    }
+
+    /// Scale 3D points by `sx`, `sy` and `sz` along the x, y and z axes.
+    pub fn scaling(sx: f64, sy: f64, sz: f64) -> Mat4x4<f64> {
+        Mat4x4::new(sx, 0.0, 0.0, 0.0,
+                    0.0, sy, 0.0, 0.0,
+                    0.0, 0.0, sz, 0.0,
+                    0.0, 0.0, 0.0, 1.0)
+    }
+
+    /// Shear 3D points: `xy` is how much x moves per unit of y, and so on, so `(x, y, z)` goes to
+    /// `(x + xy y + xz z, y + yx x + yz z, z + zx x + zy y)`.
+    pub fn shear(xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> Mat4x4<f64> {
+        Mat4x4::new(1.0, xy, xz, 0.0,
+                    yx, 1.0, yz, 0.0,
+                    zx, zy, 1.0, 0.0,
+                    0.0, 0.0, 0.0, 1.0)
+    }
+
+    /// Apply the transformation to the 3D point `(x, y, z)`, dividing through by the homogeneous
+    /// coordinate at the end.
+    pub fn transform_point(&self, x: f64, y: f64, z: f64) -> (f64, f64, f64) {
+        let w = self.m30 * x + self.m31 * y + self.m32 * z + self.m33;
+        ((self.m00 * x + self.m01 * y + self.m02 * z + self.m03) / w,
+         (self.m10 * x + self.m11 * y + self.m12 * z + self.m13) / w,
+         (self.m20 * x + self.m21 * y + self.m22 * z + self.m23) / w)
+    }
+}
+
+impl<T: Add<T, T> + Mul<T, T>> Mul<Mat4x4<T>, Mat4x4<T>> for Mat4x4<T> {
+    fn mul(&self, o: &Mat4x4<T>) -> Mat4x4<T> {
+        Mat4x4::new(
+            self.m00 * o.m00 + self.m01 * o.m10 + self.m02 * o.m20 + self.m03 * o.m30,
+            self.m00 * o.m01 + self.m01 * o.m11 + self.m02 * o.m21 + self.m03 * o.m31,
+            self.m00 * o.m02 + self.m01 * o.m12 + self.m02 * o.m22 + self.m03 * o.m32,
+            self.m00 * o.m03 + self.m01 * o.m13 + self.m02 * o.m23 + self.m03 * o.m33,
+            self.m10 * o.m00 + self.m11 * o.m10 + self.m12 * o.m20 + self.m13 * o.m30,
+            self.m10 * o.m01 + self.m11 * o.m11 + self.m12 * o.m21 + self.m13 * o.m31,
+            self.m10 * o.m02 + self.m11 * o.m12 + self.m12 * o.m22 + self.m13 * o.m32,
+            self.m10 * o.m03 + self.m11 * o.m13 + self.m12 * o.m23 + self.m13 * o.m33,
+            self.m20 * o.m00 + self.m21 * o.m10 + self.m22 * o.m20 + self.m23 * o.m30,
+            self.m20 * o.m01 + self.m21 * o.m11 + self.m22 * o.m21 + self.m23 * o.m31,
+            self.m20 * o.m02 + self.m21 * o.m12 + self.m22 * o.m22 + self.m23 * o.m32,
+            self.m20 * o.m03 + self.m21 * o.m13 + self.m22 * o.m23 + self.m23 * o.m33,
+            self.m30 * o.m00 + self.m31 * o.m10 + self.m32 * o.m20 + self.m33 * o.m30,
+            self.m30 * o.m01 + self.m31 * o.m11 + self.m32 * o.m21 + self.m33 * o.m31,
+            self.m30 * o.m02 + self.m31 * o.m12 + self.m32 * o.m22 + self.m33 * o.m32,
+            self.m30 * o.m03 + self.m31 * o.m13 + self.m32 * o.m23 + self.m33 * o.m33)
+    }
+}
+
+impl<T: Clone> Mat4x4<T> {
+    /// Copy into a heap-allocated `Mat2`.
+    pub fn to_mat(&self) -> Mat2<T> {
+        Mat2::from_vec(
+            ~[~[self.m00.clone(), self.m01.clone(), self.m02.clone(), self.m03.clone()],
+              ~[self.m10.clone(), self.m11.clone(), self.m12.clone(), self.m13.clone()],
+              ~[self.m20.clone(), self.m21.clone(), self.m22.clone(), self.m23.clone()],
+              ~[self.m30.clone(), self.m31.clone(), self.m32.clone(), self.m33.clone()]]).unwrap()
+    }
+
+    /// Copy out of a `Mat2`, or return `None` if it isn't 4x4.
+    pub fn from_mat(a: &Mat2<T>) -> Option<Mat4x4<T>> {
+        if a.get_dimension() != Shape::new(4, 4) { return None; }
+        Some(Mat4x4::new(a.at(0, 0), a.at(0, 1), a.at(0, 2), a.at(0, 3),
+                         a.at(1, 0), a.at(1, 1), a.at(1, 2), a.at(1, 3),
+                         a.at(2, 0), a.at(2, 1), a.at(2, 2), a.at(2, 3),
+                         a.at(3, 0), a.at(3, 1), a.at(3, 2), a.at(3, 3)))
+    }
+}
+
+/// A quaternion `w + x i + y j + z k`. Unit quaternions represent 3D rotations, and composing
+/// them and renormalizing now and then doesn't drift away from being a rotation the way a product
+/// of rotation matrices does.
+#[deriving(Clone, Eq)]
+pub struct Quat<T> {
+    w: T, x: T, y: T, z: T,
+}
+
+impl<T> Quat<T> {
+    /// Create a quaternion from its real part `w` and its imaginary parts `x`, `y` and `z`.
+    pub fn new(w: T, x: T, y: T, z: T) -> Quat<T> {
+        Quat { w: w, x: x, y: y, z: z }
+    }
+}
+
+impl<T: Clone + Neg<T>> Quat<T> {
+    /// The conjugate `w - x i - y j - z k`, which is the inverse for a unit quaternion.
+    pub fn conjugate(&self) -> Quat<T> {
+        Quat::new(self.w.clone(), -self.x, -self.y, -self.z)
+    }
+}
+
+impl<T: Add<T, T> + Sub<T, T> + Mul<T, T>> Mul<Quat<T>, Quat<T>> for Quat<T> {
+    /// The Hamilton product. As rotations, `p * q` applies `q` first.
+    fn mul(&self, o: &Quat<T>) -> Quat<T> {
+        Quat::new(self.w * o.w - self.x * o.x - self.y * o.y - self.z * o.z,
+                  self.w * o.x + self.x * o.w + self.y * o.z - self.z * o.y,
+                  self.w * o.y - self.x * o.z + self.y * o.w + self.z * o.x,
+                  self.w * o.z + self.x * o.y - self.y * o.x + self.z * o.w)
+    }
+}
+
+impl Quat<f64> {
+    /// The quaternion that doesn't rotate anything.
+    pub fn identity() -> Quat<f64> {
+        Quat::new(1.0, 0.0, 0.0, 0.0)
+    }
+
+    /// A rotation by `theta` radians about the axis `(x, y, z)`, the same one as
+    /// `Mat4x4::rotation_axis`. Fails if the axis is zero.
+    pub fn from_axis_angle(x: f64, y: f64, z: f64, theta: f64) -> Quat<f64> {
+        let len = (x * x + y * y + z * z).sqrt();
+        assert!(len > 0.0, "from_axis_angle: axis must be nonzero");
+        let s = (theta / 2.0).sin() / len;
+        Quat::new((theta / 2.0).cos(), x * s, y * s, z * s)
+    }
+
+    /// The four-dimensional dot product.
+    pub fn dot(&self, o: &Quat<f64>) -> f64 {
+        self.w * o.w + self.x * o.x + self.y * o.y + self.z * o.z
+    }
+
+    /// The length.
+    pub fn norm(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    /// Scale to unit length. The zero quaternion is returned as is.
+    pub fn normalize(&self) -> Quat<f64> {
+        let n = self.norm();
+        if n == 0.0 { return self.clone(); }
+        Quat::new(self.w / n, self.x / n, self.y / n, self.z / n)
+    }
+
+    /// Spherical linear interpolation between two unit quaternions, going from `self` at `t = 0`
+    /// to `o` at `t = 1` at a constant angular speed, and always the short way round.
+    pub fn slerp(&self, o: &Quat<f64>, t: f64) -> Quat<f64> {
+        // q and -q are the same rotation, so pick whichever is closer
+        let mut d = self.dot(o);
+        let o = if d < 0.0 { d = -d; Quat::new(-o.w, -o.x, -o.y, -o.z) } else { o.clone() };
+
+        // nearly the same rotation, where sin(angle) is too small to divide by
+        if d > 0.9995 {
+            return Quat::new(self.w + t * (o.w - self.w), self.x + t * (o.x - self.x),
+                             self.y + t * (o.y - self.y), self.z + t * (o.z - self.z)).normalize();
+        }
+
+        let angle = d.acos();
+        let a = ((1.0 - t) * angle).sin() / angle.sin();
+        let b = (t * angle).sin() / angle.sin();
+        Quat::new(a * self.w + b * o.w, a * self.x + b * o.x,
+                  a * self.y + b * o.y, a * self.z + b * o.z)
+    }
+
+    /// The 3D rotation matrix for a unit quaternion. Note that this is a plain 3x3 rotation, not
+    /// one of the 2D transformations in homogeneous coordinates.
+    pub fn to_mat3(&self) -> Mat3x3<f64> {
+        let (w, x, y, z) = (self.w, self.x, self.y, self.z);
+        Mat3x3::new(1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y - w * z), 2.0 * (x * z + w * y),
+                    2.0 * (x * y + w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z - w * x),
+                    2.0 * (x * z - w * y), 2.0 * (y * z + w * x), 1.0 - 2.0 * (x * x + y * y))
+    }
+
+    /// The unit quaternion for a 3x3 rotation matrix. The result is garbage if the matrix isn't a
+    /// rotation.
+    pub fn from_mat3(m: &Mat3x3<f64>) -> Quat<f64> {
+        // work from the biggest of w, x, y and z so the division is well conditioned
+        let trace = m.m00 + m.m11 + m.m22;
+        if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Quat::new(s / 4.0, (m.m21 - m.m12) / s, (m.m02 - m.m20) / s, (m.m10 - m.m01) / s)
+        } else if m.m00 > m.m11 && m.m00 > m.m22 {
+            let s = (1.0 + m.m00 - m.m11 - m.m22).sqrt() * 2.0;
+            Quat::new((m.m21 - m.m12) / s, s / 4.0, (m.m01 + m.m10) / s, (m.m02 + m.m20) / s)
+        } else if m.m11 > m.m22 {
+            let s = (1.0 + m.m11 - m.m00 - m.m22).sqrt() * 2.0;
+            Quat::new((m.m02 - m.m20) / s, (m.m01 + m.m10) / s, s / 4.0, (m.m12 + m.m21) / s)
+        } else {
+            let s = (1.0 + m.m22 - m.m00 - m.m11).sqrt() * 2.0;
+            Quat::new((m.m10 - m.m01) / s, (m.m02 + m.m20) / s, (m.m12 + m.m21) / s, s / 4.0)
+        }
+    }
+
+    /// Rotate the point `(x, y, z)` by a unit quaternion.
+    pub fn rotate_point(&self, x: f64, y: f64, z: f64) -> (f64, f64, f64) {
+        let p = *self * Quat::new(0.0, x, y, z) * self.conjugate();
+        (p.x, p.y, p.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+    use matrix::Mat2;
+
+    #[test]
+    fn test_mat2x2() {
+        let a = Mat2x2::new(4i, 7,
+                            2, 6);
+        let b = Mat2x2::new(1i, 1,
+                            0, 1);
+        assert_eq!(a * b, Mat2x2::new(4, 11, 2, 8));
+        assert_eq!(a.det(), 10);
+        assert_eq!(b.inverse(), Some(Mat2x2::new(1, -1, 0, 1)));
+        assert_eq!(Mat2x2::new(1i, 2, 2, 4).inverse(), None);
+        let inv = Mat2x2::new(4.0, 7.0, 2.0, 6.0).inverse().unwrap();
+        assert_eq!(inv, Mat2x2::new(0.6, -0.7, -0.2, 0.4));
+    }
+
+    #[test]
+    fn test_mat3x3() {
+        let a = Mat3x3::new(2i, 0, 1,
+                            1, 3, 0,
+                            0, 1, 4);
+        let b = Mat3x3::new(1i, 2, 0,
+                            0, 1, 0,
+                            3, 0, 1);
+        assert_eq!(a * b, Mat3x3::new(5, 4, 1,
+                                      1, 5, 0,
+                                      12, 1, 4));
+        assert_eq!(a.det(), 25);
+        assert_eq!(b.det(), 1);
+        assert_eq!(b.inverse(), Some(Mat3x3::new(1, -2, 0,
+                                                 0, 1, 0,
+                                                 -3, 6, 1)));
+        assert_eq!(Mat3x3::new(1i, 2, 3, 2, 4, 6, 0, 0, 1).inverse(), None);
+    }
+
+    #[test]
+    fn test_mat4x4() {
+        let a = Mat4x4::new(2i, 0, 1, 0,
+                            1, 1, 0, 0,
+                            0, 0, 1, 3,
+                            0, 1, 0, 1);
+        let b = Mat4x4::new(1i, 0, 0, 1,
+                            0, 2, 0, 0,
+                            1, 0, 1, 0,
+                            0, 0, 0, 1);
+        assert_eq!(a * b, Mat4x4::new(3, 0, 1, 2,
+                                      1, 2, 0, 1,
+                                      1, 0, 1, 3,
+                                      0, 2, 0, 1));
+        assert_eq!(a.det(), -1);
+        assert_eq!(b.det(), 2);
+        assert_eq!(a.inverse(), Some(Mat4x4::new(-1, 3, 1, -3,
+                                                 1, -2, -1, 3,
+                                                 3, -6, -2, 6,
+                                                 -1, 2, 1, -2)));
+        assert_eq!(Mat4x4::new(1i, 2, 3, 4, 2, 4, 6, 8, 0, 0, 1, 0, 0, 0, 0, 1).inverse(), None);
+    }
+
+    fn close2((x, y): (f64, f64), (ex, ey): (f64, f64)) -> bool {
+        (x - ex).abs() < 1e-12 && (y - ey).abs() < 1e-12
+    }
+
+    fn close3((x, y, z): (f64, f64, f64), (ex, ey, ez): (f64, f64, f64)) -> bool {
+        (x - ex).abs() < 1e-12 && (y - ey).abs() < 1e-12 && (z - ez).abs() < 1e-12
+    }
+
+    #[test]
+    fn test_transform_2d() {
+        let half_pi = PI / 2.0;
+        assert!(close2(Mat3x3::rotation(half_pi).transform_point(1.0, 0.0), (0.0, 1.0)));
+        assert!(close2(Mat3x3::translation(1.0, -2.0).transform_point(3.0, 4.0), (4.0, 2.0)));
+        assert!(close2(Mat3x3::scaling(2.0, 3.0).transform_point(1.0, 1.0), (2.0, 3.0)));
+        assert!(close2(Mat3x3::shear(2.0, 0.0).transform_point(1.0, 1.0), (3.0, 1.0)));
+        // scale first, then move
+        let t = Mat3x3::translation(1.0, 0.0) * Mat3x3::scaling(2.0, 2.0);
+        assert!(close2(t.transform_point(1.0, 1.0), (3.0, 2.0)));
+        assert_eq!(Mat3x3::rotation(0.0), Mat3x3::identity());
+    }
+
+    #[test]
+    fn test_transform_3d() {
+        let half_pi = PI / 2.0;
+        assert!(close3(Mat4x4::rotation_x(half_pi).transform_point(0.0, 1.0, 0.0),
+                       (0.0, 0.0, 1.0)));
+        assert!(close3(Mat4x4::rotation_y(half_pi).transform_point(0.0, 0.0, 1.0),
+                       (1.0, 0.0, 0.0)));
+        assert!(close3(Mat4x4::rotation_z(half_pi).transform_point(1.0, 0.0, 0.0),
+                       (0.0, 1.0, 0.0)));
+        // a third of a turn about (1, 1, 1) cycles the axes
+        let r = Mat4x4::rotation_axis(1.0, 1.0, 1.0, 2.0 * PI / 3.0);
+        assert!(close3(r.transform_point(1.0, 0.0, 0.0), (0.0, 1.0, 0.0)));
+        assert!(close3(Mat4x4::translation(1.0, 2.0, 3.0).transform_point(1.0, 1.0, 1.0),
+                       (2.0, 3.0, 4.0)));
+        assert!(close3(Mat4x4::scaling(2.0, 3.0, 4.0).transform_point(1.0, 1.0, 1.0),
+                       (2.0, 3.0, 4.0)));
+        let sh = Mat4x4::shear(1.0, 0.0, 0.0, 0.0, 0.0, 2.0);
+        assert!(close3(sh.transform_point(0.0, 1.0, 0.0), (1.0, 1.0, 2.0)));
+        assert_eq!(Mat4x4::rotation_z(0.0), Mat4x4::identity());
+    }
+
+    fn close_quat(p: Quat<f64>, q: Quat<f64>) -> bool {
+        (p.w - q.w).abs() < 1e-12 && (p.x - q.x).abs() < 1e-12
+            && (p.y - q.y).abs() < 1e-12 && (p.z - q.z).abs() < 1e-12
+    }
+
+    #[test]
+    fn test_quat() {
+        let q = Quat::from_axis_angle(0.0, 0.0, 2.0, PI / 2.0);
+        assert!(close3(q.rotate_point(1.0, 0.0, 0.0), (0.0, 1.0, 0.0)));
+        assert!(close_quat(q * q.conjugate(), Quat::identity()));
+        assert_eq!(Quat::new(0.0, 3.0, 0.0, 4.0).normalize(), Quat::new(0.0, 0.6, 0.0, 0.8));
+
+        // composing quaternions matches composing the matrices
+        let p = Quat::from_axis_angle(1.0, 2.0, 3.0, 0.7);
+        let m = p.to_mat3() * q.to_mat3();
+        assert!(close_quat(Quat::from_mat3(&m), p * q));
+        let r = Mat4x4::rotation_axis(1.0, 2.0, 3.0, 0.7);
+        let pm = p.to_mat3();
+        assert!((pm.m02 - r.m02).abs() < 1e-12 && (pm.m21 - r.m21).abs() < 1e-12);
+        // a half turn has w = 0, which takes the other branches of from_mat3
+        let h = Quat::from_axis_angle(0.0, 1.0, 0.0, PI);
+        assert!(close_quat(Quat::from_mat3(&h.to_mat3()), h));
+    }
+
+    #[test]
+    fn test_slerp() {
+        let a = Quat::identity();
+        let b = Quat::from_axis_angle(0.0, 0.0, 1.0, PI / 2.0);
+        assert!(close_quat(a.slerp(&b, 0.0), a));
+        assert!(close_quat(a.slerp(&b, 1.0), b));
+        assert!(close_quat(a.slerp(&b, 0.5), Quat::from_axis_angle(0.0, 0.0, 1.0, PI / 4.0)));
+        // -b is the same rotation, and slerp should still go the short way
+        let nb = Quat::new(-b.w, -b.x, -b.y, -b.z);
+        assert!(close_quat(a.slerp(&nb, 0.5), Quat::from_axis_angle(0.0, 0.0, 1.0, PI / 4.0)));
+    }
+
+    #[test]
+    fn test_to_from_mat() {
+        let a = Mat3x3::new(1i, 2, 3, 4, 5, 6, 7, 8, 9);
+        let m = a.to_mat();
+        assert_eq!(m, Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6], ~[7, 8, 9]]).unwrap());
+        assert_eq!(Mat3x3::from_mat(&m), Some(a));
+        assert_eq!(Mat2x2::from_mat(&m), None);
+
+        let b = Mat4x4::new(1i, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16);
+        assert_eq!(Mat4x4::from_mat(&b.to_mat()), Some(b));
+        let c = Mat2x2::new(1i, 2, 3, 4);
+        assert_eq!(Mat2x2::from_mat(&c.to_mat()), Some(c));
+    }
+}
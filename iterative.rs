@@ -0,0 +1,446 @@
+//! Iterative solvers for `A x = b`, for systems too big (or too implicit) to eliminate directly.
+//! CG and GMRES only ever multiply by `A`, so anything implementing `LinearOperator` will do. The
+//! stationary methods (Jacobi, Gauss-Seidel and SOR) need to see the entries, so they take a
+//! `Mat2`. CG and GMRES also come in preconditioned versions, `pcg` and `pgmres`, which take
+//! anything implementing `precond::Preconditioner`.
+//!
+//! Every solver comes in two flavours: a plain one, and a `_monitor` one that additionally calls
+//! `f(iteration, residual_norm)` after every iteration, for watching long solves as they go.
+//! Both return the final iterate along with a `Convergence` describing how the solve went. The
+//! tolerance and iteration budget come from a `SolveOpts`.
+
+use std::vec;
+use matrix::{Mat2, Shape};
+use operator::LinearOperator;
+use precond::{Preconditioner, IdentityPrecond};
+use system::SolveOpts;
+use vector::{dot, norm};
+
+/// How an iterative solve went.
+#[deriving(Clone)]
+pub struct Convergence {
+    /// Whether the residual got below the requested tolerance.
+    converged: bool,
+    /// How many iterations were performed.
+    iterations: uint,
+    /// The residual norm `||b - A x||` of the starting guess, followed by the residual norm after
+    /// each iteration.
+    residuals: ~[f64],
+}
+
+fn residual<A: LinearOperator<f64>>(a: &A, x: &[f64], b: &[f64]) -> ~[f64] {
+    a.apply(x).iter().zip(b.iter()).map(|(ax, b)| *b - *ax).to_owned_vec()
+}
+
+/// Solve `A x = b` for symmetric positive definite `A` with the conjugate gradient method,
+/// starting from zero. Stops once `||b - A x|| <= opts.tol * ||b||` or after `opts.max_iter`
+/// iterations.
+pub fn cg<A: LinearOperator<f64>>(a: &A, b: &[f64],
+                                  opts: &SolveOpts<f64>) -> (~[f64], Convergence) {
+    cg_monitor(a, b, opts, |_, _| {})
+}
+
+/// `cg`, calling `f` after every iteration.
+pub fn cg_monitor<A: LinearOperator<f64>>(a: &A, b: &[f64], opts: &SolveOpts<f64>,
+                                          f: |uint, f64|) -> (~[f64], Convergence) {
+    pcg_monitor(a, b, &IdentityPrecond, opts, f)
+}
+
+/// Solve `A x = b` with the preconditioned conjugate gradient method, starting from zero. Both
+/// `A` and the preconditioner `M` have to be symmetric positive definite. The residuals reported
+/// are the true ones, `||b - A x||`, not the preconditioned ones.
+pub fn pcg<A: LinearOperator<f64>, P: Preconditioner>(a: &A, b: &[f64], m: &P,
+                                                      opts: &SolveOpts<f64>)
+                                                      -> (~[f64], Convergence) {
+    pcg_monitor(a, b, m, opts, |_, _| {})
+}
+
+/// `pcg`, calling `f` after every iteration.
+pub fn pcg_monitor<A: LinearOperator<f64>, P: Preconditioner>(a: &A, b: &[f64], m: &P,
+                                                              opts: &SolveOpts<f64>,
+                                                              f: |uint, f64|)
+                                                              -> (~[f64], Convergence) {
+    let (n, cols) = a.dimensions();
+    assert!(n == cols && n == b.len(), "cg: operator must be square and match b");
+
+    let target = opts.tol * norm(b);
+    let mut x = vec::from_elem(n, 0.0f64);
+    let mut r = b.to_owned();
+    let mut p = m.apply_inverse(r);
+    let mut rz = dot(r, p);
+    let mut res = norm(r);
+    let mut residuals = ~[res];
+
+    while residuals.len() <= opts.max_iter && res > target {
+        let ap = a.apply(p);
+        let alpha = rz / dot(p, ap);
+        for i in range(0, n) {
+            x[i] += alpha * p[i];
+            r[i] -= alpha * ap[i];
+        }
+        res = norm(r);
+        residuals.push(res);
+        f(residuals.len() - 1, res);
+        if res <= target { break; }
+
+        let z = m.apply_inverse(r);
+        let rz_new = dot(r, z);
+        let beta = rz_new / rz;
+        for i in range(0, n) {
+            p[i] = z[i] + beta * p[i];
+        }
+        rz = rz_new;
+    }
+
+    let conv = Convergence {
+        converged: res <= target,
+        iterations: residuals.len() - 1,
+        residuals: residuals,
+    };
+    (x, conv)
+}
+
+/// Solve `A x = b` for any nonsingular `A` with restarted GMRES, starting from zero. The Krylov
+/// basis is thrown away and rebuilt every `restart` iterations, which bounds the memory used to
+/// `restart` vectors. Stops once `||b - A x|| <= opts.tol * ||b||` or after `opts.max_iter`
+/// iterations.
+pub fn gmres<A: LinearOperator<f64>>(a: &A, b: &[f64], restart: uint,
+                                     opts: &SolveOpts<f64>) -> (~[f64], Convergence) {
+    gmres_monitor(a, b, restart, opts, |_, _| {})
+}
+
+/// `gmres`, calling `f` after every iteration.
+pub fn gmres_monitor<A: LinearOperator<f64>>(a: &A, b: &[f64], restart: uint,
+                                             opts: &SolveOpts<f64>,
+                                             f: |uint, f64|) -> (~[f64], Convergence) {
+    pgmres_monitor(a, b, restart, &IdentityPrecond, opts, f)
+}
+
+/// Solve `A x = b` with right-preconditioned restarted GMRES, starting from zero: it runs GMRES
+/// on `A M⁻¹ y = b` and then sets `x = M⁻¹ y`. Preconditioning on the right means the residuals
+/// it minimizes and reports are still the true ones, `||b - A x||`.
+pub fn pgmres<A: LinearOperator<f64>, P: Preconditioner>(a: &A, b: &[f64], restart: uint, m: &P,
+                                                         opts: &SolveOpts<f64>)
+                                                         -> (~[f64], Convergence) {
+    pgmres_monitor(a, b, restart, m, opts, |_, _| {})
+}
+
+/// `pgmres`, calling `f` after every iteration.
+pub fn pgmres_monitor<A: LinearOperator<f64>, P: Preconditioner>(a: &A, b: &[f64], restart: uint,
+                                                                 m: &P, opts: &SolveOpts<f64>,
+                                                                 f: |uint, f64|)
+                                                                 -> (~[f64], Convergence) {
+    let (n, cols) = a.dimensions();
+    assert!(n == cols && n == b.len(), "gmres: operator must be square and match b");
+    assert!(restart > 0, "gmres: restart length must be positive");
+
+    let target = opts.tol * norm(b);
+    let mut x = vec::from_elem(n, 0.0f64);
+    let mut residuals = ~[norm(b)];
+    let mut res = norm(b);
+
+    while residuals.len() <= opts.max_iter && res > target {
+        let r = residual(a, x, b);
+        let beta = norm(r);
+        // the last cycle's Givens estimate can be off, so go by the true residual
+        res = beta;
+        if beta <= target || beta == 0.0 { break; }
+
+        // Arnoldi, with the Hessenberg matrix kept upper triangular by Givens rotations as we go
+        let mut v = ~[r.iter().map(|e| *e / beta).to_owned_vec()];
+        let mut h = vec::from_fn(restart + 1, |_| vec::from_elem(restart, 0.0f64));
+        let mut cs = vec::from_elem(restart, 0.0f64);
+        let mut sn = vec::from_elem(restart, 0.0f64);
+        let mut g = vec::from_elem(restart + 1, 0.0f64);
+        g[0] = beta;
+
+        let mut k = 0;
+        while k < restart && residuals.len() <= opts.max_iter {
+            let mut w = a.apply(m.apply_inverse(v[k]));
+            for j in range(0, k + 1) {
+                h[j][k] = dot(w, v[j]);
+                for i in range(0, n) {
+                    w[i] -= h[j][k] * v[j][i];
+                }
+            }
+            let next = norm(w);
+            h[k+1][k] = next;
+
+            for j in range(0, k) {
+                let t = cs[j] * h[j][k] + sn[j] * h[j+1][k];
+                h[j+1][k] = -sn[j] * h[j][k] + cs[j] * h[j+1][k];
+                h[j][k] = t;
+            }
+            let d = (h[k][k] * h[k][k] + h[k+1][k] * h[k+1][k]).sqrt();
+            cs[k] = h[k][k] / d;
+            sn[k] = h[k+1][k] / d;
+            h[k][k] = d;
+            h[k+1][k] = 0.0;
+            g[k+1] = -sn[k] * g[k];
+            g[k] = cs[k] * g[k];
+
+            k += 1;
+            res = g[k].abs();
+            residuals.push(res);
+            f(residuals.len() - 1, res);
+
+            if res <= target || next == 0.0 { break; }
+            v.push(w.iter().map(|e| *e / next).to_owned_vec());
+        }
+
+        // back substitute for the coefficients of the update in the Krylov basis
+        let mut y = vec::from_elem(k, 0.0f64);
+        let mut i = k;
+        while i > 0 {
+            i -= 1;
+            let s = range(i + 1, k).fold(g[i], |s, j| s - h[i][j] * y[j]);
+            y[i] = s / h[i][i];
+        }
+        let mut update = vec::from_elem(n, 0.0f64);
+        for j in range(0, k) {
+            for e in range(0, n) {
+                update[e] += y[j] * v[j][e];
+            }
+        }
+        for (xe, u) in x.mut_iter().zip(m.apply_inverse(update).iter()) {
+            *xe += *u;
+        }
+    }
+
+    let conv = Convergence {
+        converged: res <= target,
+        iterations: residuals.len() - 1,
+        residuals: residuals,
+    };
+    (x, conv)
+}
+
+/// Solve `A x = b` with Jacobi iteration, starting from zero. This converges when `A` is strictly
+/// diagonally dominant (and sometimes otherwise). Stops once `||b - A x|| <= opts.tol * ||b||`
+/// or after `opts.max_iter` iterations. Fails if there's a zero on the diagonal.
+pub fn jacobi(a: &Mat2<f64>, b: &[f64], opts: &SolveOpts<f64>) -> (~[f64], Convergence) {
+    jacobi_monitor(a, b, opts, |_, _| {})
+}
+
+/// `jacobi`, calling `f` after every iteration.
+pub fn jacobi_monitor(a: &Mat2<f64>, b: &[f64], opts: &SolveOpts<f64>,
+                      f: |uint, f64|) -> (~[f64], Convergence) {
+    let Shape { rows: n, cols: m } = a.get_dimension();
+    assert!(n == m && n == b.len(), "jacobi: matrix must be square and match b");
+    assert!(range(0, n).all(|i| *a.get(i, i) != 0.0), "jacobi: zero on the diagonal");
+
+    let target = opts.tol * norm(b);
+    let mut x = vec::from_elem(n, 0.0f64);
+    let mut r = residual(a, x, b);
+    let mut res = norm(r);
+    let mut residuals = ~[res];
+
+    while residuals.len() <= opts.max_iter && res > target {
+        // x_i + r_i / a_ii is exactly the Jacobi update for x_i
+        for i in range(0, n) {
+            x[i] += r[i] / *a.get(i, i);
+        }
+
+        r = residual(a, x, b);
+        res = norm(r);
+        residuals.push(res);
+        f(residuals.len() - 1, res);
+    }
+
+    let conv = Convergence {
+        converged: res <= target,
+        iterations: residuals.len() - 1,
+        residuals: residuals,
+    };
+    (x, conv)
+}
+
+/// Solve `A x = b` with Gauss-Seidel iteration, starting from zero. This is Jacobi, except that
+/// each updated `x_i` is used straight away for the rest of the sweep, which usually makes it
+/// converge about twice as fast. It converges when `A` is strictly diagonally dominant or
+/// symmetric positive definite. Stops once `||b - A x|| <= opts.tol * ||b||` or after
+/// `opts.max_iter` iterations. Fails if there's a zero on the diagonal.
+pub fn gauss_seidel(a: &Mat2<f64>, b: &[f64], opts: &SolveOpts<f64>) -> (~[f64], Convergence) {
+    sor_monitor(a, b, 1.0, opts, |_, _| {})
+}
+
+/// `gauss_seidel`, calling `f` after every iteration.
+pub fn gauss_seidel_monitor(a: &Mat2<f64>, b: &[f64], opts: &SolveOpts<f64>,
+                            f: |uint, f64|) -> (~[f64], Convergence) {
+    sor_monitor(a, b, 1.0, opts, f)
+}
+
+/// Solve `A x = b` with successive over-relaxation, starting from zero: Gauss-Seidel, but moving
+/// each `x_i` `omega` times as far as Gauss-Seidel would. `omega` has to be in (0, 2) for there
+/// to be any hope of converging, and a well chosen one a bit above 1 can be much faster than
+/// Gauss-Seidel (which is `omega = 1`). Stops once `||b - A x|| <= opts.tol * ||b||` or after
+/// `opts.max_iter` iterations. Fails if there's a zero on the diagonal.
+pub fn sor(a: &Mat2<f64>, b: &[f64], omega: f64, opts: &SolveOpts<f64>) -> (~[f64], Convergence) {
+    sor_monitor(a, b, omega, opts, |_, _| {})
+}
+
+/// `sor`, calling `f` after every iteration.
+pub fn sor_monitor(a: &Mat2<f64>, b: &[f64], omega: f64, opts: &SolveOpts<f64>,
+                   f: |uint, f64|) -> (~[f64], Convergence) {
+    let Shape { rows: n, cols: m } = a.get_dimension();
+    assert!(n == m && n == b.len(), "sor: matrix must be square and match b");
+    assert!(range(0, n).all(|i| *a.get(i, i) != 0.0), "sor: zero on the diagonal");
+
+    let target = opts.tol * norm(b);
+    let mut x = vec::from_elem(n, 0.0f64);
+    let mut res = norm(b);
+    let mut residuals = ~[res];
+
+    while residuals.len() <= opts.max_iter && res > target {
+        for i in range(0, n) {
+            let row = a.get_row(i);
+            // x_i + r_i / a_ii is the Gauss-Seidel update, with r_i using the newest x
+            let r = b[i] - dot(row, x);
+            x[i] += omega * r / row[i];
+        }
+
+        res = norm(residual(a, x, b));
+        residuals.push(res);
+        f(residuals.len() - 1, res);
+    }
+
+    let conv = Convergence {
+        converged: res <= target,
+        iterations: residuals.len() - 1,
+        residuals: residuals,
+    };
+    (x, conv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::vec;
+    use diff::{laplacian_2d, Dirichlet};
+    use matrix::Mat2;
+    use operator::{LinearOperator, Diagonal};
+    use precond::{JacobiPrecond, IncompleteCholesky};
+    use system::SolveOpts;
+
+    fn opts(tol: f64, max_iter: uint) -> SolveOpts<f64> {
+        SolveOpts { tol: tol, max_iter: max_iter, ..Default::default() }
+    }
+
+    fn residual<A: LinearOperator<f64>>(a: &A, x: &[f64], b: &[f64]) -> f64 {
+        a.apply(x).iter().zip(b.iter()).fold(0.0, |s, (ax, b)| s + (*ax - *b) * (*ax - *b)).sqrt()
+    }
+
+    #[test]
+    fn test_cg() {
+        let a = laplacian_2d(4, 3, Dirichlet);
+        let b = ~[1.0, 2.0, 0.0, -1.0, 3.0, 1.0, 1.0, 0.0, 2.0, 5.0, -2.0, 1.0];
+        let (x, conv) = cg(&a, b, &opts(1e-10, 100));
+        assert!(conv.converged);
+        assert!(residual(&a, x, b) < 1e-8);
+
+        let d = Diagonal::new(~[2.0, 4.0, 8.0]);
+        let (x, conv) = cg(&d, &[2.0, 2.0, 2.0], &opts(1e-12, 10));
+        assert!(conv.converged);
+        assert!(residual(&d, x, &[2.0, 2.0, 2.0]) < 1e-10);
+
+        let (_, conv) = cg(&a, b, &opts(1e-10, 1));
+        assert!(!conv.converged);
+        assert_eq!(conv.iterations, 1);
+    }
+
+    #[test]
+    fn test_gmres() {
+        // nonsymmetric, so CG is no good here
+        let a = Mat2::from_vec(
+            ~[
+                ~[4.0, 1.0, 0.0, 2.0],
+                ~[-1.0, 3.0, 1.0, 0.0],
+                ~[0.0, 2.0, 5.0, -1.0],
+                ~[1.0, 0.0, -2.0, 6.0]
+            ]).unwrap();
+        let b = ~[1.0, 2.0, 3.0, 4.0];
+        let (x, conv) = gmres(&a, b, 10, &opts(1e-12, 50));
+        assert!(conv.converged);
+        assert!(conv.iterations <= 4);
+        assert!(residual(&a, x, b) < 1e-10);
+
+        // restarting every other step still gets there, just slower
+        let (x, conv) = gmres(&a, b, 2, &opts(1e-10, 200));
+        assert!(conv.converged);
+        assert!(residual(&a, x, b) < 1e-8);
+    }
+
+    #[test]
+    fn test_pcg() {
+        // badly scaled, which Jacobi preconditioning undoes completely
+        let d = Mat2::new_with(4, 4, |i, j| if i == j { [1.0, 1e3, 1e-3, 1e6][i] } else { 0.0 });
+        let b = ~[1.0, 1.0, 1.0, 1.0];
+        let (x, conv) = pcg(&d, b, &JacobiPrecond::new(&d), &opts(1e-12, 10));
+        assert!(conv.converged);
+        assert_eq!(conv.iterations, 1);
+        assert_vec_eq_eps!(x, ~[1.0, 1e-3, 1e3, 1e-6], 1e-9);
+
+        let a = laplacian_2d(6, 6, Dirichlet);
+        let b = vec::from_fn(36, |i| (i % 7) as f64 - 3.0);
+        let (_, plain) = cg(&a, b, &opts(1e-10, 200));
+        let ic = IncompleteCholesky::new(&a).unwrap();
+        let (x, conv) = pcg(&a, b, &ic, &opts(1e-10, 200));
+        assert!(conv.converged);
+        assert!(conv.iterations < plain.iterations);
+        assert!(residual(&a, x, b) < 1e-8);
+    }
+
+    #[test]
+    fn test_pgmres() {
+        let t = Mat2::from_vec(~[~[4.0, -1.0, 0.0], ~[-2.0, 4.0, -1.0], ~[0.0, -2.0, 4.0]]);
+        let t = t.unwrap();
+        let b = ~[1.0, 2.0, 3.0];
+        let (x, conv) = pgmres(&t, b, 10, &JacobiPrecond::new(&t), &opts(1e-12, 20));
+        assert!(conv.converged);
+        assert!(residual(&t, x, b) < 1e-10);
+    }
+
+    #[test]
+    fn test_gauss_seidel_sor() {
+        let a = laplacian_2d(4, 4, Dirichlet);
+        let b = vec::from_fn(16, |i| (i % 5) as f64 - 2.0);
+        let bn = b.iter().fold(0.0, |s, x| s + *x * *x).sqrt();
+        let (x, gs) = gauss_seidel(&a, b, &opts(1e-10, 1000));
+        assert!(gs.converged);
+        assert!(residual(&a, x, b) < 1e-8 * bn);
+
+        let (_, jac) = jacobi(&a, b, &opts(1e-10, 1000));
+        assert!(gs.iterations < jac.iterations);
+        // over-relaxing helps a lot on the Laplacian
+        let (x, s) = sor(&a, b, 1.5, &opts(1e-10, 1000));
+        assert!(s.converged);
+        assert!(s.iterations < gs.iterations);
+        assert!(residual(&a, x, b) < 1e-8 * bn);
+
+        let mut count = 0;
+        let (_, conv) = gauss_seidel_monitor(&a, b, &opts(1e-10, 5), |_, _| count += 1);
+        assert!(!conv.converged);
+        assert_eq!(count, 5);
+    }
+
+    #[test]
+    fn test_jacobi_monitor() {
+        let a = Mat2::from_vec(
+            ~[
+                ~[10.0, 1.0, 2.0],
+                ~[1.0, 8.0, -1.0],
+                ~[2.0, -1.0, 9.0]
+            ]).unwrap();
+        let b = ~[1.0, 1.0, 1.0];
+        let mut seen = ~[];
+        let (x, conv) = jacobi_monitor(&a, b, &opts(1e-10, 100), |i, r| seen.push((i, r)));
+        assert!(conv.converged);
+        assert!(residual(&a, x, b) < 1e-9);
+        assert_eq!(seen.len(), conv.iterations);
+        assert_eq!(conv.residuals.len(), conv.iterations + 1);
+        for (k, &(i, r)) in seen.iter().enumerate() {
+            assert_eq!(i, k + 1);
+            assert_eq!(r, conv.residuals[k + 1]);
+        }
+    }
+}
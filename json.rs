@@ -0,0 +1,94 @@
+//! Reading and writing matrices as JSON objects of the form
+//! `{ "rows": n, "cols": m, "data": [...] }`, with the elements listed in row-major order.
+
+use std::num::CheckedMul;
+use extra::json;
+use extra::json::{Json, ToJson, Number, List, Object};
+use extra::treemap::TreeMap;
+use matrix::{Mat2, Shape};
+
+impl<T: ToJson> ToJson for Mat2<T> {
+    fn to_json(&self) -> Json {
+        let Shape { rows: n, cols: m } = self.get_dimension();
+        let mut data = ~[];
+        for row in self.row_iter() {
+            for x in row.iter() {
+                data.push(x.to_json());
+            }
+        }
+
+        let mut obj = ~TreeMap::new();
+        obj.insert(~"rows", n.to_json());
+        obj.insert(~"cols", m.to_json());
+        obj.insert(~"data", List(data));
+        Object(obj)
+    }
+}
+
+fn get_uint(obj: &json::Object, key: &str) -> Option<uint> {
+    match obj.find(&key.to_owned()) {
+        Some(&Number(x)) if x >= 0.0 && x == x.floor() => Some(x as uint),
+        _ => None
+    }
+}
+
+impl Mat2<f64> {
+    /// Read a matrix back out of the JSON produced by `to_json`. Returns `None` if `j` isn't an
+    /// object of that shape, if `rows * cols` overflows, or if `data` doesn't hold exactly that
+    /// many numbers.
+    pub fn from_json(j: &Json) -> Option<Mat2<f64>> {
+        let obj = match *j {
+            Object(ref o) => o,
+            _ => return None
+        };
+        let (n, m) = match (get_uint(&**obj, "rows"), get_uint(&**obj, "cols")) {
+            (Some(n), Some(m)) => (n, m),
+            _ => return None
+        };
+        let len = match n.checked_mul(&m) {
+            Some(len) => len,
+            None => return None
+        };
+        let data = match obj.find(&~"data") {
+            Some(&List(ref d)) if d.len() == len => d,
+            _ => return None
+        };
+
+        let mut nums = ~[];
+        for x in data.iter() {
+            match *x {
+                Number(v) => nums.push(v),
+                _ => return None
+            }
+        }
+        Some(Mat2::new_with(n, m, |i, j| nums[i * m + j]))
+    }
+
+    /// Parse `s` as JSON and read a matrix out of it with `from_json`.
+    pub fn from_json_str(s: &str) -> Option<Mat2<f64>> {
+        match json::from_str(s) {
+            Ok(j) => Mat2::from_json(&j),
+            Err(_) => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use extra::json::ToJson;
+    use matrix::Mat2;
+
+    #[test]
+    fn test_json() {
+        let x = Mat2::from_vec(~[~[1.0, 2.5, 3.0], ~[-4.0, 5.0, 6.0]]).unwrap();
+        assert_eq!(Mat2::from_json(&x.to_json()), Some(x.clone()));
+        assert_eq!(Mat2::from_json_str(x.to_json().to_str()), Some(x));
+
+        assert_eq!(Mat2::from_json_str("{\"rows\": 1, \"cols\": 2, \"data\": [1, 2]}"),
+                   Mat2::from_vec(~[~[1.0, 2.0]]));
+        assert_eq!(Mat2::from_json_str("{\"rows\": 2, \"cols\": 2, \"data\": [1, 2]}"), None);
+        assert_eq!(Mat2::from_json_str("[1, 2]"), None);
+        assert_eq!(Mat2::from_json_str(
+            "{\"rows\": 4294967296, \"cols\": 4294967296, \"data\": []}"), None);
+    }
+}
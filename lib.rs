@@ -1,7 +1,29 @@
 //! A simple linear algebra library to help me learn the subject.
 
 #[crate_id="linalg#0.1"];
-#[feature(globs)];
+#[feature(globs, macro_rules, struct_variant)];
 
+extern mod extra;
+
+// first, so the macros are visible in the other modules
+#[macro_escape]
+mod macros;
+
+pub mod backend;
+pub mod batch;
+pub mod decomp;
+pub mod diff;
+pub mod eigen;
+pub mod error;
+pub mod fixed;
+pub mod iterative;
+pub mod json;
 pub mod matrix;
+pub mod modular;
+pub mod operator;
+pub mod permutation;
+pub mod precond;
+pub mod sparse;
+pub mod svd;
 pub mod system;
+pub mod vector;
@@ -0,0 +1,84 @@
+//! Macros for testing numeric code, where exact equality is too much to ask for.
+
+/// Assert that two matrices have the same shape and that every pair of corresponding elements
+/// is within `eps` of each other. On failure, reports the shapes and the first coordinate
+/// (in row-major order) where they differ.
+#[macro_export]
+macro_rules! assert_mat_eq_eps(
+    ($a:expr, $b:expr, $eps:expr) => ({
+        let (a, b, eps) = (&$a, &$b, $eps);
+        let (sa, sb) = (a.get_dimension(), b.get_dimension());
+        if sa != sb {
+            fail!("assert_mat_eq_eps: shapes differ: left is {}x{}, right is {}x{}",
+                  sa.rows, sa.cols, sb.rows, sb.cols);
+        }
+        for i in range(0, sa.rows) {
+            for j in range(0, sa.cols) {
+                let (x, y) = (*a.get(i, j), *b.get(i, j));
+                if !((x - y).abs() <= eps) {
+                    fail!("assert_mat_eq_eps: {}x{} matrices differ at ({}, {}): \
+                           left is {}, right is {}, eps is {}", sa.rows, sa.cols, i, j, x, y, eps);
+                }
+            }
+        }
+    })
+)
+
+/// Assert that two vectors (or slices) have the same length and that every pair of
+/// corresponding elements is within `eps` of each other. On failure, reports the lengths and
+/// the first index where they differ.
+#[macro_export]
+macro_rules! assert_vec_eq_eps(
+    ($a:expr, $b:expr, $eps:expr) => ({
+        let (a, b, eps) = ($a.as_slice(), $b.as_slice(), $eps);
+        if a.len() != b.len() {
+            fail!("assert_vec_eq_eps: lengths differ: left has {}, right has {}",
+                  a.len(), b.len());
+        }
+        for i in range(0, a.len()) {
+            if !((a[i] - b[i]).abs() <= eps) {
+                fail!("assert_vec_eq_eps: vectors of length {} differ at {}: \
+                       left is {}, right is {}, eps is {}", a.len(), i, a[i], b[i], eps);
+            }
+        }
+    })
+)
+
+#[cfg(test)]
+mod tests {
+    use matrix::Mat2;
+
+    #[test]
+    fn test_assert_mat_eq_eps() {
+        let a = Mat2::from_vec(~[~[1.0, 2.0], ~[3.0, 4.0]]).unwrap();
+        let b = Mat2::from_vec(~[~[1.0, 2.0 + 1e-9], ~[3.0, 4.0 - 1e-9]]).unwrap();
+        assert_mat_eq_eps!(a, b, 1e-8);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_assert_mat_eq_eps_differs() {
+        let a = Mat2::from_vec(~[~[1.0, 2.0], ~[3.0, 4.0]]).unwrap();
+        let b = Mat2::from_vec(~[~[1.0, 2.0], ~[3.5, 4.0]]).unwrap();
+        assert_mat_eq_eps!(a, b, 1e-8);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_assert_mat_eq_eps_shape() {
+        let a = Mat2::from_vec(~[~[1.0, 2.0]]).unwrap();
+        let b = Mat2::from_vec(~[~[1.0], ~[2.0]]).unwrap();
+        assert_mat_eq_eps!(a, b, 1e-8);
+    }
+
+    #[test]
+    fn test_assert_vec_eq_eps() {
+        assert_vec_eq_eps!(~[1.0, 2.0], ~[1.0, 2.0 + 1e-12], 1e-10);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_assert_vec_eq_eps_differs() {
+        assert_vec_eq_eps!(~[1.0, 2.0], ~[1.0, 2.1], 1e-10);
+    }
+}
@@ -1,13 +1,29 @@
 use std::vec;
+use std::cast;
 use std::fmt;
-use std::num::{Zero, One, one};
-
-/// A two-dimensional matrix.
+use std::f32;
+use std::f64;
+use std::uint;
+use std::to_bytes;
+use std::util::replace;
+use std::iter::Range;
+use std::unstable::raw::Slice;
+use error::{LinalgResult, OutOfBounds, WrongLength};
+use system::{solve_multi_opts, SolveOpts, Pivoting, NoPivoting, PartialPivoting};
+use std::num::{Zero, One, ApproxEq, zero, one};
+
+/// A two-dimensional matrix, stored as a single row-major vector.
+///
+/// # Memory layout
+///
+/// The elements live in one contiguous allocation, row after row, with no padding between rows:
+/// element (i, j) of an (n x m) matrix is at offset `i * m + j` from `as_ptr()`. This is the same
+/// layout as a C array `T a[n][m]`, which is what `export_c` and `import_c` rely on.
 #[deriving(Clone)]
 pub struct Mat2<T> {
-    // INVARIANT: data.len() == n, data.iter().all(|v| v.len() == m)
+    // INVARIANT: data.len() == n * m, and row i lives at data[i * m .. (i + 1) * m]
     // If this gets violated, shit hits the fan ASAP.
-    priv data: ~[~[T]],
+    priv data: ~[T],
     /// Number of rows
     priv n: uint,
     /// Number of columns
@@ -28,6 +44,28 @@ impl<'a, T> Iterator<&'a [T]> for RowIterator<'a, T> {
     }
 }
 
+/// Iterator over the rows of a matrix as mutable slices. Made by `Mat2::row_iter_mut`.
+pub struct MutRowIterator<'a, T> {
+    // the rows that haven't been handed out yet
+    priv rest: Option<&'a mut [T]>,
+    priv rows: uint,
+    priv m: uint,
+}
+
+impl<'a, T> Iterator<&'a mut [T]> for MutRowIterator<'a, T> {
+    fn next(&mut self) -> Option<&'a mut [T]> {
+        if self.rows == 0 { return None; }
+        self.rows -= 1;
+        let (row, rest) = self.rest.take_unwrap().mut_split_at(self.m);
+        self.rest = Some(rest);
+        Some(row)
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        (self.rows, Some(self.rows))
+    }
+}
+
 pub struct ColumnIterator<'a, T> {
     priv mat: &'a Mat2<T>,
     priv col: uint,
@@ -36,9 +74,10 @@ pub struct ColumnIterator<'a, T> {
 
 impl<'a, T> Iterator<&'a T> for ColumnIterator<'a, T> {
     fn next(&mut self) -> Option<&'a T> {
-        let r = match self.mat.data.get_opt(self.i) {
-            Some(s) => s.get_opt(self.col),
-            None => None
+        let r = if self.i < self.mat.n && self.col < self.mat.m {
+            self.mat.data.get_opt(self.i * self.mat.m + self.col)
+        } else {
+            None
         };
 
         // handle overflow
@@ -47,27 +86,450 @@ impl<'a, T> Iterator<&'a T> for ColumnIterator<'a, T> {
     }
 }
 
-// TODO: remove clone bound?
-impl<T: Default+Clone> Mat2<T> {
+/// Iterator down a diagonal of a matrix. Made by `Mat2::diag_iter` and `Mat2::diag_offset_iter`.
+pub struct DiagIterator<'a, T> {
+    priv mat: &'a Mat2<T>,
+    priv i: uint,
+    priv j: uint,
+}
+
+impl<'a, T> Iterator<&'a T> for DiagIterator<'a, T> {
+    fn next(&mut self) -> Option<&'a T> {
+        let r = self.mat.get_opt(self.i, self.j);
+        if r.is_some() {
+            self.i += 1;
+            self.j += 1;
+        }
+        r
+    }
+}
+
+/// Iterator over the elements of a matrix in row-major order, along with their coordinates.
+/// Made by `Mat2::enumerate_iter`.
+pub struct EnumerateIterator<'a, T> {
+    priv mat: &'a Mat2<T>,
+    priv k: uint,
+}
+
+impl<'a, T> Iterator<((uint, uint), &'a T)> for EnumerateIterator<'a, T> {
+    fn next(&mut self) -> Option<((uint, uint), &'a T)> {
+        let k = self.k;
+        match self.mat.data.get_opt(k) {
+            Some(x) => {
+                self.k += 1;
+                Some(((k / self.mat.m, k % self.mat.m), x))
+            }
+            None => None
+        }
+    }
+
+    fn size_hint(&self) -> (uint, Option<uint>) {
+        let left = self.mat.data.len() - self.k;
+        (left, Some(left))
+    }
+}
+
+/// A read-only view of every `row_step`-th row and every `col_step`-th column of a matrix,
+/// starting from the top left. Made by `Mat2::step_view`; nothing is copied until `to_mat`.
+pub struct MatView<'a, T> {
+    priv mat: &'a Mat2<T>,
+    priv rows: uint,
+    priv cols: uint,
+    priv row_step: uint,
+    priv col_step: uint,
+}
+
+impl<'a, T> MatView<'a, T> {
+    /// Return the dimensions of the view.
+    pub fn get_dimension(&self) -> Shape {
+        Shape { rows: self.rows, cols: self.cols }
+    }
+
+    /// Get element (i, j) of the view, which is element (i * row_step, j * col_step) of the
+    /// matrix. Returns None if it's out of bounds.
+    pub fn get_opt(&self, i: uint, j: uint) -> Option<&'a T> {
+        if i >= self.rows || j >= self.cols {
+            None
+        } else {
+            Some(self.mat.get(i * self.row_step, j * self.col_step))
+        }
+    }
+
+    /// Get element (i, j) of the view, failing if it's out of bounds.
+    pub fn get(&self, i: uint, j: uint) -> &'a T {
+        match self.get_opt(i, j) {
+            Some(x) => x,
+            None => fail!("MatView::get: ({}, {}) is out of bounds for a {}x{} view",
+                          i, j, self.rows, self.cols)
+        }
+    }
+
+    /// A view of every `row_step`-th row and `col_step`-th column of this view.
+    pub fn step_view(&self, row_step: uint, col_step: uint) -> MatView<'a, T> {
+        assert!(row_step > 0 && col_step > 0, "step_view: steps must be positive");
+        MatView {
+            mat: self.mat,
+            rows: (self.rows + row_step - 1) / row_step,
+            cols: (self.cols + col_step - 1) / col_step,
+            row_step: self.row_step * row_step,
+            col_step: self.col_step * col_step,
+        }
+    }
+}
+
+impl<'a, T: Clone> MatView<'a, T> {
+    /// Copy the elements of the view into a new matrix.
+    pub fn to_mat(&self) -> Mat2<T> {
+        Mat2::new_with(self.rows, self.cols, |i, j| self.get(i, j).clone())
+    }
+}
+
+/// A read-only view of an (n x m) row-major matrix that lives in memory the view doesn't own,
+/// like a buffer from C code or a memory-mapped file, where consecutive rows start `stride`
+/// elements apart. Nothing is copied until `to_mat`.
+pub struct StridedView<'a, T> {
+    priv data: &'a [T],
+    priv rows: uint,
+    priv cols: uint,
+    priv stride: uint,
+}
+
+impl<'a, T> StridedView<'a, T> {
+    /// View `data` as an (n x m) matrix with rows `stride` elements apart. Returns `None` if
+    /// `stride < m`, or if `data` is too short to hold the last row.
+    pub fn from_slice(data: &'a [T], n: uint, m: uint, stride: uint) -> Option<StridedView<'a, T>> {
+        if stride < m { return None; }
+        let len = if n == 0 { 0 } else { (n - 1) * stride + m };
+        if data.len() < len { return None; }
+        Some(StridedView { data: data.slice_to(len), rows: n, cols: m, stride: stride })
+    }
+
+    /// View the memory at `ptr` as an (n x m) matrix with rows `stride` elements apart, without
+    /// copying it. Fails if `stride < m`.
+    ///
+    /// This is unsafe because `ptr` must be valid for reading `(n - 1) * stride + m` elements,
+    /// and must stay valid and unchanged for as long as the view is alive.
+    pub unsafe fn from_raw_parts(ptr: *T, n: uint, m: uint, stride: uint) -> StridedView<'a, T> {
+        assert!(stride >= m, "from_raw_parts: stride is shorter than a row");
+        let len = if n == 0 { 0 } else { (n - 1) * stride + m };
+        let data: &'a [T] = cast::transmute(Slice { data: ptr, len: len });
+        StridedView { data: data, rows: n, cols: m, stride: stride }
+    }
+
+    /// Return the dimensions of the view.
+    pub fn get_dimension(&self) -> Shape {
+        Shape { rows: self.rows, cols: self.cols }
+    }
+
+    /// The distance between the starts of consecutive rows, in elements.
+    pub fn stride(&self) -> uint {
+        self.stride
+    }
+
+    /// Get element (i, j) of the view. Returns None if it's out of bounds.
+    pub fn get_opt(&self, i: uint, j: uint) -> Option<&'a T> {
+        if i >= self.rows || j >= self.cols {
+            None
+        } else {
+            Some(&self.data[i * self.stride + j])
+        }
+    }
+
+    /// Get element (i, j) of the view, failing if it's out of bounds.
+    pub fn get(&self, i: uint, j: uint) -> &'a T {
+        match self.get_opt(i, j) {
+            Some(x) => x,
+            None => fail!("StridedView::get: ({}, {}) is out of bounds for a {}x{} view",
+                          i, j, self.rows, self.cols)
+        }
+    }
+
+    /// Get row `i` of the view, which is contiguous. Fails if it's out of bounds.
+    pub fn get_row(&self, i: uint) -> &'a [T] {
+        assert!(i < self.rows, "StridedView::get_row: index out of bounds");
+        self.data.slice(i * self.stride, i * self.stride + self.cols)
+    }
+}
+
+impl<'a, T: Clone> StridedView<'a, T> {
+    /// Copy the elements of the view into a new matrix.
+    pub fn to_mat(&self) -> Mat2<T> {
+        Mat2::new_with(self.rows, self.cols, |i, j| self.get(i, j).clone())
+    }
+}
+
+/// A mutable view of a block of consecutive whole rows of a matrix. Made by `Mat2::view_mut`
+/// or `split_rows_at_mut`; since the row blocks of two views from a split never overlap, both
+/// can be written to at the same time, e.g. by different tasks working on different blocks.
+pub struct MatViewMut<'a, T> {
+    priv data: &'a mut [T],
+    /// Number of rows
+    priv n: uint,
+    /// Number of columns
+    priv m: uint,
+}
+
+impl<'a, T> MatViewMut<'a, T> {
+    /// Return the dimensions of the view.
+    pub fn get_dimension(&self) -> Shape {
+        Shape { rows: self.n, cols: self.m }
+    }
+
+    /// Get element (i, j) of the view, failing if it's out of bounds.
+    pub fn get<'b>(&'b self, i: uint, j: uint) -> &'b T {
+        assert!(i < self.n && j < self.m, "MatViewMut::get: index out of bounds");
+        &self.data[i * self.m + j]
+    }
+
+    /// Get a mutable reference to element (i, j) of the view, failing if it's out of bounds.
+    pub fn get_mut<'b>(&'b mut self, i: uint, j: uint) -> &'b mut T {
+        assert!(i < self.n && j < self.m, "MatViewMut::get_mut: index out of bounds");
+        &mut self.data[i * self.m + j]
+    }
+
+    /// Get row `i` of the view, mutably. Fails if it's out of bounds.
+    pub fn get_row_mut<'b>(&'b mut self, i: uint) -> &'b mut [T] {
+        assert!(i < self.n, "MatViewMut::get_row_mut: index out of bounds");
+        self.data.mut_slice(i * self.m, (i + 1) * self.m)
+    }
+
+    /// Split the view into the rows before `i` and the rows from `i` on. Fails if `i` is more
+    /// than the number of rows.
+    pub fn split_rows_at_mut(self, i: uint) -> (MatViewMut<'a, T>, MatViewMut<'a, T>) {
+        let MatViewMut { data, n, m } = self;
+        assert!(i <= n, "split_rows_at_mut: split point is out of bounds");
+        let (top, bottom) = data.mut_split_at(i * m);
+        (MatViewMut { data: top, n: i, m: m }, MatViewMut { data: bottom, n: n - i, m: m })
+    }
+}
+
+impl<T: Default> Mat2<T> {
     /// Create a new (n x m) matrix, using the Default implementation of T
     pub fn new(n: uint, m: uint) -> Mat2<T> {
-        let data = vec::from_elem(n, vec::from_elem(m, Default::default()));
+        Mat2::new_with(n, m, |_, _| Default::default())
+    }
+
+    /// Create a new matrix of the given shape, using the Default implementation of T.
+    pub fn from_shape(shape: Shape) -> Mat2<T> {
+        Mat2::new(shape.rows, shape.cols)
+    }
+
+    /// Create a new (n x m) matrix like `new`, with room for `extra_rows` more rows to be appended
+    /// before it has to reallocate.
+    pub fn with_capacity(n: uint, m: uint, extra_rows: uint) -> Mat2<T> {
+        let mut data = vec::with_capacity((n + extra_rows) * m);
+        for _ in range(0, n * m) {
+            data.push(Default::default());
+        }
 
         Mat2 { data: data, n: n, m: m }
     }
 }
 
+/// How `to_str_opts` writes floating point elements.
+#[deriving(Eq, Clone)]
+pub enum Notation {
+    /// Plain decimal notation, like `1234.5`.
+    Fixed,
+    /// Scientific notation, like `1.2345e3`.
+    Scientific,
+}
+
+/// Options for turning a matrix into text with `to_str_opts`. Formatting a matrix with `{}` uses
+/// the defaults, so big matrices come out with their middle elided.
+#[deriving(Clone)]
+pub struct FormatOpts {
+    /// Digits after the decimal point for floats, or `None` for as many as it takes. Defaults to
+    /// `None`.
+    precision: Option<uint>,
+    /// Decimal or scientific notation for floats. Defaults to `Fixed`.
+    notation: Notation,
+    /// Show at most this many rows, replacing the ones in the middle with "…". Defaults to 20.
+    max_rows: uint,
+    /// Show at most this many columns, replacing the ones in the middle with "…". Defaults to 20.
+    max_cols: uint,
+}
+
+impl Default for FormatOpts {
+    fn default() -> FormatOpts {
+        FormatOpts { precision: None, notation: Fixed, max_rows: 20, max_cols: 20 }
+    }
+}
+
+/// Elements that know how to write themselves out according to a `FormatOpts`. Only floats pay
+/// any attention to the precision and notation.
+pub trait FormatElem {
+    fn format_elem(&self, opts: &FormatOpts) -> ~str;
+}
+
+macro_rules! format_elem_float(
+    ($t:ty, $m:ident) => (
+        impl FormatElem for $t {
+            fn format_elem(&self, opts: &FormatOpts) -> ~str {
+                match (opts.notation, opts.precision) {
+                    (Fixed, None) => $m::to_str(*self),
+                    (Fixed, Some(p)) => $m::to_str_exact(*self, p),
+                    (Scientific, None) => $m::to_str_exp_digits(*self, 17, false),
+                    (Scientific, Some(p)) => $m::to_str_exp_exact(*self, p, false),
+                }
+            }
+        }
+    )
+)
+
+macro_rules! format_elem_int(
+    ($($t:ty),+) => (
+        $(impl FormatElem for $t {
+            fn format_elem(&self, _opts: &FormatOpts) -> ~str {
+                self.to_str()
+            }
+        })+
+    )
+)
+
+format_elem_float!(f32, f32)
+format_elem_float!(f64, f64)
+format_elem_int!(int, i8, i16, i32, i64, uint, u8, u16, u32, u64)
+
+/// The indices of `len` items to show when at most `max` fit, with `None` standing for the
+/// elided ones in the middle.
+fn shown(len: uint, max: uint) -> ~[Option<uint>] {
+    if len <= max {
+        return range(0, len).map(|i| Some(i)).to_owned_vec();
+    }
+    let (head, tail) = ((max + 1) / 2, max / 2);
+    let mut v = range(0, head).map(|i| Some(i)).to_owned_vec();
+    v.push(None);
+    v.push_all_move(range(len - tail, len).map(|i| Some(i)).to_owned_vec());
+    v
+}
+
+impl<T> Mat2<T> {
+    /// Lay the matrix out as text, eliding rows and columns as `opts` says and writing each
+    /// element with `elem`.
+    fn render(&self, opts: &FormatOpts, elem: |&T| -> ~str) -> ~str {
+        let cols = shown(self.m, opts.max_cols);
+        let mut s = ~"[\n";
+        for i in shown(self.n, opts.max_rows).move_iter() {
+            match i {
+                Some(i) => {
+                    for j in cols.iter() {
+                        match *j {
+                            Some(j) => s.push_str(elem(self.get(i, j))),
+                            None => s.push_str("…")
+                        }
+                        s.push_str(" ");
+                    }
+                }
+                None => s.push_str("… ")
+            }
+            s.push_str("\n");
+        }
+        s.push_str("]\n");
+        s
+    }
+}
+
+impl<T: FormatElem> Mat2<T> {
+    /// Turn the matrix into text according to `opts`.
+    pub fn to_str_opts(&self, opts: &FormatOpts) -> ~str {
+        self.render(opts, |x| x.format_elem(opts))
+    }
+}
+
 impl<T: fmt::Default> fmt::Default for Mat2<T> {
     fn fmt(s: &Mat2<T>, f: &mut fmt::Formatter) {
-        let b = &mut f.buf;
-        b.write(bytes!("[\n"));
-        for row in s.row_iter() {
-            for it in row.iter() {
-                write!(*b, "{} ", *it);
-            }
-            b.write(bytes!("\n"));
+        let text = s.render(&Default::default(), |x| format!("{}", *x));
+        f.buf.write(text.as_bytes());
+    }
+}
+
+/// The shape of a matrix. The fields are named so that there's no mixing up which number is
+/// the rows and which is the columns.
+#[deriving(Eq, Clone)]
+pub struct Shape {
+    /// Number of rows
+    rows: uint,
+    /// Number of columns
+    cols: uint,
+}
+
+impl Shape {
+    /// The shape of a matrix with `rows` rows and `cols` columns.
+    pub fn new(rows: uint, cols: uint) -> Shape {
+        Shape { rows: rows, cols: cols }
+    }
+}
+
+/// Which LaTeX environment `to_latex` wraps the matrix in.
+#[deriving(Eq, Clone)]
+pub enum LatexEnv {
+    /// `pmatrix`, with round brackets.
+    PMatrix,
+    /// `bmatrix`, with square brackets.
+    BMatrix,
+}
+
+fn format_row<T: fmt::Default>(row: &[T], sep: &str) -> ~str {
+    row.iter().map(|x| format!("{}", *x)).to_owned_vec().connect(sep)
+}
+
+impl<T: fmt::Default> Mat2<T> {
+    /// Render the matrix as LaTeX, ready to be pasted into a math environment. Elements are
+    /// written with their `fmt::Default` formatting.
+    pub fn to_latex(&self, env: LatexEnv) -> ~str {
+        let name = match env { PMatrix => "pmatrix", BMatrix => "bmatrix" };
+        let rows = self.row_iter().map(|r| format_row(r, " & ")).to_owned_vec();
+        let mut s = ~"\\begin{";
+        s.push_str(name);
+        s.push_str("}\n");
+        s.push_str(rows.connect(" \\\\\n"));
+        s.push_str("\n\\end{");
+        s.push_str(name);
+        s.push_str("}");
+        s
+    }
+
+    /// Render the matrix as a Markdown table. Markdown tables need a header, so the columns are
+    /// headed with their (0-based) indices.
+    pub fn to_markdown_table(&self) -> ~str {
+        let header = range(0, self.m).map(|j| j.to_str()).to_owned_vec();
+        let rule = range(0, self.m).map(|_| ~"---").to_owned_vec();
+
+        let mut s = format!("| {} |\n|{}|\n", header.connect(" | "), rule.connect("|"));
+        for row in self.row_iter() {
+            s.push_str(format!("| {} |\n", format_row(row, " | ")));
         }
-        b.write(bytes!("]\n"));
+        s
+    }
+}
+
+impl<T: Zero> Mat2<T> {
+    /// An (n x m) matrix of zeros. Unlike `new`, this only needs `T: Zero`.
+    pub fn zeros(n: uint, m: uint) -> Mat2<T> {
+        Mat2::new_with(n, m, |_, _| zero())
+    }
+}
+
+impl<T: One> Mat2<T> {
+    /// An (n x m) matrix of ones.
+    pub fn ones(n: uint, m: uint) -> Mat2<T> {
+        Mat2::new_with(n, m, |_, _| one())
+    }
+}
+
+impl<T: Zero + One> Mat2<T> {
+    /// The (n x n) identity matrix.
+    pub fn identity(n: uint) -> Mat2<T> {
+        Mat2::new_with(n, n, |i, j| if i == j { one() } else { zero() })
+    }
+}
+
+impl<T: Zero + Clone> Mat2<T> {
+    /// A square matrix with `d` along the diagonal and zeros everywhere else.
+    pub fn from_diag(d: &[T]) -> Mat2<T> {
+        let n = d.len();
+        Mat2::new_with(n, n, |i, j| if i == j { d[i].clone() } else { zero() })
     }
 }
 
@@ -75,11 +537,45 @@ impl<T> Mat2<T> {
     /// Create a new (n x m) matrix, using `f` to create each element. `f` is given the coordinate
     /// (row, column) for each element it's constructing.
     pub fn new_with(n: uint, m: uint, f: |uint, uint| -> T) -> Mat2<T> {
-        let data = vec::from_fn(n, |n| vec::from_fn(m, |m| f(n,m)));
+        let mut data = vec::with_capacity(n * m);
+        for i in range(0, n) {
+            for j in range(0, m) {
+                data.push(f(i, j));
+            }
+        }
 
         Mat2 { data: data, n: n, m: m }
     }
 
+    /// Turn this into an (n x m) matrix with elements from `f`, as for `new_with`, but reusing
+    /// the existing storage when it's big enough instead of allocating.
+    pub fn refill(&mut self, n: uint, m: uint, f: |uint, uint| -> T) {
+        self.data.truncate(0);
+        self.data.reserve(n * m);
+        for i in range(0, n) {
+            for j in range(0, m) {
+                self.data.push(f(i, j));
+            }
+        }
+        self.n = n;
+        self.m = m;
+    }
+
+    /// Create a new matrix of the given shape, using `f` to create each element as for
+    /// `new_with`.
+    pub fn from_shape_fn(shape: Shape, f: |uint, uint| -> T) -> Mat2<T> {
+        Mat2::new_with(shape.rows, shape.cols, f)
+    }
+
+    /// Create an (n x m) matrix from its elements in row-major order, the layout it's stored in,
+    /// so `data` is used as is. Returns `None` if `data` doesn't have exactly `n * m` elements.
+    pub fn from_flat(n: uint, m: uint, data: ~[T]) -> Option<Mat2<T>> {
+        if data.len() != n * m {
+            return None;
+        }
+        Some(Mat2 { data: data, n: n, m: m })
+    }
+
     /// Create a new matrix from a vector. Returns None if the inner vectors don't all have the same
     /// length, or if the vector is empty.
     pub fn from_vec(m: ~[~[T]]) -> Option<Mat2<T>> {
@@ -91,253 +587,1984 @@ impl<T> Mat2<T> {
 
         let l = m[0].len();
 
-        if m.iter().all(|x| x.len() == l) {
-            Some(Mat2 { data: m, n: n, m: l })
+        if !m.iter().all(|x| x.len() == l) {
+            return None;
+        }
+
+        let mut data = vec::with_capacity(n * l);
+        for row in m.move_iter() {
+            data.push_all_move(row);
+        }
+        Some(Mat2 { data: data, n: n, m: l })
+    }
+
+    /// The index into `data` of row `i`, column `j`. Fails if either is out of bounds.
+    fn idx(&self, i: uint, j: uint) -> uint {
+        if i >= self.n || j >= self.m {
+            fail!("index ({}, {}) out of bounds for a {}x{} matrix", i, j, self.n, self.m);
+        }
+        i * self.m + j
+    }
+
+    /// Return the dimensions of the matrix.
+    pub fn get_dimension(&self) -> Shape {
+        Shape { rows: self.n, cols: self.m }
+    }
+
+    /// Swap two rows. Fails if either of the indices are out of bounds.
+    pub fn swap_rows(&mut self, i: uint, j: uint) {
+        assert!(i < self.n && j < self.n, "swap_rows: row index out of bounds");
+        if i == j { return; }
+        for k in range(0, self.m) {
+            self.data.swap(i * self.m + k, j * self.m + k);
+        }
+    }
+
+    /// Swap two rows, or return an `OutOfBounds` error if either of the indices are out of
+    /// bounds.
+    pub fn try_swap_rows(&mut self, i: uint, j: uint) -> LinalgResult<()> {
+        if i.max(&j) >= self.n { return Err(OutOfBounds(i.max(&j), self.n)); }
+        self.swap_rows(i, j);
+        Ok(())
+    }
+
+    /// Swap two columns. Fails if either of the indices are out of bounds.
+    pub fn swap_cols(&mut self, i: uint, j: uint) {
+        assert!(i < self.m && j < self.m, "swap_cols: column index out of bounds");
+        if i == j { return; }
+        for k in range(0, self.n) {
+            self.data.swap(k * self.m + i, k * self.m + j);
+        }
+    }
+
+    /// Set a row to the given vector. Fails if `i` is out of bounds, or if `r` isn't as long as a
+    /// row.
+    pub fn set_row(&mut self, i: uint, r: ~[T]) {
+        assert!(i < self.n, "set_row: row index out of bounds");
+        assert_eq!(r.len(), self.m);
+        for (k, x) in r.move_iter().enumerate() {
+            self.data[i * self.m + k] = x;
+        }
+    }
+
+    /// Set a row to the given vector, or return an `OutOfBounds` error if `i` is out of bounds,
+    /// or `WrongLength` if `r` isn't as long as a row.
+    pub fn try_set_row(&mut self, i: uint, r: ~[T]) -> LinalgResult<()> {
+        if i >= self.n { return Err(OutOfBounds(i, self.n)); }
+        if r.len() != self.m { return Err(WrongLength(self.m, r.len())); }
+        self.set_row(i, r);
+        Ok(())
+    }
+
+    /// Get the row at `i` as a slice. Fails if `i` is out of bounds.
+    pub fn get_row<'a>(&'a self, i: uint) -> &'a [T] {
+        match self.get_row_opt(i) {
+            Some(r) => r,
+            None => fail!("get_row: row {} out of bounds for a matrix with {} rows", i, self.n)
+        }
+    }
+
+    /// Get the row at `i` as a mutable slice, to change it in place. Fails if `i` is out of
+    /// bounds.
+    pub fn get_row_mut<'a>(&'a mut self, i: uint) -> &'a mut [T] {
+        assert!(i < self.n,
+                "get_row_mut: row {} out of bounds for a matrix with {} rows", i, self.n);
+        self.data.mut_slice(i * self.m, (i + 1) * self.m)
+    }
+
+    /// Get a reference to the element at row `i`, column `j` (both starting at 0). Returns `None`
+    /// if `i` or `j` are out of bounds.
+    pub fn get_opt<'a>(&'a self, i: uint, j: uint) -> Option<&'a T> {
+        if i >= self.n || j >= self.m {
+            None
+        } else {
+            Some(&self.data[i * self.m + j])
+        }
+    }
+
+    /// Get a reference to the element at row `i`, column `j` (both starting at 0). Fails if `i` or
+    /// `j` are out of bounds.
+    pub fn get<'a>(&'a self, i: uint, j: uint) -> &'a T {
+        &self.data[self.idx(i, j)]
+    }
+
+    /// Get a mutable reference to the element at row `i`, column `j`. Fails if `i` or `j` are
+    /// out of bounds.
+    pub fn get_mut<'a>(&'a mut self, i: uint, j: uint) -> &'a mut T {
+        let k = self.idx(i, j);
+        &mut self.data[k]
+    }
+
+    /// Set the element at row `i`, column `j` to `v`. Fails if `i` or `j` are out of bounds.
+    pub fn set(&mut self, i: uint, j: uint, v: T) {
+        let k = self.idx(i, j);
+        self.data[k] = v;
+    }
+
+    /// Get the row at `i` as a slice. Returns `None` if `i` is out of bounds.
+    pub fn get_row_opt<'a>(&'a self, i: uint) -> Option<&'a [T]> {
+        if i < self.n {
+            Some(self.data.slice(i * self.m, (i + 1) * self.m))
         } else {
             None
         }
     }
 
-    /// Return the dimensions of the matrix, (m, n)
-    pub fn get_dimension(&self) -> (uint, uint) {
-        (self.m, self.n)
+    /// Append a column to the matrix. Returns true if the insert succeeded, false otherwise.
+    pub fn append_column(&mut self, column: ~[T]) -> bool {
+        if self.n != column.len() { return false; }
+
+        let old = replace(&mut self.data, vec::with_capacity(self.n * (self.m + 1)));
+        let mut old = old.move_iter();
+        for itm in column.move_iter() {
+            for _ in range(0, self.m) {
+                self.data.push(old.next().unwrap());
+            }
+            self.data.push(itm);
+        }
+
+        self.m += 1;
+
+        true
+    }
+
+    /// Append a row to the matrix. Returns true if the insert succeeded, false otherwise.
+    pub fn append_row(&mut self, row: ~[T]) -> bool {
+        if self.m != row.len() { return false; }
+
+        self.n += 1;
+
+        self.data.push_all_move(row);
+
+        true
+    }
+
+    /// Insert a row so that it becomes row `i`, moving the rows from `i` on down by one. Returns
+    /// true if the insert succeeded, false if the row is the wrong length or `i` is past the
+    /// last row.
+    pub fn insert_row(&mut self, i: uint, row: ~[T]) -> bool {
+        if self.m != row.len() || i > self.n { return false; }
+
+        let old = replace(&mut self.data, vec::with_capacity((self.n + 1) * self.m));
+        let mut old = old.move_iter();
+        for _ in range(0, i * self.m) {
+            self.data.push(old.next().unwrap());
+        }
+        self.data.push_all_move(row);
+        for itm in old {
+            self.data.push(itm);
+        }
+
+        self.n += 1;
+
+        true
+    }
+
+    /// Insert a column so that it becomes column `j`, moving the columns from `j` on right by
+    /// one. Returns true if the insert succeeded, false if the column is the wrong length or `j`
+    /// is past the last column.
+    pub fn insert_col(&mut self, j: uint, column: ~[T]) -> bool {
+        if self.n != column.len() || j > self.m { return false; }
+
+        let old = replace(&mut self.data, vec::with_capacity(self.n * (self.m + 1)));
+        let mut old = old.move_iter();
+        for itm in column.move_iter() {
+            for _ in range(0, j) {
+                self.data.push(old.next().unwrap());
+            }
+            self.data.push(itm);
+            for _ in range(j, self.m) {
+                self.data.push(old.next().unwrap());
+            }
+        }
+
+        self.m += 1;
+
+        true
+    }
+
+    /// Remove row `i` and return it, moving the rows after it up by one. Fails if `i` is out of
+    /// bounds.
+    pub fn remove_row(&mut self, i: uint) -> ~[T] {
+        assert!(i < self.n, "remove_row: row index out of bounds");
+
+        let old = replace(&mut self.data, vec::with_capacity((self.n - 1) * self.m));
+        let mut row = vec::with_capacity(self.m);
+        for (k, itm) in old.move_iter().enumerate() {
+            if k / self.m == i { row.push(itm); } else { self.data.push(itm); }
+        }
+
+        self.n -= 1;
+
+        row
+    }
+
+    /// Remove column `j` and return it, moving the columns after it left by one. Fails if `j` is
+    /// out of bounds.
+    pub fn remove_col(&mut self, j: uint) -> ~[T] {
+        assert!(j < self.m, "remove_col: column index out of bounds");
+
+        let old = replace(&mut self.data, vec::with_capacity(self.n * (self.m - 1)));
+        let mut column = vec::with_capacity(self.n);
+        for (k, itm) in old.move_iter().enumerate() {
+            if k % self.m == j { column.push(itm); } else { self.data.push(itm); }
+        }
+
+        self.m -= 1;
+
+        column
+    }
+
+    /// ["Augment"](http://goo.gl/Q1hIuC) a matrix with this one. Takes the columns of `other` and
+    /// appends them to this matrix. Returns true if the augment succeeded, false otherwise.
+    pub fn augment(&mut self, other: Mat2<T>) -> bool {
+        if self.n != other.n { return false; }
+
+        let (om, w) = (other.m, self.m + other.m);
+        let old = replace(&mut self.data, vec::with_capacity(self.n * w));
+        let (mut a, mut b) = (old.move_iter(), other.data.move_iter());
+        for _ in range(0, self.n) {
+            for _ in range(0, self.m) {
+                self.data.push(a.next().unwrap());
+            }
+            for _ in range(0, om) {
+                self.data.push(b.next().unwrap());
+            }
+        }
+
+        self.m = w;
+
+        true
+    }
+
+    /// Stack the rows of `other` underneath this matrix, the vertical counterpart of `augment`.
+    /// Returns true if the stack succeeded, false if the matrices have different numbers of
+    /// columns.
+    pub fn stack(&mut self, other: Mat2<T>) -> bool {
+        if self.m != other.m { return false; }
+
+        self.n += other.n;
+
+        self.data.push_all_move(other.data);
+
+        true
+    }
+
+    /// Make room for at least `k` more rows to be appended without reallocating.
+    pub fn reserve_rows(&mut self, k: uint) {
+        self.data.reserve_additional(k * self.m);
+    }
+
+    /// How many rows fit in the matrix's current allocation. A matrix with no columns never needs
+    /// to allocate, and says `uint::max_value`.
+    pub fn row_capacity(&self) -> uint {
+        if self.m == 0 { uint::max_value } else { self.data.capacity() / self.m }
+    }
+
+    /// Give back any memory that's been reserved but isn't holding rows.
+    pub fn shrink_to_fit(&mut self) {
+        self.data.shrink_to_fit();
+    }
+
+    /// A pointer to the first element. The elements are contiguous and row-major, so element
+    /// (i, j) is at offset `i * cols + j`. The pointer is only valid for as long as the matrix
+    /// isn't resized or dropped.
+    pub fn as_ptr(&self) -> *T {
+        self.data.as_ptr()
+    }
+
+    /// A mutable pointer to the first element, laid out as for `as_ptr`.
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.data.as_mut_ptr()
+    }
+
+    /// Describe the matrix for C code as (pointer, rows, columns, row stride), where the row
+    /// stride is the distance between the starts of consecutive rows, in elements. The stride is
+    /// currently always equal to the number of columns, but C code should use it rather than
+    /// assume that. The pointer is borrowed: it is only valid while the matrix is alive and not
+    /// resized, and C code must not free it.
+    pub fn export_c(&self) -> (*T, uint, uint, uint) {
+        (self.data.as_ptr(), self.n, self.m, self.m)
+    }
+
+    /// A view of every `row_step`-th row and every `col_step`-th column, starting with row and
+    /// column 0. E.g. `step_view(2, 1)` is the even-numbered rows. Fails if either step is 0.
+    pub fn step_view<'a>(&'a self, row_step: uint, col_step: uint) -> MatView<'a, T> {
+        assert!(row_step > 0 && col_step > 0, "step_view: steps must be positive");
+        MatView {
+            mat: self,
+            rows: (self.n + row_step - 1) / row_step,
+            cols: (self.m + col_step - 1) / col_step,
+            row_step: row_step,
+            col_step: col_step,
+        }
+    }
+
+    /// A mutable view of the whole matrix.
+    pub fn view_mut<'a>(&'a mut self) -> MatViewMut<'a, T> {
+        MatViewMut { data: self.data.as_mut_slice(), n: self.n, m: self.m }
+    }
+
+    /// Split the matrix into two mutable views, of the rows before `i` and the rows from `i` on.
+    /// Fails if `i` is more than the number of rows.
+    pub fn split_rows_at_mut<'a>(&'a mut self, i: uint) -> (MatViewMut<'a, T>, MatViewMut<'a, T>) {
+        self.view_mut().split_rows_at_mut(i)
+    }
+
+    /// Transpose a square matrix in place. Fails if it isn't square (use `transpose` or
+    /// `transpose_into` for those).
+    pub fn transpose_inplace(&mut self) {
+        assert!(self.n == self.m, "transpose_inplace: matrix must be square");
+        for i in range(0, self.n) {
+            for j in range(i + 1, self.m) {
+                self.data.swap(i * self.m + j, j * self.m + i);
+            }
+        }
+    }
+
+    /// Iterate over the elements of a matrix, in row-major order.
+    pub fn iter<'a>(&'a self) -> vec::VecIterator<'a, T> {
+        self.data.iter()
+    }
+
+    /// Iterate over the elements of a matrix in row-major order, as `((row, col), &element)`.
+    pub fn enumerate_iter<'a>(&'a self) -> EnumerateIterator<'a, T> {
+        EnumerateIterator {
+            mat: self,
+            k: 0
+        }
+    }
+
+    /// Iterate over the rows of a matrix.
+    pub fn row_iter<'a>(&'a self) -> RowIterator<'a, T> {
+        RowIterator {
+            mat: self,
+            i: 0
+        }
+    }
+
+    /// Iterate over the rows of a matrix as mutable slices.
+    pub fn row_iter_mut<'a>(&'a mut self) -> MutRowIterator<'a, T> {
+        MutRowIterator {
+            rest: Some(self.data.as_mut_slice()),
+            rows: self.n,
+            m: self.m
+        }
+    }
+
+    /// Iterate over the items column `col` (0-based) of a matrix. This does *NOT* iterate over all
+    /// columns.  If you want that, transpose the matrix and `row_iter` over that (it requires the
+    /// same amount of work).
+    pub fn column_iter<'a>(&'a self, col: uint) -> ColumnIterator<'a, T> {
+        ColumnIterator {
+            mat: self,
+            col: col,
+            i: 0
+        }
+    }
+
+    /// Iterate down the main diagonal of a matrix.
+    pub fn diag_iter<'a>(&'a self) -> DiagIterator<'a, T> {
+        self.diag_offset_iter(0)
+    }
+
+    /// Iterate down the `k`-th diagonal of a matrix: the main diagonal for `k = 0`, the ones
+    /// above it for `k > 0`, and the ones below it for `k < 0`. If it's entirely outside the
+    /// matrix, there's nothing to iterate over.
+    pub fn diag_offset_iter<'a>(&'a self, k: int) -> DiagIterator<'a, T> {
+        let (i, j) = if k >= 0 { (0, k as uint) } else { ((-k) as uint, 0) };
+        DiagIterator {
+            mat: self,
+            i: i,
+            j: j
+        }
+    }
+
+    /// Call `f` on each row in turn, collecting what it returns.
+    pub fn apply_rows<U>(&self, f: |&[T]| -> U) -> ~[U] {
+        self.row_iter().map(|r| f(r)).to_owned_vec()
+    }
+
+    /// Fold `f` over each row, starting from `init` every time, and collect the results.
+    pub fn fold_rows<U: Clone>(&self, init: U, f: |U, &T| -> U) -> ~[U] {
+        let mut out = vec::with_capacity(self.n);
+        for r in self.row_iter() {
+            let mut acc = init.clone();
+            for x in r.iter() {
+                acc = f(acc, x);
+            }
+            out.push(acc);
+        }
+        out
+    }
+
+    /// Fold `f` down each column, starting from `init` every time, and collect the results.
+    pub fn fold_cols<U: Clone>(&self, init: U, f: |U, &T| -> U) -> ~[U] {
+        let mut out = vec::with_capacity(self.m);
+        for j in range(0, self.m) {
+            let mut acc = init.clone();
+            for x in self.column_iter(j) {
+                acc = f(acc, x);
+            }
+            out.push(acc);
+        }
+        out
+    }
+
+    /// Fold `f` over every element, in row-major order.
+    pub fn fold<U>(&self, init: U, f: |U, &T| -> U) -> U {
+        let mut acc = init;
+        for x in self.data.iter() {
+            acc = f(acc, x);
+        }
+        acc
+    }
+}
+
+impl<T: Zero + Add<T, T> + Clone> Mat2<T> {
+    /// The sum of all the elements.
+    pub fn sum(&self) -> T {
+        self.fold(zero(), |s, x| s + *x)
+    }
+
+    /// The trace, the sum of the main diagonal. Fails if the matrix isn't square.
+    pub fn trace(&self) -> T {
+        assert!(self.n == self.m, "trace: matrix must be square");
+        self.diag_iter().fold(zero(), |s, x| s + *x)
+    }
+
+    /// The sum of each row.
+    pub fn row_sums(&self) -> ~[T] {
+        self.fold_rows(zero(), |s, x| s + *x)
+    }
+
+    /// The sum of each column.
+    pub fn col_sums(&self) -> ~[T] {
+        self.fold_cols(zero(), |s, x| s + *x)
+    }
+}
+
+impl<T: Ord + Clone> Mat2<T> {
+    /// The smallest element, or `None` if the matrix is empty.
+    pub fn min(&self) -> Option<T> {
+        self.fold(None, |m: Option<T>, x| match m {
+            Some(m) => Some(if *x < m { x.clone() } else { m }),
+            None => Some(x.clone())
+        })
+    }
+
+    /// The biggest element, or `None` if the matrix is empty.
+    pub fn max(&self) -> Option<T> {
+        self.fold(None, |m: Option<T>, x| match m {
+            Some(m) => Some(if *x > m { x.clone() } else { m }),
+            None => Some(x.clone())
+        })
+    }
+}
+
+impl Mat2<f64> {
+    /// The mean of all the elements, which is NaN for an empty matrix.
+    pub fn mean(&self) -> f64 {
+        self.sum() / (self.n * self.m) as f64
+    }
+
+    /// The mean of each row.
+    pub fn row_means(&self) -> ~[f64] {
+        self.row_sums().map(|s| *s / self.m as f64)
+    }
+
+    /// The mean of each column.
+    pub fn col_means(&self) -> ~[f64] {
+        self.col_sums().map(|s| *s / self.n as f64)
+    }
+}
+
+impl<T: Clone> Mat2<T> {
+    /// The transpose, as a new matrix.
+    pub fn transpose(&self) -> Mat2<T> {
+        Mat2::new_with(self.m, self.n, |i, j| self.data[j * self.m + i].clone())
+    }
+
+    /// The matrix `[self | other]`, with the columns of `other` after the columns of this one,
+    /// e.g. the augmented matrix of a system. Returns `None` if they have different numbers of
+    /// rows.
+    pub fn hstack(&self, other: &Mat2<T>) -> Option<Mat2<T>> {
+        if self.n != other.n { return None; }
+        Some(Mat2::new_with(self.n, self.m + other.m, |i, j| {
+            if j < self.m { self.data[i * self.m + j].clone() } else { other.at(i, j - self.m) }
+        }))
+    }
+
+    /// The matrix with the rows of `other` underneath the rows of this one. Returns `None` if
+    /// they have different numbers of columns.
+    pub fn vstack(&self, other: &Mat2<T>) -> Option<Mat2<T>> {
+        if self.m != other.m { return None; }
+        let mut data = self.data.clone();
+        data.push_all(other.data);
+        Some(Mat2 { data: data, n: self.n + other.n, m: self.m })
+    }
+
+    /// A copy of the block made of the rows in `rows` and the columns in `cols`, e.g.
+    /// `a.submatrix(range(1, 3), range(0, a.get_dimension().cols))` is rows 1 and 2. Fails if
+    /// the ranges go past the edge of the matrix.
+    pub fn submatrix(&self, rows: Range<uint>, cols: Range<uint>) -> Mat2<T> {
+        let rows = rows.to_owned_vec();
+        let cols = cols.to_owned_vec();
+        assert!(rows.iter().all(|&i| i < self.n) && cols.iter().all(|&j| j < self.m),
+                "submatrix: range out of bounds");
+        Mat2::new_with(rows.len(), cols.len(), |i, j| {
+            self.data[rows[i] * self.m + cols[j]].clone()
+        })
+    }
+
+    /// Copies of the rows before `i` and the rows from `i` on. `split_rows_at_mut` does the
+    /// same without copying, as views. Fails if `i` is more than the number of rows.
+    pub fn split_at_row(&self, i: uint) -> (Mat2<T>, Mat2<T>) {
+        assert!(i <= self.n, "split_at_row: split point is out of bounds");
+        (self.submatrix(range(0, i), range(0, self.m)),
+         self.submatrix(range(i, self.n), range(0, self.m)))
+    }
+
+    /// Copies of the columns before `j` and the columns from `j` on. Fails if `j` is more than
+    /// the number of columns.
+    pub fn split_at_col(&self, j: uint) -> (Mat2<T>, Mat2<T>) {
+        assert!(j <= self.m, "split_at_col: split point is out of bounds");
+        (self.submatrix(range(0, self.n), range(0, j)),
+         self.submatrix(range(0, self.n), range(j, self.m)))
+    }
+
+    /// Partition the matrix into blocks, cutting it before each of the rows in `row_splits` and
+    /// each of the columns in `col_splits`, which have to be in increasing order. Block `[r][c]`
+    /// of the result is the `r`-th band of rows and the `c`-th band of columns, so e.g.
+    /// `blocks([k], [k])` gives the four blocks `[[A11, A12], [A21, A22]]` of block elimination.
+    /// Fails if the splits are out of order or out of bounds.
+    pub fn blocks(&self, row_splits: &[uint], col_splits: &[uint]) -> ~[~[Mat2<T>]] {
+        fn bounds(splits: &[uint], len: uint) -> ~[(uint, uint)] {
+            let mut cuts = ~[0];
+            cuts.push_all(splits);
+            cuts.push(len);
+            assert!(cuts.window_iter(2).all(|w| w[0] <= w[1]),
+                    "blocks: splits out of order or out of bounds");
+            cuts.window_iter(2).map(|w| (w[0], w[1])).to_owned_vec()
+        }
+        let rows = bounds(row_splits, self.n);
+        let cols = bounds(col_splits, self.m);
+        rows.map(|&(r0, r1)| {
+            cols.map(|&(c0, c1)| self.submatrix(range(r0, r1), range(c0, c1)))
+        })
+    }
+
+    /// The minor at `(i, j)`: the matrix with row `i` and column `j` deleted. Fails if `i` or `j`
+    /// are out of bounds.
+    pub fn minor(&self, i: uint, j: uint) -> Mat2<T> {
+        assert!(i < self.n && j < self.m, "minor: index out of bounds");
+        Mat2::new_with(self.n - 1, self.m - 1, |r, c| {
+            let r = if r < i { r } else { r + 1 };
+            let c = if c < j { c } else { c + 1 };
+            self.data[r * self.m + c].clone()
+        })
+    }
+
+    /// A copy of the main diagonal.
+    pub fn diag(&self) -> ~[T] {
+        self.diag_iter().map(|x| x.clone()).to_owned_vec()
+    }
+
+    /// A copy of the `k`-th diagonal, numbered as for `diag_offset_iter`.
+    pub fn diag_offset(&self, k: int) -> ~[T] {
+        self.diag_offset_iter(k).map(|x| x.clone()).to_owned_vec()
+    }
+
+    /// Set the main diagonal to `d`. Fails if `d` isn't as long as the diagonal.
+    pub fn set_diag(&mut self, d: &[T]) {
+        self.set_diag_offset(0, d);
+    }
+
+    /// Set the `k`-th diagonal (numbered as for `diag_offset_iter`) to `d`, e.g. to fill in the
+    /// bands of a banded matrix. Fails if `d` isn't as long as that diagonal.
+    pub fn set_diag_offset(&mut self, k: int, d: &[T]) {
+        let (i, j) = if k >= 0 { (0, k as uint) } else { ((-k) as uint, 0) };
+        let len = if i < self.n && j < self.m { (self.n - i).min(&(self.m - j)) } else { 0 };
+        assert!(d.len() == len, "set_diag_offset: expected {} entries, got {}", len, d.len());
+        for (t, x) in d.iter().enumerate() {
+            self.data[(i + t) * self.m + j + t] = x.clone();
+        }
+    }
+
+    /// A copy of the element at row `i`, column `j`. Fails if `i` or `j` are out of bounds.
+    pub fn at(&self, i: uint, j: uint) -> T {
+        self.get(i, j).clone()
+    }
+
+    /// A copy of the element at row `i`, column `j`, or `None` if `i` or `j` are out of bounds.
+    pub fn at_opt(&self, i: uint, j: uint) -> Option<T> {
+        self.get_opt(i, j).map(|x| x.clone())
+    }
+
+    /// Call `f` on each column in turn, collecting what it returns. Columns aren't contiguous,
+    /// so each is copied into a scratch vector first.
+    pub fn apply_cols<U>(&self, f: |&[T]| -> U) -> ~[U] {
+        let mut out = vec::with_capacity(self.m);
+        let mut col = vec::with_capacity(self.n);
+        for j in range(0, self.m) {
+            col.truncate(0);
+            for x in self.column_iter(j) {
+                col.push(x.clone());
+            }
+            out.push(f(col));
+        }
+        out
+    }
+
+    /// Create an (n x m) matrix by copying out of row-major memory at `ptr`, where consecutive
+    /// rows start `stride` elements apart. A `stride` bigger than `m` picks out the leading
+    /// columns of a wider matrix. To use the memory in place instead, see
+    /// `StridedView::from_raw_parts`. Fails if `stride < m`.
+    ///
+    /// This is unsafe because `ptr` must be valid for reading `(n - 1) * stride + m` elements.
+    pub unsafe fn copy_from_raw_parts(ptr: *T, n: uint, m: uint, stride: uint) -> Mat2<T> {
+        StridedView::from_raw_parts(ptr, n, m, stride).to_mat()
+    }
+
+    /// The counterpart to `export_c`: view a C matrix described as (pointer, rows, columns, row
+    /// stride) in place, without copying it. This is `StridedView::from_raw_parts` under a name
+    /// that pairs with `export_c`, and is unsafe for the same reason.
+    pub unsafe fn import_c<'a>(ptr: *T, rows: uint, cols: uint,
+                               row_stride: uint) -> StridedView<'a, T> {
+        StridedView::from_raw_parts(ptr, rows, cols, row_stride)
+    }
+
+    /// Copy the elements out in row-major order, the inverse of `from_flat`: element (i, j) ends
+    /// up at index `i * cols + j`.
+    pub fn to_flat(&self) -> ~[T] {
+        self.data.clone()
+    }
+
+    /// Copy the elements out in column-major (Fortran) order, the layout LAPACK and friends
+    /// expect: element (i, j) ends up at index `j * rows + i`.
+    pub fn to_fortran_vec(&self) -> ~[T] {
+        let mut v = vec::with_capacity(self.n * self.m);
+        for j in range(0, self.m) {
+            for i in range(0, self.n) {
+                v.push(self.data[i * self.m + j].clone());
+            }
+        }
+        v
+    }
+
+    /// Create an (n x m) matrix from elements in column-major (Fortran) order, e.g. as handed
+    /// back by LAPACK. Returns `None` if `data` doesn't have exactly `n * m` elements.
+    pub fn from_fortran_vec(n: uint, m: uint, data: ~[T]) -> Option<Mat2<T>> {
+        if data.len() != n * m {
+            return None;
+        }
+        Some(Mat2::new_with(n, m, |i, j| data[j * n + i].clone()))
+    }
+}
+
+impl<T> Mat2<T> {
+    /// Replace each element (i, j) with `f(element, v[j])`.
+    fn broadcast_rows(&mut self, v: &[T], f: |&T, &T| -> T) {
+        assert!(v.len() == self.m, "row vector length doesn't match the number of columns");
+        for idx in range(0, self.data.len()) {
+            self.data[idx] = f(&self.data[idx], &v[idx % self.m]);
+        }
+    }
+
+    /// Replace each element (i, j) with `f(element, v[i])`.
+    fn broadcast_cols(&mut self, v: &[T], f: |&T, &T| -> T) {
+        assert!(v.len() == self.n, "column vector length doesn't match the number of rows");
+        for idx in range(0, self.data.len()) {
+            self.data[idx] = f(&self.data[idx], &v[idx / self.m]);
+        }
+    }
+}
+
+/// Applying a vector to every row or every column. The `_row_vec` methods combine `v[j]` with
+/// column j of every row (so `v` has one entry per column), and the `_col_vec` ones combine
+/// `v[i]` with row i of every column. They all fail if `v` is the wrong length.
+impl<T: Add<T, T> + Sub<T, T> + Mul<T, T> + Div<T, T>> Mat2<T> {
+    /// Add `v` to every row.
+    pub fn add_row_vec(&mut self, v: &[T]) {
+        self.broadcast_rows(v, |a, b| *a + *b);
+    }
+
+    /// Subtract `v` from every row, e.g. to center the columns on their means.
+    pub fn sub_row_vec(&mut self, v: &[T]) {
+        self.broadcast_rows(v, |a, b| *a - *b);
+    }
+
+    /// Multiply every row elementwise by `v`, e.g. to scale each feature.
+    pub fn mul_row_vec(&mut self, v: &[T]) {
+        self.broadcast_rows(v, |a, b| *a * *b);
+    }
+
+    /// Divide every row elementwise by `v`.
+    pub fn div_row_vec(&mut self, v: &[T]) {
+        self.broadcast_rows(v, |a, b| *a / *b);
+    }
+
+    /// Add `v` to every column.
+    pub fn add_col_vec(&mut self, v: &[T]) {
+        self.broadcast_cols(v, |a, b| *a + *b);
+    }
+
+    /// Subtract `v` from every column.
+    pub fn sub_col_vec(&mut self, v: &[T]) {
+        self.broadcast_cols(v, |a, b| *a - *b);
+    }
+
+    /// Multiply every column elementwise by `v`.
+    pub fn mul_col_vec(&mut self, v: &[T]) {
+        self.broadcast_cols(v, |a, b| *a * *b);
+    }
+
+    /// Divide every column elementwise by `v`.
+    pub fn div_col_vec(&mut self, v: &[T]) {
+        self.broadcast_cols(v, |a, b| *a / *b);
+    }
+}
+
+/// Which vector norm to use, e.g. for `normalize_rows`.
+#[deriving(Eq, Clone)]
+pub enum NormKind {
+    /// The sum of the absolute values
+    L1,
+    /// The Euclidean length
+    L2,
+    /// The largest absolute value
+    Inf,
+}
+
+fn norm_of(kind: NormKind, v: &[f64]) -> f64 {
+    match kind {
+        L1 => v.iter().fold(0.0, |a, x| a + x.abs()),
+        L2 => v.iter().fold(0.0, |a, x| a + *x * *x).sqrt(),
+        Inf => v.iter().fold(0.0, |a, x| a.max(&x.abs())),
+    }
+}
+
+impl Mat2<f64> {
+    /// Divide each row by its `kind` norm, and return the norms. Rows that are all zero are left
+    /// alone. With `L1` on a nonnegative matrix, this makes it row stochastic.
+    pub fn normalize_rows(&mut self, kind: NormKind) -> ~[f64] {
+        let norms = self.apply_rows(|r| norm_of(kind, r));
+        self.div_col_vec(norms.map(|x| if *x == 0.0 { 1.0 } else { *x }));
+        norms
+    }
+
+    /// Divide each column by its `kind` norm, and return the norms. Columns that are all zero are
+    /// left alone.
+    pub fn normalize_cols(&mut self, kind: NormKind) -> ~[f64] {
+        let norms = self.apply_cols(|c| norm_of(kind, c));
+        self.div_row_vec(norms.map(|x| if *x == 0.0 { 1.0 } else { *x }));
+        norms
+    }
+
+    /// The Frobenius norm, the square root of the sum of the squares of all the elements.
+    pub fn norm_fro(&self) -> f64 {
+        norm_of(L2, self.data.as_slice())
+    }
+
+    /// The 1-norm, `max ||A x||_1 / ||x||_1`, which is the biggest column sum of absolute values.
+    pub fn norm_one(&self) -> f64 {
+        self.apply_cols(|c| norm_of(L1, c)).iter().fold(0.0, |a, x| a.max(x))
+    }
+
+    /// The infinity norm, `max ||A x||_inf / ||x||_inf`, which is the biggest row sum of absolute
+    /// values.
+    pub fn norm_inf(&self) -> f64 {
+        self.apply_rows(|r| norm_of(L1, r)).iter().fold(0.0, |a, x| a.max(x))
+    }
+}
+
+impl<T: Mul<T, T>> Mat2<T> {
+    /// The outer product `u vᵀ`, the rank-one matrix with `u[i] * v[j]` at (i, j). Use
+    /// `rank1_update` to add one onto an existing matrix without building it first.
+    pub fn outer(u: &[T], v: &[T]) -> Mat2<T> {
+        Mat2::new_with(u.len(), v.len(), |i, j| u[i] * v[j])
+    }
+
+    /// Scale a row by a scalar.
+    pub fn scale_row(&mut self, i: uint, a: T) {
+        assert!(i < self.n, "scale_row: row index out of bounds");
+        for idx in range(i * self.m, (i + 1) * self.m) {
+            self.data[idx] = self.data[idx] * a;
+        }
+    }
+
+    /// Scale a row by a scalar, or return an `OutOfBounds` error if `i` is out of bounds.
+    pub fn try_scale_row(&mut self, i: uint, a: T) -> LinalgResult<()> {
+        if i >= self.n { return Err(OutOfBounds(i, self.n)); }
+        self.scale_row(i, a);
+        Ok(())
+    }
+
+    /// Scale a column by a scalar.
+    pub fn scale_col(&mut self, j: uint, a: T) {
+        assert!(j < self.m, "scale_col: column index out of bounds");
+        let m = self.m;
+        for k in range(0, self.n) {
+            self.data[k * m + j] = self.data[k * m + j] * a;
+        }
+    }
+
+    /// Scale row i by `v[i]`, which is `D A` for the diagonal matrix `D` with `v` along its
+    /// diagonal, without ever forming `D`. Fails if `v` isn't one entry per row.
+    pub fn scale_rows_by(&mut self, v: &[T]) {
+        self.broadcast_cols(v, |a, b| *a * *b);
+    }
+
+    /// Scale column j by `v[j]`, which is `A D` for the diagonal matrix `D` with `v` along its
+    /// diagonal. Fails if `v` isn't one entry per column.
+    pub fn scale_cols_by(&mut self, v: &[T]) {
+        self.broadcast_rows(v, |a, b| *a * *b);
+    }
+}
+
+impl<T: Sub<T, T>> Mat2<T> {
+    /// First differences down each column: row `i` of the result is row `i+1` minus row `i`, so
+    /// an (n x m) matrix gives back an (n-1 x m) one.
+    pub fn diff_rows(&self) -> Mat2<T> {
+        let n = if self.n == 0 { 0 } else { self.n - 1 };
+        Mat2::new_with(n, self.m, |i, j| *self.get(i+1, j) - *self.get(i, j))
+    }
+
+    /// First differences along each row: column `j` of the result is column `j+1` minus column
+    /// `j`, so an (n x m) matrix gives back an (n x m-1) one.
+    pub fn diff_cols(&self) -> Mat2<T> {
+        let m = if self.m == 0 { 0 } else { self.m - 1 };
+        Mat2::new_with(self.n, m, |i, j| *self.get(i, j+1) - *self.get(i, j))
+    }
+}
+
+/// `m[(i, j)]` is a copy of the element at row `i`, column `j`, as with `at`.
+impl<T: Clone> Index<(uint, uint), T> for Mat2<T> {
+    fn index(&self, &(i, j): &(uint, uint)) -> T {
+        self.at(i, j)
+    }
+}
+
+impl<T: Eq> Eq for Mat2<T> {
+    fn eq(&self, other: &Mat2<T>) -> bool {
+        self.n == other.n && self.m == other.m && self.data == other.data
+    }
+}
+
+/// How close two floats have to be to count as equal, for `Mat2::approx_eq_tol`.
+#[deriving(Eq, Clone)]
+pub enum Tolerance {
+    /// Within this much of each other
+    Absolute(f64),
+    /// Within this fraction of the bigger of the two (in absolute value)
+    Relative(f64),
+}
+
+impl Mat2<f64> {
+    /// Test if two matrices are the same shape, with every pair of corresponding elements equal
+    /// up to `tol`. Use `Relative` when the elements vary a lot in size, but note that nothing is
+    /// relatively close to zero except zero itself.
+    pub fn approx_eq_tol(&self, other: &Mat2<f64>, tol: Tolerance) -> bool {
+        self.n == other.n && self.m == other.m &&
+            self.data.iter().zip(other.data.iter()).all(|(x, y)| {
+                let d = (*x - *y).abs();
+                match tol {
+                    Absolute(eps) => d <= eps,
+                    Relative(eps) => d <= eps * x.abs().max(&y.abs()),
+                }
+            })
+    }
+}
+
+impl ApproxEq<f64> for Mat2<f64> {
+    /// 1e-6, the same as for `f64` itself.
+    fn approx_epsilon() -> f64 {
+        1.0e-6
+    }
+
+    /// The same shape, with all the elements within `approx_epsilon()` of each other.
+    fn approx_eq(&self, other: &Mat2<f64>) -> bool {
+        self.approx_eq_eps(other, &1.0e-6)
+    }
+
+    /// The same shape, with all the elements within `eps` of each other.
+    fn approx_eq_eps(&self, other: &Mat2<f64>, eps: &f64) -> bool {
+        self.approx_eq_tol(other, Absolute(*eps))
+    }
+}
+
+impl<T: TotalEq> TotalEq for Mat2<T> {
+    fn equals(&self, other: &Mat2<T>) -> bool {
+        self.n == other.n && self.m == other.m
+            && self.data.iter().zip(other.data.iter()).all(|(a, b)| a.equals(b))
+    }
+}
+
+/// Matrices are ordered first by their number of rows, then by their number of columns, and
+/// then lexicographically by their elements in row-major order. This isn't meant to mean
+/// anything mathematically; it just gives ordered collections a deterministic order to work
+/// with.
+impl<T: TotalOrd> TotalOrd for Mat2<T> {
+    fn cmp(&self, other: &Mat2<T>) -> Ordering {
+        match (self.n.cmp(&other.n), self.m.cmp(&other.m)) {
+            (Equal, Equal) => {}
+            (Equal, o) | (o, _) => return o
+        }
+        for (a, b) in self.data.iter().zip(other.data.iter()) {
+            match a.cmp(b) {
+                Equal => {}
+                o => return o
+            }
+        }
+        Equal
+    }
+}
+
+impl<T: TotalOrd> Ord for Mat2<T> {
+    fn lt(&self, other: &Mat2<T>) -> bool {
+        self.cmp(other) == Less
+    }
+}
+
+// Hashes the shape too, so that e.g. a 2x3 and a 3x2 matrix with the same elements don't collide
+// (they aren't equal either).
+impl<T: IterBytes> IterBytes for Mat2<T> {
+    fn iter_bytes(&self, lsb0: bool, f: to_bytes::Cb) -> bool {
+        self.n.iter_bytes(lsb0, |b| f(b))
+            && self.m.iter_bytes(lsb0, |b| f(b))
+            && self.data.iter().all(|x| x.iter_bytes(lsb0, |b| f(b)))
+    }
+}
+
+impl<T: Mul<T, T> + Add<T, T>> Mat2<T> {
+    /// Add a row `i` scaled by `a` to another row `j`. Fails if either of the indices are out of
+    /// bounds.
+    pub fn add_scaled(&mut self, i: uint, j: uint, a: T) {
+        assert!(i < self.n && j < self.n, "add_scaled: row index out of bounds");
+        let m = self.m;
+        for k in range(0, m) {
+            let v = self.data[i * m + k] * a + self.data[j * m + k];
+            self.data[j * m + k] = v;
+        }
+    }
+
+    /// Add a row `i` scaled by `a` to another row `j`, or return an `OutOfBounds` error if
+    /// either of the indices are out of bounds.
+    pub fn try_add_scaled(&mut self, i: uint, j: uint, a: T) -> LinalgResult<()> {
+        if i.max(&j) >= self.n { return Err(OutOfBounds(i.max(&j), self.n)); }
+        self.add_scaled(i, j, a);
+        Ok(())
+    }
+
+    /// Add a column `i` scaled by `a` to another column `j`. Fails if either of the indices are
+    /// out of bounds.
+    pub fn add_scaled_col(&mut self, i: uint, j: uint, a: T) {
+        assert!(i < self.m && j < self.m, "add_scaled_col: column index out of bounds");
+        let m = self.m;
+        for k in range(0, self.n) {
+            let v = self.data[k * m + i] * a + self.data[k * m + j];
+            self.data[k * m + j] = v;
+        }
+    }
+
+    /// Add `alpha * u * vᵀ` to the matrix in place (BLAS's `ger`), where `u` has one entry per row
+    /// and `v` one per column. Handy for building up a matrix as a sum of outer products, like a
+    /// covariance matrix. Fails if `u` or `v` is the wrong length.
+    pub fn rank1_update(&mut self, alpha: T, u: &[T], v: &[T]) {
+        assert!(u.len() == self.n && v.len() == self.m,
+                "rank1_update: vectors are the wrong length");
+        let m = self.m;
+        for i in range(0, self.n) {
+            let au = alpha * u[i];
+            for j in range(0, m) {
+                self.data[i * m + j] = self.data[i * m + j] + au * v[j];
+            }
+        }
+    }
+}
+
+impl<T: Num + Signed + Ord + Clone> Mat2<T> {
+    /// The inverse, by Gauss-Jordan elimination with partial pivoting on `[A | I]`. Returns
+    /// `None` if the matrix is singular. Fails if it isn't square.
+    pub fn inverse(&self) -> Option<Mat2<T>> {
+        self.inverse_opts(PartialPivoting, zero())
+    }
+
+    /// The inverse, by Gauss-Jordan elimination on `[A | I]` with pivots chosen by `pivoting`,
+    /// which is `system::solve_multi_opts` with `B = I`. Pivots no bigger than `tol` in absolute
+    /// value count as zero. Returns `None` if the matrix is singular (or a pivot is zero under
+    /// `NoPivoting`). Fails if it isn't square.
+    pub fn inverse_opts(&self, pivoting: Pivoting, tol: T) -> Option<Mat2<T>> {
+        assert!(self.n == self.m, "inverse: matrix must be square");
+        let opts = SolveOpts { pivot_strategy: pivoting, pivot_tol: tol, ..Default::default() };
+        solve_multi_opts(self, &Mat2::identity(self.n), &opts)
+    }
+}
+
+impl<T: Num + Signed + Ord + Clone> Mat2<T> {
+    /// Reduce this matrix to Reduced Row-Echelon Form in place, by Gauss-Jordan elimination with
+    /// partial pivoting, and return the pivot columns (the columns with a leading one in them),
+    /// in order. Their number is the rank. Pivots and eliminated entries are set to exactly one
+    /// and zero, so the result passes `is_rref`, but for floats rounding can leave an entry that
+    /// should be zero slightly off, and it then counts as a pivot; `to_rref_opts` can take a
+    /// tolerance to stop that.
+    pub fn to_rref(&mut self) -> ~[uint] {
+        self.to_rref_opts(PartialPivoting, zero())
+    }
+
+    /// `to_rref`, with pivots chosen by `pivoting`, and where entries no bigger than `tol` in
+    /// absolute value count as zero (and are set to zero when their column is skipped). Since
+    /// the RREF fixes the order of the columns, there are no column swaps: `NoPivoting` takes
+    /// the first entry down the column that's bigger than `tol`, which is the cheap choice for
+    /// exact types, and `FullPivoting` and `RookPivoting` act like `PartialPivoting`.
+    pub fn to_rref_opts(&mut self, pivoting: Pivoting, tol: T) -> ~[uint] {
+        let mut pivots = ~[];
+        let mut r = 0;
+        for j in range(0, self.m) {
+            if r == self.n { break; }
+            let p = match pivoting {
+                NoPivoting => range(r, self.n).find(|&i| self.get(i, j).abs() > tol).unwrap_or(r),
+                _ => {
+                    let (p, _) = pivoting.choose(r, j, self.n, j + 1, |i, c| self.at(i, c));
+                    p
+                }
+            };
+            if self.get(p, j).abs() <= tol {
+                for i in range(r, self.n) {
+                    self.set(i, j, zero());
+                }
+                continue;
+            }
+
+            self.swap_rows(r, p);
+            let s = one::<T>() / *self.get(r, j);
+            self.scale_row(r, s);
+            self.set(r, j, one());
+            for i in range(0, self.n) {
+                if i != r && *self.get(i, j) != zero() {
+                    let f = -*self.get(i, j);
+                    self.add_scaled(r, i, f);
+                    self.set(i, j, zero());
+                }
+            }
+
+            pivots.push(j);
+            r += 1;
+        }
+        pivots
+    }
+
+    /// The Reduced Row-Echelon Form of this matrix, and its pivot columns. See `to_rref`.
+    pub fn rref(&self) -> (Mat2<T>, ~[uint]) {
+        let mut a = self.clone();
+        let pivots = a.to_rref();
+        (a, pivots)
+    }
+
+    /// `rref`, choosing pivots and treating small entries as zero as for `to_rref_opts`.
+    pub fn rref_opts(&self, pivoting: Pivoting, tol: T) -> (Mat2<T>, ~[uint]) {
+        let mut a = self.clone();
+        let pivots = a.to_rref_opts(pivoting, tol);
+        (a, pivots)
+    }
+
+    /// The rank of the matrix: the number of pivots left after reducing it to row echelon form
+    /// with partial pivoting, where any entry no bigger than `tol` in absolute value counts as
+    /// zero. Use a `tol` of zero for exact types, and something a bit bigger than the rounding
+    /// error for floats, e.g. `1e-10` times the size of the biggest entry.
+    pub fn rank(&self, tol: T) -> uint {
+        let mut a = self.clone();
+        let mut r = 0;
+        for j in range(0, self.m) {
+            if r == self.n { break; }
+            let p = range(r, self.n).fold(r, |best, i| {
+                if a.get(i, j).abs() > a.get(best, j).abs() { i } else { best }
+            });
+            if a.get(p, j).abs() <= tol { continue; }
+
+            a.swap_rows(r, p);
+            for i in range(r + 1, self.n) {
+                let f = -*a.get(i, j) / *a.get(r, j);
+                a.add_scaled(r, i, f);
+            }
+            r += 1;
+        }
+        r
+    }
+
+    /// Do Gauss-Jordan elimination on this matrix to convert it into Reduced Row-Echelon Form.
+    /// This is `to_rref`, without the pivots.
+    pub fn reduce(&mut self) {
+        self.to_rref();
+    }
+}
+
+impl<T: Num + Clone> Mat2<T> {
+    // Bareiss's fraction-free elimination, in place. Each step replaces a_ic with
+    // (a_rj a_ic - a_ij a_rc) / (the previous pivot), and that division is always exact, since
+    // every entry is then a minor of the original matrix. So integer division never truncates,
+    // and it works just as well for rationals (or floats, though there's no point). Returns the
+    // pivot columns, and whether an odd number of row swaps was needed.
+    fn bareiss(&mut self) -> (~[uint], bool) {
+        let mut prev = one::<T>();
+        let mut pivots = ~[];
+        let mut odd = false;
+        let mut r = 0;
+        for j in range(0, self.m) {
+            if r == self.n { break; }
+            let p = match range(r, self.n).find(|&i| !self.get(i, j).is_zero()) {
+                Some(p) => p,
+                None => continue,
+            };
+            if p != r {
+                self.swap_rows(r, p);
+                odd = !odd;
+            }
+
+            for i in range(r + 1, self.n) {
+                for c in range(j + 1, self.m) {
+                    let x = (*self.get(r, j) * *self.get(i, c) - *self.get(i, j) * *self.get(r, c))
+                        / prev;
+                    self.set(i, c, x);
+                }
+                self.set(i, j, zero());
+            }
+            prev = self.get(r, j).clone();
+            pivots.push(j);
+            r += 1;
+        }
+        (pivots, odd)
+    }
+
+    /// A row echelon form of the matrix that never leaves the integers, by Bareiss's
+    /// fraction-free elimination, along with its pivot columns. Unlike `rref` the pivots aren't
+    /// scaled to one, but the entries only grow like the matrix's minors do, rather than
+    /// exponentially like plain integer elimination.
+    pub fn echelon_exact(&self) -> (Mat2<T>, ~[uint]) {
+        let mut a = self.clone();
+        let (pivots, _) = a.bareiss();
+        (a, pivots)
+    }
+
+    /// The exact determinant, by Bareiss's fraction-free elimination: after it, the last pivot
+    /// is the determinant (up to the sign of the row swaps). Fails if the matrix isn't square.
+    pub fn det_exact(&self) -> T {
+        assert!(self.n == self.m, "det_exact: matrix must be square");
+        if self.n == 0 { return one(); }
+        let mut a = self.clone();
+        let (pivots, odd) = a.bareiss();
+        if pivots.len() < self.n { return zero(); }
+        let d = a.get(self.n - 1, self.n - 1).clone();
+        if odd { zero::<T>() - d } else { d }
+    }
+
+    /// The cofactor matrix, whose `(i, j)` entry is `(-1)ⁱ⁺ʲ` times the determinant of the minor
+    /// at `(i, j)`. Each of those is a `det_exact`, so this takes O(n⁵) time, which is only
+    /// reasonable for small matrices. Fails if the matrix isn't square.
+    pub fn cofactor_matrix(&self) -> Mat2<T> {
+        assert!(self.n == self.m, "cofactor_matrix: matrix must be square");
+        Mat2::new_with(self.n, self.n, |i, j| {
+            let d = self.minor(i, j).det_exact();
+            if (i + j) % 2 == 0 { d } else { zero::<T>() - d }
+        })
+    }
+
+    /// The adjugate, the transpose of the cofactor matrix. `A adj(A) = det(A) I`, so for an
+    /// invertible matrix the inverse is `adj(A) / det(A)` exactly, with a single division at the
+    /// end. Fails if the matrix isn't square.
+    pub fn adjugate(&self) -> Mat2<T> {
+        self.cofactor_matrix().transpose()
+    }
+}
+
+impl<T: Zero + One + Ord + Eq> Mat2<T> {
+    /// Test if this matrix is in Reduced Row-Echelon Form. Mostly useful in the shell or as a
+    /// helper for `Mat2::rref` (the conversion function).
+    pub fn is_rref(&self) -> bool {
+        debug!("is_rref: entry");
+        // 1.  If there is a row where every entry is zero, then this row lies below any other row
+        //     that contains a nonzero entry.
+        let zeroes_not_at_end = self.row_iter().fold((false, false), |tup, row| {
+            // tuple is (seen_all_zero, seen_non_zero_after_all_zero)
+            if row.iter().all(|x| *x == Zero::zero()) {
+                (true, tup.n1())
+            } else {
+                (tup.n0(), if tup.n0() { true } else { false })
+            }
+        }).n1();
+        if zeroes_not_at_end { debug!("all-zero rows not at end"); return false; }
+
+        let mut last_colidx = 0;
+        'row: for (rowidx, row) in self.row_iter().enumerate() {
+            let mut seen_leading_one = false;
+            for (leftmostidx, val) in row.iter().enumerate() {
+                if seen_leading_one { continue 'row; }
+                if *val != Zero::zero() {
+                    if *val != One::one() {
+                        debug!("is_rref: false because first non-zero item isn't one \
+                                rowidx={}, row={:?}, val={:?}", rowidx, row, *val);
+                        return false;
+                    }
+
+                    seen_leading_one = true;
+                    if leftmostidx < last_colidx { return false; }
+                    last_colidx = leftmostidx;
+                    for (colidx, colval) in self.column_iter(leftmostidx).enumerate() {
+                        debug!("cols: {:?}", self.column_iter(leftmostidx).to_owned_vec());
+                        if colidx != rowidx && *colval != Zero::zero() {
+                            // 3.  The leftmost nonzero entry of a row is the only nonzero entry
+                            //     in its column.
+                            debug!("is_rref: false b/c cond 3 violated with colidx={}, \
+                                colval={:?}, leftmostidx={}", colidx, *colval, leftmostidx);
+                            return false;
+                        }
+                    }
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Compute the matrix product `a * b` into `c`, overwriting whatever was there. `c` must already
+/// have the right shape, which lets it be reused across calls without reallocating. Fails if `a`
+/// and `b` can't be multiplied, or `c` is the wrong shape.
+pub fn mul_into<T: Zero + Add<T, T> + Mul<T, T>>(a: &Mat2<T>, b: &Mat2<T>, c: &mut Mat2<T>) {
+    assert!(a.m == b.n, "mul_into: a has {} columns but b has {} rows", a.m, b.n);
+    assert!(c.n == a.n && c.m == b.m, "mul_into: output matrix is the wrong shape");
+
+    for i in range(0, a.n) {
+        for j in range(0, b.m) {
+            let v = range(0, a.m).fold(zero::<T>(), |s, k| {
+                s + a.data[i * a.m + k] * b.data[k * b.m + j]
+            });
+            c.data[i * c.m + j] = v;
+        }
+    }
+}
+
+impl<T> Mat2<T> {
+    /// A new matrix of the same shape, with `f` applied to each element.
+    pub fn map<U>(&self, f: |&T| -> U) -> Mat2<U> {
+        Mat2 { data: self.data.map(f), n: self.n, m: self.m }
+    }
+
+    /// Replace each element `x` with `f(x)`, in place.
+    pub fn map_inplace(&mut self, f: |&T| -> T) {
+        for x in self.data.mut_iter() {
+            *x = f(x);
+        }
+    }
+
+    /// The elementwise combination `f(self(i, j), other(i, j))` of two matrices of the same shape.
+    /// Fails if they aren't the same shape.
+    pub fn zip_with<U, V>(&self, other: &Mat2<U>, f: |&T, &U| -> V) -> Mat2<V> {
+        assert!(self.n == other.n && self.m == other.m,
+                "matrices must be the same shape: {}x{} vs {}x{}", self.n, self.m, other.n, other.m);
+        Mat2 {
+            data: self.data.iter().zip(other.data.iter()).map(|(a, b)| f(a, b)).to_owned_vec(),
+            n: self.n,
+            m: self.m,
+        }
+    }
+}
+
+impl<T: Mul<T, T> + Div<T, T>> Mat2<T> {
+    /// The elementwise (Hadamard) product, as opposed to the matrix product `mul`. Fails if the
+    /// matrices aren't the same shape.
+    pub fn elem_mul(&self, other: &Mat2<T>) -> Mat2<T> {
+        self.zip_with(other, |a, b| *a * *b)
+    }
+
+    /// The elementwise quotient. Fails if the matrices aren't the same shape.
+    pub fn elem_div(&self, other: &Mat2<T>) -> Mat2<T> {
+        self.zip_with(other, |a, b| *a / *b)
+    }
+}
+
+impl<T: Add<T, T>> Add<Mat2<T>, Mat2<T>> for Mat2<T> {
+    /// Elementwise sum. Fails if the matrices aren't the same shape.
+    fn add(&self, other: &Mat2<T>) -> Mat2<T> {
+        self.zip_with(other, |a, b| *a + *b)
+    }
+}
+
+impl<T: Sub<T, T>> Sub<Mat2<T>, Mat2<T>> for Mat2<T> {
+    /// Elementwise difference. Fails if the matrices aren't the same shape.
+    fn sub(&self, other: &Mat2<T>) -> Mat2<T> {
+        self.zip_with(other, |a, b| *a - *b)
+    }
+}
+
+impl<T: Neg<T>> Neg<Mat2<T>> for Mat2<T> {
+    fn neg(&self) -> Mat2<T> {
+        Mat2 { data: self.data.map(|x| -*x), n: self.n, m: self.m }
+    }
+}
+
+impl<T: Add<T, T> + Sub<T, T>> Mat2<T> {
+    /// Add `other` to this matrix in place, without allocating. Fails if they aren't the same
+    /// shape.
+    pub fn add_assign(&mut self, other: &Mat2<T>) {
+        assert!(self.n == other.n && self.m == other.m,
+                "add_assign: matrices must be the same shape");
+        for k in range(0, self.data.len()) {
+            self.data[k] = self.data[k] + other.data[k];
+        }
+    }
+
+    /// Subtract `other` from this matrix in place, without allocating. Fails if they aren't the
+    /// same shape.
+    pub fn sub_assign(&mut self, other: &Mat2<T>) {
+        assert!(self.n == other.n && self.m == other.m,
+                "sub_assign: matrices must be the same shape");
+        for k in range(0, self.data.len()) {
+            self.data[k] = self.data[k] - other.data[k];
+        }
+    }
+}
+
+impl<T: Zero + Add<T, T> + Mul<T, T>> Mat2<T> {
+    /// The matrix product `self * other`. Fails if `self` doesn't have as many columns as `other`
+    /// has rows. `a * b` does the same thing.
+    pub fn mul(&self, other: &Mat2<T>) -> Mat2<T> {
+        let mut c = Mat2::new_with(self.n, other.m, |_, _| zero());
+        mul_into(self, other, &mut c);
+        c
+    }
+}
+
+impl<T: Zero + One + Add<T, T> + Mul<T, T> + Clone> Mat2<T> {
+    /// The matrix power `Aᵏ`, by repeated squaring, so it only takes about `2 log₂ k` products.
+    /// `A⁰` is the identity. Fails if the matrix isn't square.
+    pub fn pow(&self, k: uint) -> Mat2<T> {
+        assert!(self.n == self.m, "pow: matrix must be square");
+        let mut result = Mat2::identity(self.n);
+        let mut base = self.clone();
+        let mut k = k;
+        while k > 0 {
+            if k & 1 == 1 { result = result * base; }
+            k >>= 1;
+            if k > 0 { base = base * base; }
+        }
+        result
+    }
+}
+
+impl<T: Zero + Add<T, T> + Mul<T, T>> Mul<Mat2<T>, Mat2<T>> for Mat2<T> {
+    fn mul(&self, other: &Mat2<T>) -> Mat2<T> {
+        let mut c = Mat2::new_with(self.n, other.m, |_, _| zero());
+        mul_into(self, other, &mut c);
+        c
+    }
+}
+
+/// Compute `c = alpha * a * aᵀ + beta * c` (BLAS's `syrk`). The result is symmetric, so only the
+/// lower triangle is actually computed, and then copied into the upper one; this is about half the
+/// work of doing it with `mul_into`. Only the lower triangle of `c` is read, so its upper triangle
+/// can hold anything going in. Fails unless `c` is square with as many rows as `a`.
+pub fn syrk<T: Zero + Add<T, T> + Mul<T, T> + Clone>(alpha: T, a: &Mat2<T>, beta: T,
+                                                     c: &mut Mat2<T>) {
+    assert!(c.n == a.n && c.m == a.n, "syrk: output matrix is the wrong shape");
+
+    for i in range(0, a.n) {
+        for j in range(0, i + 1) {
+            let s = range(0, a.m).fold(zero::<T>(), |s, k| {
+                s + a.data[i * a.m + k] * a.data[j * a.m + k]
+            });
+            let v = alpha * s + beta * c.data[i * c.m + j];
+            c.data[j * c.m + i] = v.clone();
+            c.data[i * c.m + j] = v;
+        }
+    }
+}
+
+/// Write the transpose of `a` into `c`, overwriting whatever was there. `c` must already have
+/// the transposed shape. Fails if it doesn't.
+pub fn transpose_into<T: Clone>(a: &Mat2<T>, c: &mut Mat2<T>) {
+    assert!(c.n == a.m && c.m == a.n, "transpose_into: output matrix is the wrong shape");
+
+    for i in range(0, a.n) {
+        for j in range(0, a.m) {
+            c.data[j * c.m + i] = a.data[i * a.m + j].clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::Hash;
+    use extra::treemap::TreeSet;
+    use std::hashmap::HashMap;
+    use std::num::{Zero, One};
+    use super::{Mat2, Shape, L1, L2, Inf, PMatrix, BMatrix, FormatOpts, Scientific};
+    use super::{Absolute, Relative};
+    use error::{OutOfBounds, WrongLength};
+    use super::{mul_into, transpose_into, syrk, StridedView};
+    use system::{NoPivoting, PartialPivoting, FullPivoting, RookPivoting};
+
+    #[test]
+    fn test_cons() {
+        let x: Mat2<int> = Mat2::new(3, 2);
+
+        let y: Option<Mat2<int>> = Mat2::from_vec(~[]);
+        assert_eq!(y, None);
+
+        let z: Mat2<int> = Mat2::new_with(3, 2, |_,_| 0);
+        assert_eq!(x, z);
+
+        let a: Option<Mat2<int>> = Mat2::from_vec(~[~[1, 2, 3], ~[1, 2]]);
+        assert_eq!(a, None);
+    }
+
+    #[test]
+    fn test_get_dimension() {
+        let x: Mat2<int> = Mat2::from_vec(~[~[1, 2], ~[3, 4]]).unwrap();
+        assert_eq!(x.get_dimension(), Shape::new(2, 2));
+        let y: Mat2<int> = Mat2::from_vec(~[~[1, 2, 3], ~[4, 5, 6]]).unwrap();
+        assert_eq!(y.get_dimension(), Shape { rows: 2, cols: 3 });
+        let z: Mat2<int> = Mat2::from_shape(y.get_dimension());
+        assert_eq!(z.get_dimension(), y.get_dimension());
+        let w = Mat2::from_shape_fn(Shape::new(3, 1), |i, _| i);
+        assert_eq!(w, Mat2::from_vec(~[~[0u], ~[1], ~[2]]).unwrap());
+    }
+
+    #[test]
+    fn test_step_view() {
+        let x = Mat2::new_with(4, 5, |i, j| i * 10 + j);
+        let v = x.step_view(2, 2);
+        assert_eq!(v.get_dimension(), Shape::new(2, 3));
+        assert_eq!(*v.get(1, 2), 24);
+        assert_eq!(v.get_opt(2, 0), None);
+        assert_eq!(v.to_mat(), Mat2::from_vec(~[~[0, 2, 4], ~[20, 22, 24]]).unwrap());
+
+        let w = x.step_view(3, 1);
+        assert_eq!(w.get_dimension(), Shape::new(2, 5));
+        assert_eq!(*w.get(1, 4), 34);
+
+        // steps compose
+        let vv = v.step_view(1, 2);
+        assert_eq!(vv.to_mat(), Mat2::from_vec(~[~[0, 4], ~[20, 24]]).unwrap());
+    }
+
+    #[test]
+    fn test_split_rows_at_mut() {
+        let mut x = Mat2::new_with(4, 2, |i, j| i * 10 + j);
+        {
+            let (mut top, bottom) = x.split_rows_at_mut(1);
+            assert_eq!(top.get_dimension(), Shape::new(1, 2));
+            assert_eq!(bottom.get_dimension(), Shape::new(3, 2));
+
+            let (mut mid, mut last) = bottom.split_rows_at_mut(2);
+            *top.get_mut(0, 1) = 100;
+            mid.get_row_mut(1)[0] = 200;
+            *last.get_mut(0, 0) += *top.get(0, 1);
+            assert_eq!(*last.get(0, 0), 130);
+        }
+        assert_eq!(x, Mat2::from_vec(~[~[0, 100], ~[10, 11], ~[200, 21], ~[130, 31]]).unwrap());
+
+        let (top, bottom) = x.split_rows_at_mut(4);
+        assert_eq!(top.get_dimension(), Shape::new(4, 2));
+        assert_eq!(bottom.get_dimension(), Shape::new(0, 2));
+    }
+
+    #[test]
+    fn test_apply_rows_cols() {
+        let x = Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6]]).unwrap();
+        assert_eq!(x.apply_rows(|r| r.iter().fold(0, |a, b| a + *b)), ~[6, 15]);
+        assert_eq!(x.apply_cols(|c| c.iter().fold(0, |a, b| a + *b)), ~[5, 7, 9]);
+        assert_eq!(x.apply_cols(|c| c.len()), ~[2, 2, 2]);
+    }
+
+    #[test]
+    fn test_folds() {
+        let x = Mat2::from_vec(~[~[1.0, -2.0, 3.0], ~[-4.0, 5.0, 0.5]]).unwrap();
+        assert_eq!(x.fold_rows(0.0, |a, b| a + b.abs() * b.abs() * b.abs()), ~[36.0, 189.125]);
+        assert_eq!(x.fold_cols(0.0, |a, b| a.max(&b.abs())), ~[4.0, 5.0, 3.0]);
+        let y: Mat2<int> = Mat2::new(0, 2);
+        assert_eq!(y.fold_cols(7, |a, b| a + *b), ~[7, 7]);
+        assert_eq!(y.fold_rows(7, |a, b| a + *b), ~[]);
+    }
+
+    #[test]
+    fn test_broadcast() {
+        let mut x = Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6]]).unwrap();
+        x.sub_row_vec(&[1, 2, 3]);
+        assert_eq!(x, Mat2::from_vec(~[~[0, 0, 0], ~[3, 3, 3]]).unwrap());
+        x.add_col_vec(&[1, -1]);
+        assert_eq!(x, Mat2::from_vec(~[~[1, 1, 1], ~[2, 2, 2]]).unwrap());
+        x.mul_row_vec(&[1, 2, 3]);
+        assert_eq!(x, Mat2::from_vec(~[~[1, 2, 3], ~[2, 4, 6]]).unwrap());
+        x.div_col_vec(&[1, 2]);
+        assert_eq!(x, Mat2::from_vec(~[~[1, 2, 3], ~[1, 2, 3]]).unwrap());
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_broadcast_wrong_length() {
+        let mut x = Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6]]).unwrap();
+        x.add_row_vec(&[1, 2]);
+    }
+
+    #[test]
+    fn test_normalize() {
+        let mut x = Mat2::from_vec(~[~[3.0, -4.0], ~[0.0, 0.0], ~[1.0, 1.0]]).unwrap();
+        assert_eq!(x.normalize_rows(L1), ~[7.0, 0.0, 2.0]);
+        assert_eq!(x.get_row(0), &[3.0 / 7.0, -4.0 / 7.0]);
+        assert_eq!(x.get_row(1), &[0.0, 0.0]);
+
+        let mut y = Mat2::from_vec(~[~[3.0, 1.0], ~[4.0, -2.0]]).unwrap();
+        assert_eq!(y.normalize_cols(L2), ~[5.0, 5.0f64.sqrt()]);
+        let norms = y.normalize_cols(L2);
+        assert_vec_eq_eps!(norms, ~[1.0, 1.0], 1e-15);
+        let r = 2.0 / 5.0f64.sqrt();
+        let norms = y.normalize_rows(Inf);
+        assert_vec_eq_eps!(norms, ~[0.6, r], 1e-15);
+        assert_vec_eq_eps!(y.get_row(1), &[0.8 / r, -1.0], 1e-15);
+    }
+
+    #[test]
+    fn test_norms() {
+        let a = Mat2::from_vec(~[~[1.0, -2.0], ~[-3.0, 4.0], ~[0.0, 5.0]]).unwrap();
+        assert_eq!(a.norm_fro(), 55.0f64.sqrt());
+        assert_eq!(a.norm_one(), 11.0);
+        assert_eq!(a.norm_inf(), 7.0);
+        assert_eq!(a.transpose().norm_one(), a.norm_inf());
+        assert_eq!(Mat2::new_with(0, 0, |_, _| 1.0).norm_one(), 0.0);
+    }
+
+    #[test]
+    fn test_scale_by() {
+        let mut x = Mat2::from_vec(~[~[1i, 2], ~[3, 4]]).unwrap();
+        x.scale_rows_by(&[2, -1]);
+        assert_eq!(x, Mat2::from_vec(~[~[2, 4], ~[-3, -4]]).unwrap());
+        x.scale_cols_by(&[1, 10]);
+        assert_eq!(x, Mat2::from_vec(~[~[2, 40], ~[-3, -40]]).unwrap());
+    }
+
+    #[test]
+    fn test_rank1_update() {
+        let mut x = Mat2::from_vec(~[~[1i, 0, 0], ~[0, 1, 0]]).unwrap();
+        x.rank1_update(2, &[1, -1], &[1, 2, 3]);
+        assert_eq!(x, Mat2::from_vec(~[~[3, 4, 6], ~[-2, -3, -6]]).unwrap());
+    }
+
+    #[test]
+    fn test_syrk() {
+        let a = Mat2::from_vec(~[~[1i, 2], ~[0, 1], ~[3, -1]]).unwrap();
+        // the upper triangle is ignored
+        let mut c = Mat2::from_vec(~[~[1i, 99, 99], ~[0, 1, 99], ~[0, 0, 1]]).unwrap();
+        syrk(2, &a, 3, &mut c);
+        assert_eq!(c, Mat2::from_vec(~[~[13, 4, 2], ~[4, 5, -2], ~[2, -2, 23]]).unwrap());
+    }
+
+    #[test]
+    fn test_element_access() {
+        let mut x = Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6]]).unwrap();
+        assert_eq!(x.at(1, 2), 6);
+        assert_eq!(x.at_opt(1, 2), Some(6));
+        assert_eq!(x.at_opt(2, 0), None);
+        assert_eq!(x[(0, 1)], 2);
+        x.set(0, 1, 20);
+        *x.get_mut(1, 0) += 40;
+        assert_eq!(x, Mat2::from_vec(~[~[1, 20, 3], ~[44, 5, 6]]).unwrap());
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_set_out_of_bounds() {
+        let mut x = Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6]]).unwrap();
+        x.set(0, 3, 7);
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut x: Mat2<int> = Mat2::with_capacity(2, 3, 4);
+        assert_eq!(x.get_dimension(), Shape::new(2, 3));
+        assert!(x.row_capacity() >= 6);
+        for i in range(0, 4) {
+            assert!(x.append_row(~[i, i, i]));
+        }
+        x.reserve_rows(10);
+        assert!(x.row_capacity() >= 16);
+        x.shrink_to_fit();
+        assert_eq!(x.row_capacity(), 6);
+        assert!(x.get_row(5) == &[3, 3, 3]);
+    }
+
+    #[test]
+    fn test_raw_parts() {
+        let mut x = Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6]]).unwrap();
+        unsafe {
+            *x.as_mut_ptr().offset(4) = 50;
+            assert_eq!(*x.as_ptr().offset(3), 4);
+
+            let y = Mat2::copy_from_raw_parts(x.as_ptr(), 2, 3, 3);
+            assert_eq!(y, x);
+            let y = Mat2::copy_from_raw_parts(x.as_ptr(), 2, 2, 3);
+            assert_eq!(y, Mat2::from_vec(~[~[1, 2], ~[4, 50]]).unwrap());
+
+            // no copy: the view sees later writes
+            let v = StridedView::from_raw_parts(x.as_ptr(), 2, 2, 3);
+            assert_eq!(v.get_row(1), &[4, 50]);
+            *x.as_mut_ptr().offset(1) = 20;
+            assert_eq!(*v.get(0, 1), 20);
+        }
+    }
+
+    #[test]
+    fn test_strided_view() {
+        let buf = ~[1i, 2, 3, 4, 5, 6, 7, 8];
+        let v = StridedView::from_slice(buf, 3, 2, 3).unwrap();
+        assert_eq!(v.get_dimension(), Shape::new(3, 2));
+        assert_eq!(v.to_mat(), Mat2::from_vec(~[~[1, 2], ~[4, 5], ~[7, 8]]).unwrap());
+        assert_eq!(v.get_opt(2, 1), None);
+        assert!(StridedView::from_slice(buf, 3, 2, 1).is_none());
+        assert!(StridedView::from_slice(buf, 3, 3, 3).is_none());
+    }
+
+    #[test]
+    fn test_export_c() {
+        let x = Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6]]).unwrap();
+        let (ptr, rows, cols, stride) = x.export_c();
+        assert_eq!((rows, cols, stride), (2, 3, 3));
+        unsafe {
+            assert_eq!(*ptr.offset((1 * stride + 2) as int), 6);
+            assert_eq!(Mat2::import_c(ptr, rows, cols, stride).to_mat(), x);
+        }
+    }
+
+    #[test]
+    fn test_fortran_vec() {
+        let x = Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6]]).unwrap();
+        let f = x.to_fortran_vec();
+        assert_eq!(f, ~[1, 4, 2, 5, 3, 6]);
+        assert_eq!(Mat2::from_fortran_vec(2, 3, f.clone()), Some(x));
+        assert_eq!(Mat2::from_fortran_vec(3, 3, f), None);
+    }
+
+    #[test]
+    fn test_latex_markdown() {
+        let x = Mat2::from_vec(~[~[1i, 2], ~[3, 4]]).unwrap();
+        assert_eq!(x.to_latex(PMatrix), ~"\\begin{pmatrix}\n1 & 2 \\\\\n3 & 4\n\\end{pmatrix}");
+        assert_eq!(x.to_latex(BMatrix), ~"\\begin{bmatrix}\n1 & 2 \\\\\n3 & 4\n\\end{bmatrix}");
+        assert_eq!(x.to_markdown_table(), ~"| 0 | 1 |\n|---|---|\n| 1 | 2 |\n| 3 | 4 |\n");
+    }
+
+    #[test]
+    fn test_to_str_opts() {
+        let x = Mat2::new_with(3, 3, |i, j| i * 3 + j + 1);
+        let opts = FormatOpts { max_rows: 2, max_cols: 2, ..Default::default() };
+        assert_eq!(x.to_str_opts(&opts), ~"[\n1 … 3 \n… \n7 … 9 \n]\n");
+        assert_eq!(format!("{}", x), ~"[\n1 2 3 \n4 5 6 \n7 8 9 \n]\n");
+
+        let y = Mat2::from_vec(~[~[1.0, 1.0 / 3.0]]).unwrap();
+        let opts = FormatOpts { precision: Some(2), ..Default::default() };
+        assert_eq!(y.to_str_opts(&opts), ~"[\n1.00 0.33 \n]\n");
+        let opts = FormatOpts { notation: Scientific, ..Default::default() };
+        assert!(y.to_str_opts(&opts).contains_char('e'));
+
+        let big: Mat2<int> = Mat2::new(1000, 1000);
+        assert!(format!("{}", big).lines().count() < 25);
+    }
+
+    #[test]
+    fn test_hash() {
+        let x = Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6]]).unwrap();
+        let y = Mat2::from_vec(~[~[1i, 2], ~[3, 4], ~[5, 6]]).unwrap();
+        assert_eq!(x.hash(), x.clone().hash());
+        assert!(x.hash() != y.hash());
+
+        let mut memo = HashMap::new();
+        memo.insert(x.clone(), 1);
+        memo.insert(y.clone(), 2);
+        assert_eq!(memo.find(&x), Some(&1));
+        assert_eq!(memo.find(&y), Some(&2));
+    }
+
+    #[test]
+    fn test_total_ord() {
+        let a = Mat2::from_vec(~[~[1i, 2], ~[3, 4]]).unwrap();
+        let b = Mat2::from_vec(~[~[1i, 2], ~[3, 5]]).unwrap();
+        let wide = Mat2::from_vec(~[~[0i, 0, 0]]).unwrap();
+        assert!(a.equals(&a.clone()));
+        assert!(!a.equals(&b));
+        assert_eq!(a.cmp(&b), Less);
+        assert_eq!(b.cmp(&a), Greater);
+        // fewer rows sorts first, whatever the elements
+        assert_eq!(wide.cmp(&a), Less);
+        assert!(wide < a);
+
+        let mut set = TreeSet::new();
+        for m in [b.clone(), a.clone(), b.clone(), wide.clone()].iter() {
+            set.insert(m.clone());
+        }
+        assert_eq!(set.iter().map(|m| m.clone()).to_owned_vec(), ~[wide, a, b]);
+    }
+
+    #[test]
+    fn test_swap_rows() {
+        let mut x = Mat2::from_vec(
+            ~[
+                ~[1i, 2, 3],
+                ~[4, 5, 6],
+                ~[7, 8, 9]
+            ]).unwrap();
+        x.swap_rows(0, 1);
+        assert!(x.get_row(0) == &[4, 5, 6]);
+        assert!(x.get_row(1) == &[1, 2, 3]);
+        assert!(x.get_row(2) == &[7, 8, 9]);
+    }
+
+    #[test]
+    fn test_try_ops() {
+        let mut x = Mat2::from_vec(~[~[1i, 2], ~[3, 4]]).unwrap();
+        assert_eq!(x.try_swap_rows(0, 1), Ok(()));
+        assert_eq!(x.try_swap_rows(0, 2), Err(OutOfBounds(2, 2)));
+        assert_eq!(x.try_set_row(1, ~[5, 6]), Ok(()));
+        assert_eq!(x.try_set_row(1, ~[5]), Err(WrongLength(2, 1)));
+        assert_eq!(x.try_set_row(7, ~[5, 6]), Err(OutOfBounds(7, 2)));
+        assert_eq!(x.try_scale_row(0, 10), Ok(()));
+        assert_eq!(x.try_scale_row(2, 10), Err(OutOfBounds(2, 2)));
+        assert_eq!(x.try_add_scaled(1, 0, -1), Ok(()));
+        assert_eq!(x.try_add_scaled(3, 0, -1), Err(OutOfBounds(3, 2)));
+        // nothing changed on the failures
+        assert_eq!(x, Mat2::from_vec(~[~[25i, 34], ~[5, 6]]).unwrap());
+    }
+
+    #[test]
+    fn test_column_ops() {
+        let mut x = Mat2::from_vec(
+            ~[
+                ~[1i, 2, 3],
+                ~[4, 5, 6],
+                ~[7, 8, 9]
+            ]).unwrap();
+        x.swap_cols(0, 2);
+        assert_eq!(x, Mat2::from_vec(~[~[3i, 2, 1], ~[6, 5, 4], ~[9, 8, 7]]).unwrap());
+        x.scale_col(1, 2);
+        assert_eq!(x, Mat2::from_vec(~[~[3i, 4, 1], ~[6, 10, 4], ~[9, 16, 7]]).unwrap());
+        x.add_scaled_col(2, 0, -3);
+        assert_eq!(x, Mat2::from_vec(~[~[0i, 4, 1], ~[-6, 10, 4], ~[-12, 16, 7]]).unwrap());
     }
 
-    /// Swap two rows. Fails if either of the indices are out of bounds.
-    pub fn swap_rows(&mut self, i: uint, j: uint) {
-        self.data.swap(i, j);
+    #[test]
+    #[should_fail]
+    fn test_swap_cols_out_of_bounds() {
+        let mut x = Mat2::from_vec(~[~[1i, 2], ~[3, 4], ~[5, 6]]).unwrap();
+        x.swap_cols(0, 2);
     }
 
-    /// Set a row to the given vector. Fails if `i` is out of bounds.
-    pub fn set_row(&mut self, i: uint, r: ~[T]) {
-        self.data[i] = r;
+    #[test]
+    fn test_insert_remove() {
+        let mut x = Mat2::from_vec(~[~[1i, 2], ~[3, 4]]).unwrap();
+        assert!(x.insert_row(1, ~[5, 6]));
+        assert!(x.insert_row(3, ~[7, 8]));
+        assert!(!x.insert_row(5, ~[9, 9]));
+        assert!(!x.insert_row(0, ~[9]));
+        assert_eq!(x, Mat2::from_vec(~[~[1i, 2], ~[5, 6], ~[3, 4], ~[7, 8]]).unwrap());
+
+        assert!(x.insert_col(0, ~[0, 0, 0, 0]));
+        assert!(x.insert_col(2, ~[9, 9, 9, 9]));
+        assert!(!x.insert_col(1, ~[1, 2]));
+        let e = Mat2::from_vec(~[~[0i, 1, 9, 2], ~[0, 5, 9, 6], ~[0, 3, 9, 4], ~[0, 7, 9, 8]]);
+        assert_eq!(x, e.unwrap());
+
+        assert_eq!(x.remove_row(1), ~[0, 5, 9, 6]);
+        assert_eq!(x.remove_col(2), ~[9, 9, 9]);
+        assert_eq!(x.remove_col(0), ~[0, 0, 0]);
+        assert_eq!(x, Mat2::from_vec(~[~[1i, 2], ~[3, 4], ~[7, 8]]).unwrap());
+        assert_eq!(x.get_dimension(), Shape::new(3, 2));
     }
 
-    /// Get the row at `i` as a slice. Fails if `i` is out of bounds.
-    pub fn get_row<'a>(&'a self, i: uint) -> &'a [T] {
-        self.data[i].as_slice()
+    #[test]
+    #[should_fail]
+    fn test_remove_row_out_of_bounds() {
+        let mut x = Mat2::from_vec(~[~[1i, 2], ~[3, 4]]).unwrap();
+        x.remove_row(2);
     }
 
-    /// Get a reference to the element at row `i`, column `j` (both starting at 0). Returns `None`
-    /// if `i` or `j` are out of bounds.
-    pub fn get_opt<'a>(&'a self, i: uint, j: uint) -> Option<&'a T> {
-        if (i > self.m || j > self.n) {
-            None
-        } else {
-            Some(&self.data[i][j])
-        }
+    #[test]
+    fn test_submatrix() {
+        let x = Mat2::from_vec(
+            ~[
+                ~[1i, 2, 3],
+                ~[4, 5, 6],
+                ~[7, 8, 9]
+            ]).unwrap();
+        assert_eq!(x.submatrix(range(1, 3), range(0, 2)),
+                   Mat2::from_vec(~[~[4i, 5], ~[7, 8]]).unwrap());
+        assert_eq!(x.submatrix(range(0, 3), range(2, 3)),
+                   Mat2::from_vec(~[~[3i], ~[6], ~[9]]).unwrap());
+        assert_eq!(x.submatrix(range(1, 1), range(0, 3)).get_dimension(), Shape::new(0, 3));
+
+        assert_eq!(x.minor(1, 1), Mat2::from_vec(~[~[1i, 3], ~[7, 9]]).unwrap());
+        assert_eq!(x.minor(0, 2), Mat2::from_vec(~[~[4i, 5], ~[7, 8]]).unwrap());
     }
 
-    /// Get a reference to the element at row `i`, column `j` (both starting at 0). Fails if `i` or
-    /// `j` are out of bounds.
-    pub fn get<'a>(&'a self, i: uint, j: uint) -> &'a T {
-        &self.data[i][j]
+    #[test]
+    #[should_fail]
+    fn test_submatrix_out_of_bounds() {
+        let x = Mat2::from_vec(~[~[1i, 2], ~[3, 4]]).unwrap();
+        x.submatrix(range(0, 3), range(0, 2));
     }
 
-    /// Get the row at `i` as a slice. Returns `None` if `i` is out of bounds.
-    pub fn get_row_opt<'a>(&'a self, i: uint) -> Option<&'a [T]> {
-        self.data.get_opt(i).map(|o| o.as_slice())
+    #[test]
+    fn test_stack() {
+        let a = Mat2::from_vec(~[~[1i, 2], ~[3, 4]]).unwrap();
+        let b = Mat2::from_vec(~[~[5i], ~[6]]).unwrap();
+        let c = Mat2::from_vec(~[~[7i, 8]]).unwrap();
+
+        assert_eq!(a.hstack(&b), Mat2::from_vec(~[~[1i, 2, 5], ~[3, 4, 6]]));
+        assert_eq!(a.vstack(&c), Mat2::from_vec(~[~[1i, 2], ~[3, 4], ~[7, 8]]));
+        assert!(a.hstack(&c).is_none());
+        assert!(a.vstack(&b).is_none());
+
+        let mut x = a.clone();
+        assert!(x.stack(c.clone()));
+        assert!(!x.stack(b));
+        assert_eq!(Some(x), a.vstack(&c));
     }
 
-    /// Append a column to the matrix. Returns true if the insert succeeded, false otherwise.
-    pub fn append_column(&mut self, column: ~[T]) -> bool {
-        // this makes sure the unsafe_mut_ref below will be valid
-        if self.n != column.len() { return false; }
-
-        self.n += 1;
-
-        for (idx, itm) in column.move_iter().enumerate() {
-            unsafe { (*self.data.unsafe_mut_ref(idx)).push(itm); }
-        }
-
-        true
+    #[test]
+    fn test_blocks() {
+        let x = Mat2::from_vec(
+            ~[
+                ~[1i, 2, 3],
+                ~[4, 5, 6],
+                ~[7, 8, 9]
+            ]).unwrap();
+        let (top, bottom) = x.split_at_row(1);
+        assert_eq!(top, Mat2::from_vec(~[~[1i, 2, 3]]).unwrap());
+        assert_eq!(bottom, Mat2::from_vec(~[~[4i, 5, 6], ~[7, 8, 9]]).unwrap());
+        let (left, right) = x.split_at_col(3);
+        assert_eq!(left, x);
+        assert_eq!(right.get_dimension(), Shape::new(3, 0));
+
+        let b = x.blocks([1], [2]);
+        assert_eq!(b.len(), 2);
+        assert_eq!(b[0], ~[Mat2::from_vec(~[~[1i, 2]]).unwrap(), Mat2::from_vec(~[~[3i]]).unwrap()]);
+        assert_eq!(b[1][0], Mat2::from_vec(~[~[4i, 5], ~[7, 8]]).unwrap());
+        assert_eq!(b[1][1], Mat2::from_vec(~[~[6i], ~[9]]).unwrap());
+        assert_eq!(x.blocks([], [1, 2]).map(|r| r.len()), ~[3]);
     }
 
-    /// Append a row to the matrix. Returns true if the insert succeeded, false otherwise.
-    pub fn append_row(&mut self, row: ~[T]) -> bool {
-        if self.m != row.len() { return false; }
-
-        self.n += 1;
-
-        self.data.push(row);
-
-        true
+    #[test]
+    #[should_fail]
+    fn test_blocks_out_of_order() {
+        let x = Mat2::from_vec(~[~[1i, 2], ~[3, 4]]).unwrap();
+        x.blocks([1], [2, 1]);
     }
 
-    /// ["Augment"](http://goo.gl/Q1hIuC) a matrix with this one. Takes the columns of `other` and
-    /// appends them to this matrix. Returns true if the augment succeeded, false otherwise.
-    pub fn augment(&mut self, other: Mat2<T>) -> bool {
-        if self.n != other.n { return false; }
-        self.m += other.m;
-
-        for (idx, row) in other.data.move_iter().enumerate() {
-            unsafe  { (*self.data.unsafe_mut_ref(idx)).push_all_move(row); }
-        }
+    // a number type that's neither Clone nor Default
+    #[deriving(Eq)]
+    struct Counted(int);
 
-        true
+    impl Zero for Counted {
+        fn zero() -> Counted { Counted(0) }
+        fn is_zero(&self) -> bool { *self == Counted(0) }
     }
 
-    /// Iterate over the rows of a matrix.
-    pub fn row_iter<'a>(&'a self) -> RowIterator<'a, T> {
-        RowIterator {
-            mat: self,
-            i: 0
-        }
+    impl One for Counted {
+        fn one() -> Counted { Counted(1) }
     }
 
-    /// Iterate over the items column `col` (0-based) of a matrix. This does *NOT* iterate over all
-    /// columns.  If you want that, transpose the matrix and `row_iter` over that (it requires the
-    /// same amount of work).
-    pub fn column_iter<'a>(&'a self, col: uint) -> ColumnIterator<'a, T> {
-        ColumnIterator {
-            mat: self,
-            col: col,
-            i: 0
+    impl Add<Counted, Counted> for Counted {
+        fn add(&self, other: &Counted) -> Counted {
+            let (Counted(a), Counted(b)) = (*self, *other);
+            Counted(a + b)
         }
     }
-}
 
-impl<T: Mul<T, T>> Mat2<T> {
-    /// Scale a row by a scalar.
-    pub fn scale_row(&mut self, i: uint, a: T) {
-        for idx in range(0, self.data[i].len()) {
-            self.data[i][idx] = self.data[i][idx] * a;
+    impl Mul<Counted, Counted> for Counted {
+        fn mul(&self, other: &Counted) -> Counted {
+            let (Counted(a), Counted(b)) = (*self, *other);
+            Counted(a * b)
         }
     }
-}
 
-impl<T: Eq> Eq for Mat2<T> {
-    fn eq(&self, other: &Mat2<T>) -> bool {
-        self.data == other.data
+    #[test]
+    fn test_without_clone() {
+        let mut x: Mat2<Counted> = Mat2::identity(2);
+        x.add_scaled(0, 1, Counted(3));
+        x.scale_row(1, Counted(2));
+        x.swap_rows(0, 1);
+        assert!(*x.get(0, 0) == Counted(6) && *x.get(0, 1) == Counted(2));
+        assert!(*x.get(1, 0) == Counted(1) && *x.get(1, 1) == Counted(0));
+        let z: Mat2<Counted> = Mat2::zeros(1, 2);
+        assert!(z.iter().all(|c| c.is_zero()));
     }
-}
 
-impl<T: Mul<T, T> + Add<T, T> + Clone> Mat2<T> {
-    /// Add a row `i` scaled by `a` to another row `j`. Fails if either of the indices are out of
-    /// bounds.
-    pub fn add_scaled(&mut self, i: uint, j: uint, a: T) {
-        let r = self.data[i].iter().enumerate().map(|(i, x)| x.clone() * a + self.data[j][i])
-                    .to_owned_vec();
-        self.set_row(j, r);
+    #[test]
+    fn test_special_constructors() {
+        assert_eq!(Mat2::identity(2), Mat2::from_vec(~[~[1i, 0], ~[0, 1]]).unwrap());
+        assert_eq!(Mat2::zeros(1, 3), Mat2::from_vec(~[~[0i, 0, 0]]).unwrap());
+        assert_eq!(Mat2::ones(2, 1), Mat2::from_vec(~[~[1.0], ~[1.0]]).unwrap());
+        assert_eq!(Mat2::from_diag([2i, 3]), Mat2::from_vec(~[~[2i, 0], ~[0, 3]]).unwrap());
+        let empty: Mat2<int> = Mat2::zeros(0, 4);
+        assert_eq!(empty.get_dimension(), Shape::new(0, 4));
     }
-}
-
-impl<T: fmt::Default+Mul<T, T> + Add<T, T> + Div<T, T> + Zero + One + Eq + Clone> Mat2<T> {
-    /// Do Gauss-Jordan elimination on this matrix to convert it into Reduced Row-Echelon Form.
-    pub fn reduce(&mut self) {
-        // translation of pseudocode at http://linear.ups.edu/html/section-RREF.html
-        let (m, n, mut r) = (self.n, self.m, 0);
-        for j in range(0, n) {
-            let i = r + 1;
-
-            if self.column_iter(j).skip(i).all(|e| *e == Zero::zero()) {
-                debug!("reduce: matrix is zeros in col {} from row {}", j, i);
-                continue
-            }
 
-            if i < m+1 {
-                r += 1;
-                self.swap_rows(i, r);
-                let scale_factor = one::<T>() / *self.get(r, j);
-                self.scale_row(r, scale_factor);
-
-                for k in range(1, m) {
-                    debug!("m={}", m);
-                    if (k == r) { break; }
-                    let cur_item = self.get(r, j);
-                    let to_zero = self.get(r, i);
-                    debug!("cur_item={:?}, to_zero={:?}", cur_item, to_zero);
-                }
-            }
-        }
+    #[test]
+    fn test_flat() {
+        let x = Mat2::from_flat(2, 3, ~[1i, 2, 3, 4, 5, 6]).unwrap();
+        assert_eq!(x, Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6]]).unwrap());
+        assert_eq!(x.to_flat(), ~[1, 2, 3, 4, 5, 6]);
+        assert!(Mat2::from_flat(2, 2, ~[1i, 2, 3]).is_none());
+        let empty: ~[int] = ~[];
+        assert_eq!(Mat2::from_flat(0, 5, empty).unwrap().get_dimension(), Shape::new(0, 5));
     }
-}
-
-impl<T: Zero + One + Ord + Eq> Mat2<T> {
-    /// Test if this matrix is in Reduced Row-Echelon Form. Mostly useful in the shell or as a
-    /// helper for `Mat2::rref` (the conversion function).
-    pub fn is_rref(&self) -> bool {
-        debug!("is_rref: entry");
-        // 1.  If there is a row where every entry is zero, then this row lies below any other row
-        //     that contains a nonzero entry.
-        let zeroes_not_at_end = self.row_iter().fold((false, false), |tup, row| {
-            // tuple is (seen_all_zero, seen_non_zero_after_all_zero)
-            if row.iter().all(|x| *x == Zero::zero()) {
-                (true, tup.n1())
-            } else {
-                (tup.n0(), if tup.n0() { true } else { false })
-            }
-        }).n1();
-        if zeroes_not_at_end { debug!("all-zero rows not at end"); return false; }
-
-        let mut last_colidx = 0;
-        'row: for (rowidx, row) in self.row_iter().enumerate() {
-            let mut seen_leading_one = false;
-            for (leftmostidx, val) in row.iter().enumerate() {
-                if seen_leading_one { continue 'row; }
-                if *val != Zero::zero() {
-                    if *val != One::one() {
-                        debug!("is_rref: false because first non-zero item isn't one \
-                                rowidx={}, row={:?}, val={:?}", rowidx, row, *val);
-                        return false;
-                    }
 
-                    seen_leading_one = true;
-                    if leftmostidx < last_colidx { return false; }
-                    last_colidx = leftmostidx;
-                    for (colidx, colval) in self.column_iter(leftmostidx).enumerate() {
-                        debug!("cols: {:?}", self.column_iter(leftmostidx).to_owned_vec());
-                        if colidx != rowidx && *colval != Zero::zero() {
-                            // 3.  The leftmost nonzero entry of a row is the only nonzero entry
-                            //     in its column.
-                            debug!("is_rref: false b/c cond 3 violated with colidx={}, \
-                                colval={:?}, leftmostidx={}", colidx, *colval, leftmostidx);
-                            return false;
-                        }
-                    }
-                }
-            }
-        }
-        true
+    #[test]
+    fn test_map_zip_with() {
+        let x = Mat2::from_vec(~[~[1i, -2], ~[3, -4]]).unwrap();
+        assert_eq!(x.map(|v| *v > 0), Mat2::from_vec(~[~[true, false], ~[true, false]]).unwrap());
+        let mut y = x.clone();
+        y.map_inplace(|v| v.abs());
+        assert_eq!(y, Mat2::from_vec(~[~[1i, 2], ~[3, 4]]).unwrap());
+
+        let m = x.zip_with(&y, |a, b| (*a).max(b));
+        assert_eq!(m, y);
+        let mask = Mat2::from_vec(~[~[true, false], ~[false, true]]).unwrap();
+        let picked = x.zip_with(&mask, |v, &keep| if keep { *v } else { 0 });
+        assert_eq!(picked, Mat2::from_vec(~[~[1i, 0], ~[0, -4]]).unwrap());
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::Mat2;
 
     #[test]
-    fn test_cons() {
-        let x: Mat2<int> = Mat2::new(3, 2);
-
-        let y: Option<Mat2<int>> = Mat2::from_vec(~[]);
-        assert_eq!(y, None);
-
-        let z: Mat2<int> = Mat2::new_with(3, 2, |_,_| 0);
-        assert_eq!(x, z);
-
-        let a: Option<Mat2<int>> = Mat2::from_vec(~[~[1, 2, 3], ~[1, 2]]);
-        assert_eq!(a, None);
+    #[should_fail]
+    fn test_zip_with_mismatch() {
+        let x = Mat2::from_vec(~[~[1i, 2]]).unwrap();
+        let y = Mat2::from_vec(~[~[1i], ~[2]]).unwrap();
+        x.zip_with(&y, |a, b| *a + *b);
     }
 
     #[test]
-    fn test_get_dimension() {
-        let x: Mat2<int> = Mat2::from_vec(~[~[1, 2], ~[3, 4]]).unwrap();
-        assert_eq!(x.get_dimension(), (2, 2));
+    fn test_reductions() {
+        let x = Mat2::from_vec(~[~[1i, -2, 3], ~[4, 5, -6]]).unwrap();
+        assert_eq!(x.sum(), 5);
+        assert_eq!(x.row_sums(), ~[2, 3]);
+        assert_eq!(x.col_sums(), ~[5, 3, -3]);
+        assert_eq!(x.min(), Some(-6));
+        assert_eq!(x.max(), Some(5));
+        assert_eq!(x.fold(1, |p, v| p * *v), 720);
+        let empty: Mat2<int> = Mat2::new(0, 3);
+        assert_eq!(empty.max(), None);
+
+        let sq = Mat2::from_vec(~[~[1i, 2], ~[3, 4]]).unwrap();
+        assert_eq!(sq.trace(), 5);
+        let none: Mat2<int> = Mat2::new(0, 0);
+        assert_eq!(none.trace(), 0);
+
+        let y = Mat2::from_vec(~[~[1.0, 2.0], ~[3.0, 6.0]]).unwrap();
+        assert_eq!(y.mean(), 3.0);
+        assert_eq!(y.row_means(), ~[1.5, 4.5]);
+        assert_eq!(y.col_means(), ~[2.0, 4.0]);
     }
 
     #[test]
-    fn test_swap_rows() {
-        let mut x = Mat2::from_vec(
-            ~[
-                ~[1i, 2, 3],
-                ~[4, 5, 6],
-                ~[7, 8, 9]
-            ]).unwrap();
-        x.swap_rows(0, 1);
-        assert!(x.get_row(0) == &[4, 5, 6]);
-        assert!(x.get_row(1) == &[1, 2, 3]);
-        assert!(x.get_row(2) == &[7, 8, 9]);
+    #[should_fail]
+    fn test_trace_not_square() {
+        Mat2::from_vec(~[~[1i, 2]]).unwrap().trace();
     }
 
     #[test]
@@ -451,6 +2678,71 @@ mod tests {
         assert_eq!(it.next(), None);
     }
 
+    #[test]
+    fn test_iter() {
+        let x = Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6]]).unwrap();
+        assert_eq!(x.iter().map(|x| *x).to_owned_vec(), ~[1, 2, 3, 4, 5, 6]);
+        assert_eq!(x.iter().fold(0, |s, x| s + *x), 21);
+
+        let mut it = x.enumerate_iter();
+        assert_eq!(it.size_hint(), (6, Some(6)));
+        assert_eq!(it.next(), Some(((0, 0), &1)));
+        assert_eq!(it.next(), Some(((0, 1), &2)));
+        let rest = it.map(|(ij, x)| (ij, *x)).to_owned_vec();
+        assert_eq!(rest, ~[((0, 2), 3), ((1, 0), 4), ((1, 1), 5), ((1, 2), 6)]);
+        let big = x.enumerate_iter().find(|&(_, x)| *x > 4).map(|(ij, _)| ij);
+        assert_eq!(big, Some((1, 1)));
+    }
+
+    #[test]
+    fn test_row_mut() {
+        let mut x = Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6], ~[7, 8, 9]]).unwrap();
+        {
+            let r = x.get_row_mut(1);
+            r[0] = 40;
+            r.swap(1, 2);
+        }
+        assert_eq!(x.get_row(1), &[40, 6, 5]);
+
+        for (i, r) in x.row_iter_mut().enumerate() {
+            for v in r.mut_iter() { *v *= i as int; }
+        }
+        assert_eq!(x, Mat2::from_vec(~[~[0i, 0, 0], ~[40, 6, 5], ~[14, 16, 18]]).unwrap());
+        assert_eq!(x.row_iter_mut().size_hint(), (3, Some(3)));
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_get_row_mut_out_of_bounds() {
+        let mut x = Mat2::from_vec(~[~[1i, 2], ~[3, 4]]).unwrap();
+        x.get_row_mut(2);
+    }
+
+    #[test]
+    fn test_diag() {
+        let mut x = Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6], ~[7, 8, 9], ~[10, 11, 12]]).unwrap();
+        assert_eq!(x.diag(), ~[1, 5, 9]);
+        assert_eq!(x.diag_iter().fold(0, |s, x| s + *x), 15);
+        assert_eq!(x.diag_offset(1), ~[2, 6]);
+        assert_eq!(x.diag_offset(2), ~[3]);
+        assert_eq!(x.diag_offset(3), ~[]);
+        assert_eq!(x.diag_offset(-1), ~[4, 8, 12]);
+        assert_eq!(x.diag_offset(-3), ~[10]);
+        assert_eq!(x.diag_offset(-4), ~[]);
+
+        x.set_diag([0, 0, 0]);
+        x.set_diag_offset(-1, [-1, -2, -3]);
+        let e = Mat2::from_vec(~[~[0i, 2, 3], ~[-1, 0, 6], ~[7, -2, 0], ~[10, 11, -3]]).unwrap();
+        assert_eq!(x, e);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_set_diag_wrong_length() {
+        let mut x = Mat2::from_vec(~[~[1i, 2], ~[3, 4]]).unwrap();
+        x.set_diag_offset(1, [1, 2]);
+    }
+
     #[test]
     fn test_column_iter() {
         let x = Mat2::from_vec(
@@ -507,6 +2799,29 @@ mod tests {
         assert_eq!(it.next(), None);
     }
 
+    #[test]
+    fn test_pow() {
+        // powers of the Fibonacci matrix are [F(k+1) F(k); F(k) F(k-1)]
+        let f = Mat2::from_vec(~[~[1i, 1], ~[1, 0]]).unwrap();
+        assert_eq!(f.pow(0), Mat2::identity(2));
+        assert_eq!(f.pow(1), f);
+        assert_eq!(f.pow(10), Mat2::from_vec(~[~[89, 55], ~[55, 34]]).unwrap());
+        assert_eq!(*f.pow(90).get(0, 1), 2880067194370816120);
+
+        // walks of length 3 between the corners of a triangle
+        let g = Mat2::from_vec(~[~[0i, 1, 1], ~[1, 0, 1], ~[1, 1, 0]]).unwrap();
+        assert_eq!(g.pow(3), Mat2::from_vec(~[~[2, 3, 3], ~[3, 2, 3], ~[3, 3, 2]]).unwrap());
+    }
+
+    #[test]
+    fn test_outer() {
+        let o = Mat2::outer([1i, 2, 3], [4, 5]);
+        assert_eq!(o, Mat2::from_vec(~[~[4i, 5], ~[8, 10], ~[12, 15]]).unwrap());
+        let mut z = Mat2::new_with(3, 2, |_, _| 0i);
+        z.rank1_update(1, [1, 2, 3], [4, 5]);
+        assert_eq!(z, o);
+    }
+
     #[test]
     fn test_scale_row() {
         let mut x = Mat2::from_vec(~[~[1i, 1, 1]]).unwrap();
@@ -514,6 +2829,20 @@ mod tests {
         assert!(x.get_row(0) == &[3, 3, 3]);
     }
 
+    #[test]
+    fn test_diff() {
+        let x = Mat2::from_vec(
+            ~[
+                ~[1i, 2, 4],
+                ~[2, 5, 9],
+                ~[0, 0, 1]
+            ]).unwrap();
+        let r = Mat2::from_vec(~[~[1i, 3, 5], ~[-2, -5, -8]]).unwrap();
+        let c = Mat2::from_vec(~[~[1i, 2], ~[3, 4], ~[0, 1]]).unwrap();
+        assert_eq!(x.diff_rows(), r);
+        assert_eq!(x.diff_cols(), c);
+    }
+
     #[test]
     fn test_add_scaled() {
         let mut x = Mat2::from_vec(
@@ -527,6 +2856,133 @@ mod tests {
         assert!(x.get_row(1) == &[5, 7, 9]);
     }
 
+    #[test]
+    fn test_mul() {
+        let a = Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6]]).unwrap();
+        let b = Mat2::from_vec(~[~[1i, 0], ~[0, 1], ~[2, -1]]).unwrap();
+        let ab = Mat2::from_vec(~[~[7, -1], ~[16, -1]]).unwrap();
+        assert_eq!(a.mul(&b), ab);
+        assert_eq!(a * b, ab);
+        assert_eq!((b * a).get_dimension(), Shape::new(3, 3));
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_mul_mismatch() {
+        let a = Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6]]).unwrap();
+        a.mul(&a);
+    }
+
+    #[test]
+    fn test_add_sub_neg() {
+        let a = Mat2::from_vec(~[~[1i, 2], ~[3, 4]]).unwrap();
+        let b = Mat2::from_vec(~[~[10i, 20], ~[30, 40]]).unwrap();
+        assert_eq!(a + b, Mat2::from_vec(~[~[11, 22], ~[33, 44]]).unwrap());
+        assert_eq!(b - a, Mat2::from_vec(~[~[9, 18], ~[27, 36]]).unwrap());
+        assert_eq!(-a, Mat2::from_vec(~[~[-1, -2], ~[-3, -4]]).unwrap());
+
+        let mut c = a.clone();
+        c.add_assign(&b);
+        c.sub_assign(&a);
+        c.sub_assign(&a);
+        assert_eq!(c, b - a);
+    }
+
+    #[test]
+    fn test_approx_eq() {
+        let a = Mat2::from_vec(~[~[1.0, 1e6], ~[0.1 + 0.2, 0.0]]).unwrap();
+        let b = Mat2::from_vec(~[~[1.0, 1e6 + 1e-3], ~[0.3, 0.0]]).unwrap();
+        assert!(a != b);
+        assert!(!a.approx_eq_tol(&b, Absolute(1e-6)));
+        assert!(a.approx_eq_tol(&b, Absolute(1e-2)));
+        assert!(a.approx_eq_tol(&b, Relative(1e-8)));
+        assert!(!a.approx_eq_tol(&b, Relative(1e-12)));
+        assert!(!a.approx_eq_tol(&a.transpose(), Absolute(1e10)));
+
+        assert!(a.approx_eq_eps(&b, &1e-2));
+        let c = Mat2::from_vec(~[~[1.0, 1e6], ~[0.3, 1e-9]]).unwrap();
+        assert!(a.approx_eq(&c));
+    }
+
+    #[test]
+    fn test_elem_mul_div() {
+        let a = Mat2::from_vec(~[~[1i, 2], ~[3, 4]]).unwrap();
+        let b = Mat2::from_vec(~[~[5i, 6], ~[7, 8]]).unwrap();
+        assert_eq!(a.elem_mul(&b), Mat2::from_vec(~[~[5i, 12], ~[21, 32]]).unwrap());
+        assert_eq!(a.elem_mul(&b).elem_div(&b), a);
+        assert!(a.elem_mul(&b) != a * b);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_elem_mul_mismatch() {
+        let a = Mat2::from_vec(~[~[1i, 2], ~[3, 4]]).unwrap();
+        a.elem_mul(&Mat2::from_vec(~[~[1i, 2]]).unwrap());
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_add_mismatch() {
+        let a = Mat2::from_vec(~[~[1i, 2], ~[3, 4]]).unwrap();
+        let b = Mat2::from_vec(~[~[1i, 2]]).unwrap();
+        let _ = a + b;
+    }
+
+    #[test]
+    fn test_mul_into() {
+        let a = Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6]]).unwrap();
+        let b = Mat2::from_vec(~[~[1i, 0], ~[0, 1], ~[2, -1]]).unwrap();
+        let mut c = Mat2::new(2, 2);
+        mul_into(&a, &b, &mut c);
+        assert_eq!(c, Mat2::from_vec(~[~[7, -1], ~[16, -1]]).unwrap());
+        // reusing the output overwrites it
+        mul_into(&a, &b, &mut c);
+        assert_eq!(c, Mat2::from_vec(~[~[7, -1], ~[16, -1]]).unwrap());
+    }
+
+    #[test]
+    fn test_transpose() {
+        let a = Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6]]).unwrap();
+        let at = Mat2::from_vec(~[~[1i, 4], ~[2, 5], ~[3, 6]]).unwrap();
+        assert_eq!(a.transpose(), at);
+        assert_eq!(at.transpose(), a);
+
+        let mut b = Mat2::from_vec(~[~[1i, 2], ~[3, 4]]).unwrap();
+        b.transpose_inplace();
+        assert_eq!(b, Mat2::from_vec(~[~[1i, 3], ~[2, 4]]).unwrap());
+    }
+
+    #[test]
+    fn test_inverse() {
+        let a = Mat2::from_vec(~[~[2.0, 1.0], ~[4.0, 3.0]]).unwrap();
+        assert_mat_eq_eps!(a.inverse().unwrap(),
+                           Mat2::from_vec(~[~[1.5, -0.5], ~[-2.0, 1.0]]).unwrap(), 1e-12);
+
+        // needs a row swap
+        let b = Mat2::from_vec(~[~[0.0, 1.0, 0.0], ~[0.0, 0.0, 2.0], ~[4.0, 0.0, 0.0]]).unwrap();
+        let bi = b.inverse().unwrap();
+        assert_mat_eq_eps!(b * bi, Mat2::new_with(3, 3, |i, j| if i == j { 1.0 } else { 0.0 }),
+                           1e-12);
+
+        let singular = Mat2::from_vec(~[~[1.0, 2.0], ~[2.0, 4.0]]).unwrap();
+        assert!(singular.inverse().is_none());
+
+        assert_mat_eq_eps!(b.inverse_opts(FullPivoting, 0.0).unwrap(), bi, 1e-12);
+        assert_mat_eq_eps!(b.inverse_opts(RookPivoting, 0.0).unwrap(), bi, 1e-12);
+        assert!(b.inverse_opts(NoPivoting, 0.0).is_none());
+        let near = Mat2::from_vec(~[~[1.0, 2.0], ~[2.0, 4.0 + 1e-14]]).unwrap();
+        assert!(near.inverse().is_some());
+        assert!(near.inverse_opts(PartialPivoting, 1e-10).is_none());
+    }
+
+    #[test]
+    fn test_transpose_into() {
+        let a = Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6]]).unwrap();
+        let mut c = Mat2::new(3, 2);
+        transpose_into(&a, &mut c);
+        assert_eq!(c, Mat2::from_vec(~[~[1, 4], ~[2, 5], ~[3, 6]]).unwrap());
+    }
+
     #[test]
     fn test_is_rref() {
         let x = Mat2::from_vec(
@@ -601,6 +3057,95 @@ mod tests {
         assert!(x.is_rref());
     }
 
+    #[test]
+    fn test_rank() {
+        let a = Mat2::from_vec(~[~[1.0, 2.0, 3.0], ~[2.0, 4.0, 6.0], ~[1.0, 0.0, 1.0]]).unwrap();
+        assert_eq!(a.rank(1e-10), 2);
+        assert_eq!(a.transpose().rank(1e-10), 2);
+        let i = Mat2::new_with(3, 3, |i, j| if i == j { 1.0 } else { 0.0 });
+        assert_eq!(i.rank(1e-10), 3);
+        assert_eq!(Mat2::new_with(2, 4, |_, _| 0.0).rank(1e-10), 0);
+
+        // 0.1 + 0.2 != 0.3, but it's well within the tolerance
+        let c = Mat2::from_vec(~[~[1.0, 1.0], ~[0.3, 0.1 + 0.2]]).unwrap();
+        assert_eq!(c.rank(0.0), 2);
+        assert_eq!(c.rank(1e-10), 1);
+    }
+
+    #[test]
+    fn test_rref() {
+        let x = Mat2::from_vec(
+            ~[
+                ~[0.0, 2.0, 4.0, 2.0],
+                ~[1.0, 1.0, 1.0, 0.0],
+                ~[2.0, 4.0, 6.0, 2.0]
+            ]).unwrap();
+        let (r, pivots) = x.rref();
+        assert!(r.is_rref());
+        assert_eq!(pivots, ~[0, 1]);
+        assert_mat_eq_eps!(r, Mat2::from_vec(
+            ~[
+                ~[1.0, 0.0, -1.0, -1.0],
+                ~[0.0, 1.0, 2.0, 1.0],
+                ~[0.0, 0.0, 0.0, 0.0]
+            ]).unwrap(), 1e-12);
+
+        let mut y = Mat2::from_vec(~[~[2.0, 1.0], ~[1.0, 3.0]]).unwrap();
+        assert_eq!(y.to_rref(), ~[0, 1]);
+        assert_eq!(y, Mat2::from_vec(~[~[1.0, 0.0], ~[0.0, 1.0]]).unwrap());
+
+        // NoPivoting takes the first nonzero entry, and gets the same RREF
+        let (r2, pivots2) = x.rref_opts(NoPivoting, 0.0);
+        assert_eq!(pivots2, pivots);
+        assert_mat_eq_eps!(r2, r, 1e-12);
+
+        // only rank one once the rounding error in the second row counts as zero
+        let near = Mat2::from_vec(~[~[1.0, 2.0], ~[2.0, 4.0 + 1e-14]]).unwrap();
+        let (_, pivots) = near.rref();
+        assert_eq!(pivots, ~[0, 1]);
+        let (r, pivots) = near.rref_opts(PartialPivoting, 1e-10);
+        assert_eq!(pivots, ~[0]);
+        assert!(r.is_rref());
+    }
+
+    #[test]
+    fn test_det_exact() {
+        let a = Mat2::from_vec(~[~[2i, -1, 0], ~[-1, 2, -1], ~[0, -1, 2]]).unwrap();
+        assert_eq!(a.det_exact(), 4);
+        let b = Mat2::from_vec(~[~[2i, 0, 1, 0], ~[1, 1, 0, 0], ~[0, 0, 1, 3], ~[0, 1, 0, 1]]);
+        assert_eq!(b.unwrap().det_exact(), -1);
+        let swap = Mat2::from_vec(~[~[0i, 1], ~[1, 0]]).unwrap();
+        assert_eq!(swap.det_exact(), -1);
+        let singular = Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6], ~[7, 8, 9]]).unwrap();
+        assert_eq!(singular.det_exact(), 0);
+    }
+
+    #[test]
+    fn test_adjugate() {
+        let a = Mat2::from_vec(~[~[1i, 2], ~[3, 4]]).unwrap();
+        assert_eq!(a.cofactor_matrix(), Mat2::from_vec(~[~[4, -3], ~[-2, 1]]).unwrap());
+        assert_eq!(a.adjugate(), Mat2::from_vec(~[~[4, -2], ~[-3, 1]]).unwrap());
+
+        let b = Mat2::from_vec(~[~[2i, 0, 1], ~[1, 3, 0], ~[0, 1, 4]]).unwrap();
+        let d = b.det_exact();
+        assert_eq!(d, 25);
+        assert_eq!(b * b.adjugate(), Mat2::new_with(3, 3, |i, j| if i == j { d } else { 0 }));
+        assert_eq!(Mat2::from_vec(~[~[7i]]).unwrap().adjugate(), Mat2::identity(1));
+    }
+
+    #[test]
+    fn test_echelon_exact() {
+        let a = Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6], ~[7, 8, 9]]).unwrap();
+        let (e, pivots) = a.echelon_exact();
+        assert_eq!(e, Mat2::from_vec(~[~[1, 2, 3], ~[0, -3, -6], ~[0, 0, 0]]).unwrap());
+        assert_eq!(pivots, ~[0, 1]);
+
+        let b = Mat2::from_vec(~[~[0i, 2, 4, 1], ~[0, 1, 2, 3], ~[1, 1, 1, 1]]).unwrap();
+        let (e, pivots) = b.echelon_exact();
+        assert_eq!(e, Mat2::from_vec(~[~[1, 1, 1, 1], ~[0, 1, 2, 3], ~[0, 0, 0, -5]]).unwrap());
+        assert_eq!(pivots, ~[0, 1, 3]);
+    }
+
     #[test]
     fn test_reduce() {
         let mut x = Mat2::from_vec(
@@ -0,0 +1,132 @@
+//! Linear algebra over the integers mod a prime `p`, the field GF(p). The matrices are plain
+//! `Mat2<int>`s, and every function takes `p` alongside them; entries can be any integers, and
+//! come back reduced to `0 .. p`. `p` has to be prime (so that every nonzero entry can be
+//! divided by), and small enough that the product of two entries fits in an `int`.
+
+use matrix::{Mat2, Shape};
+use system::augment;
+
+fn modp(x: int, p: int) -> int {
+    let r = x % p;
+    if r < 0 { r + p } else { r }
+}
+
+// The inverse of a nonzero a mod p, by the extended Euclidean algorithm.
+fn inv_mod(a: int, p: int) -> int {
+    let (mut r0, mut r1) = (p, a);
+    let (mut t0, mut t1) = (0, 1);
+    while r1 != 0 {
+        let q = r0 / r1;
+        let (r2, t2) = (r0 - q * r1, t0 - q * t1);
+        r0 = r1; r1 = r2;
+        t0 = t1; t1 = t2;
+    }
+    assert!(r0 == 1, "{} has no inverse mod {}, so the modulus isn't prime", a, p);
+    modp(t0, p)
+}
+
+// Gauss-Jordan elimination mod p, in place, returning the pivot columns.
+fn to_rref_mod(a: &mut Mat2<int>, p: int) -> ~[uint] {
+    assert!(p > 1, "the modulus must be a prime");
+    let Shape { rows: n, cols: m } = a.get_dimension();
+    a.map_inplace(|&x| modp(x, p));
+
+    let mut pivots = ~[];
+    let mut r = 0;
+    for j in range(0, m) {
+        if r == n { break; }
+        let piv = match range(r, n).find(|&i| *a.get(i, j) != 0) {
+            Some(piv) => piv,
+            None => continue,
+        };
+        a.swap_rows(r, piv);
+
+        let s = inv_mod(*a.get(r, j), p);
+        for c in range(j, m) {
+            let x = modp(*a.get(r, c) * s, p);
+            a.set(r, c, x);
+        }
+        for i in range(0, n) {
+            let f = *a.get(i, j);
+            if i == r || f == 0 { continue; }
+            for c in range(j, m) {
+                let x = modp(*a.get(i, c) - f * *a.get(r, c), p);
+                a.set(i, c, x);
+            }
+        }
+
+        pivots.push(j);
+        r += 1;
+    }
+    pivots
+}
+
+/// The Reduced Row-Echelon Form of `a` mod `p`, and its pivot columns.
+pub fn rref_mod(a: &Mat2<int>, p: int) -> (Mat2<int>, ~[uint]) {
+    let mut a = a.clone();
+    let pivots = to_rref_mod(&mut a, p);
+    (a, pivots)
+}
+
+/// The rank of `a` over GF(p), which can be smaller than its rank over the rationals.
+pub fn rank_mod(a: &Mat2<int>, p: int) -> uint {
+    let (_, pivots) = rref_mod(a, p);
+    pivots.len()
+}
+
+/// The inverse of `a` mod `p`, or `None` if it's singular mod `p`. Fails if `a` isn't square.
+pub fn inverse_mod(a: &Mat2<int>, p: int) -> Option<Mat2<int>> {
+    let Shape { rows: n, cols: m } = a.get_dimension();
+    assert!(m == n, "inverse_mod: matrix must be square");
+    let mut aug = a.clone();
+    aug.augment(Mat2::identity(n));
+    let pivots = to_rref_mod(&mut aug, p);
+    if pivots.len() < n || pivots.iter().any(|&j| j >= n) { return None; }
+    Some(aug.submatrix(range(0, n), range(n, 2 * n)))
+}
+
+/// Solve `A x = b` mod `p`. Returns `None` if `A` is singular mod `p`. Fails if `A` isn't
+/// square or `b` has the wrong length.
+pub fn solve_mod(a: &Mat2<int>, b: &[int], p: int) -> Option<~[int]> {
+    let Shape { rows: n, cols: m } = a.get_dimension();
+    assert!(m == n && b.len() == n, "solve_mod: A must be square with as many rows as b");
+    let mut aug = augment(a, b);
+    let pivots = to_rref_mod(&mut aug, p);
+    if pivots.len() < n || pivots.iter().any(|&j| j >= n) { return None; }
+    Some(range(0, n).map(|i| *aug.get(i, n)).to_owned_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matrix::Mat2;
+
+    #[test]
+    fn test_rref_rank_mod() {
+        let a = Mat2::from_vec(~[~[-1i, 3]]).unwrap();
+        assert_eq!(rref_mod(&a, 5), (Mat2::from_vec(~[~[1, 2]]).unwrap(), ~[0]));
+
+        // the determinant is 2, which is zero mod 2
+        let b = Mat2::from_vec(~[~[1i, 1], ~[1, 3]]).unwrap();
+        assert_eq!(rank_mod(&b, 2), 1);
+        assert_eq!(rank_mod(&b, 3), 2);
+    }
+
+    #[test]
+    fn test_inverse_mod() {
+        let a = Mat2::from_vec(~[~[1i, 2], ~[3, 4]]).unwrap();
+        let inv = inverse_mod(&a, 7).unwrap();
+        assert_eq!(inv, Mat2::from_vec(~[~[5, 1], ~[5, 3]]).unwrap());
+        assert_eq!((a * inv).map(|x| *x % 7), Mat2::identity(2));
+        assert!(inverse_mod(&a, 2).is_none());
+        assert_eq!(inverse_mod(&Mat2::zeros(0, 0), 7), Some(Mat2::zeros(0, 0)));
+    }
+
+    #[test]
+    fn test_solve_mod() {
+        let a = Mat2::from_vec(~[~[2i, 1], ~[1, 3]]).unwrap();
+        assert_eq!(solve_mod(&a, &[1, 2], 7), Some(~[3, 2]));
+        assert_eq!(solve_mod(&a, &[1, 2], 5), None);
+        assert_eq!(solve_mod(&Mat2::zeros(0, 0), &[], 7), Some(~[]));
+    }
+}
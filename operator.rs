@@ -0,0 +1,175 @@
+//! Linear operators that only need to know how to act on a vector, so the iterative solvers can
+//! work with matrices that are never written down.
+
+use std::num::{Zero, zero};
+use matrix::{Mat2, Shape};
+
+/// Something that acts like a matrix when multiplied by a vector.
+pub trait LinearOperator<T> {
+    /// The (rows, columns) of the operator.
+    fn dimensions(&self) -> (uint, uint);
+
+    /// Compute `A x`. `x` must have as many entries as the operator has columns.
+    fn apply(&self, x: &[T]) -> ~[T];
+
+    /// Compute `Aᵀ x`, or return `None` if the operator doesn't know how.
+    fn apply_transpose(&self, _x: &[T]) -> Option<~[T]> {
+        None
+    }
+}
+
+impl<T: Clone + Zero + Add<T, T> + Mul<T, T>> LinearOperator<T> for Mat2<T> {
+    fn dimensions(&self) -> (uint, uint) {
+        let s = self.get_dimension();
+        (s.rows, s.cols)
+    }
+
+    fn apply(&self, x: &[T]) -> ~[T] {
+        assert_eq!(self.get_dimension().cols, x.len());
+        self.row_iter().map(|row| {
+            row.iter().zip(x.iter()).fold(zero::<T>(), |a, (r, v)| a + *r * *v)
+        }).to_owned_vec()
+    }
+
+    fn apply_transpose(&self, x: &[T]) -> Option<~[T]> {
+        let Shape { rows: n, cols: m } = self.get_dimension();
+        assert_eq!(n, x.len());
+        Some(range(0, m).map(|j| {
+            self.column_iter(j).zip(x.iter()).fold(zero::<T>(), |a, (c, v)| a + *c * *v)
+        }).to_owned_vec())
+    }
+}
+
+/// A diagonal matrix, stored as just its diagonal.
+#[deriving(Clone, Eq)]
+pub struct Diagonal<T> {
+    priv diag: ~[T],
+}
+
+impl<T> Diagonal<T> {
+    /// Create a diagonal operator with `d` along its diagonal.
+    pub fn new(d: ~[T]) -> Diagonal<T> {
+        Diagonal { diag: d }
+    }
+
+    /// The entries along the diagonal.
+    pub fn as_slice<'a>(&'a self) -> &'a [T] {
+        self.diag.as_slice()
+    }
+}
+
+impl<T: Mul<T, T>> LinearOperator<T> for Diagonal<T> {
+    fn dimensions(&self) -> (uint, uint) {
+        (self.diag.len(), self.diag.len())
+    }
+
+    fn apply(&self, x: &[T]) -> ~[T] {
+        assert_eq!(self.diag.len(), x.len());
+        self.diag.iter().zip(x.iter()).map(|(d, v)| *d * *v).to_owned_vec()
+    }
+
+    fn apply_transpose(&self, x: &[T]) -> Option<~[T]> {
+        Some(self.apply(x))
+    }
+}
+
+/// A tridiagonal matrix, stored as its three diagonals: `sub` below the main diagonal, `diag` on
+/// it, and `sup` above it. Solve systems with it using `system::solve_tridiag`.
+#[deriving(Clone, Eq)]
+pub struct Tridiagonal<T> {
+    priv sub: ~[T],
+    priv diag: ~[T],
+    priv sup: ~[T],
+}
+
+impl<T> Tridiagonal<T> {
+    /// Create an (n x n) tridiagonal matrix from its `n` diagonal entries and the `n - 1` entries
+    /// on each side. Fails if the lengths don't fit together.
+    pub fn new(sub: ~[T], diag: ~[T], sup: ~[T]) -> Tridiagonal<T> {
+        let n = diag.len();
+        assert!(sub.len() == sup.len() && sub.len() == n.max(&1) - 1,
+                "Tridiagonal::new: off-diagonals must be one shorter than the diagonal");
+        Tridiagonal { sub: sub, diag: diag, sup: sup }
+    }
+
+    /// The entries below the diagonal, from `(1, 0)` to `(n - 1, n - 2)`.
+    pub fn sub<'a>(&'a self) -> &'a [T] {
+        self.sub.as_slice()
+    }
+
+    /// The entries on the diagonal.
+    pub fn diag<'a>(&'a self) -> &'a [T] {
+        self.diag.as_slice()
+    }
+
+    /// The entries above the diagonal, from `(0, 1)` to `(n - 2, n - 1)`.
+    pub fn sup<'a>(&'a self) -> &'a [T] {
+        self.sup.as_slice()
+    }
+}
+
+impl<T: Zero + Clone> Tridiagonal<T> {
+    /// Copy into a dense `Mat2`.
+    pub fn to_mat(&self) -> Mat2<T> {
+        let n = self.diag.len();
+        Mat2::new_with(n, n, |i, j| {
+            if i == j { self.diag[i].clone() }
+            else if i == j + 1 { self.sub[j].clone() }
+            else if j == i + 1 { self.sup[i].clone() }
+            else { zero() }
+        })
+    }
+}
+
+impl<T: Add<T, T> + Mul<T, T> + Clone> LinearOperator<T> for Tridiagonal<T> {
+    fn dimensions(&self) -> (uint, uint) {
+        (self.diag.len(), self.diag.len())
+    }
+
+    fn apply(&self, x: &[T]) -> ~[T] {
+        let n = self.diag.len();
+        assert_eq!(n, x.len());
+        range(0, n).map(|i| {
+            let mut s = self.diag[i] * x[i];
+            if i > 0 { s = s + self.sub[i - 1] * x[i - 1]; }
+            if i + 1 < n { s = s + self.sup[i] * x[i + 1]; }
+            s
+        }).to_owned_vec()
+    }
+
+    fn apply_transpose(&self, x: &[T]) -> Option<~[T]> {
+        let t = Tridiagonal::new(self.sup.clone(), self.diag.clone(), self.sub.clone());
+        Some(t.apply(x))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matrix::Mat2;
+
+    #[test]
+    fn test_mat2_operator() {
+        let a = Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6]]).unwrap();
+        assert_eq!(a.dimensions(), (2, 3));
+        assert_eq!(a.apply(&[1, 0, 2]), ~[7, 16]);
+        assert_eq!(a.apply_transpose(&[1, 1]), Some(~[5, 7, 9]));
+    }
+
+    #[test]
+    fn test_tridiagonal_operator() {
+        let t = Tridiagonal::new(~[1i, 2], ~[3, 4, 5], ~[6, 7]);
+        let a = Mat2::from_vec(~[~[3i, 6, 0], ~[1, 4, 7], ~[0, 2, 5]]).unwrap();
+        assert_eq!(t.to_mat(), a);
+        assert_eq!(t.apply(&[1, 2, 3]), a.apply(&[1, 2, 3]));
+        assert_eq!(t.apply_transpose(&[1, 2, 3]), a.apply_transpose(&[1, 2, 3]));
+    }
+
+    #[test]
+    fn test_diagonal_operator() {
+        let d = Diagonal::new(~[2i, 3, 4]);
+        assert_eq!(d.dimensions(), (3, 3));
+        assert_eq!(d.apply(&[1, 1, 2]), ~[2, 3, 8]);
+        assert_eq!(d.apply_transpose(&[1, 1, 2]), Some(~[2, 3, 8]));
+    }
+}
@@ -0,0 +1,150 @@
+//! Permutations, stored as where each entry comes from rather than as a matrix of zeros and ones.
+
+use std::vec;
+use std::num::{Zero, One, zero, one};
+use matrix::{Mat2, Shape};
+use operator::LinearOperator;
+
+/// A permutation of `0 .. n`, acting like the permutation matrix `P` with a one at `(i, p[i])`
+/// in each row `i`. So row `i` of `P A` is row `p[i]` of `A`.
+#[deriving(Clone, Eq)]
+pub struct Permutation {
+    priv idx: ~[uint],
+}
+
+impl Permutation {
+    /// The permutation that leaves everything where it is.
+    pub fn identity(n: uint) -> Permutation {
+        Permutation { idx: range(0, n).to_owned_vec() }
+    }
+
+    /// Create a permutation from where each entry comes from. Returns `None` if `idx` isn't a
+    /// permutation of `0 .. idx.len()`.
+    pub fn from_vec(idx: ~[uint]) -> Option<Permutation> {
+        let mut seen = vec::from_elem(idx.len(), false);
+        for &i in idx.iter() {
+            if i >= idx.len() || seen[i] { return None; }
+            seen[i] = true;
+        }
+        Some(Permutation { idx: idx })
+    }
+
+    /// Turn this into the identity permutation of `0 .. n`, reusing its storage.
+    pub fn reset(&mut self, n: uint) {
+        self.idx.truncate(0);
+        self.idx.extend(&mut range(0, n));
+    }
+
+    /// How many things are being permuted.
+    pub fn len(&self) -> uint {
+        self.idx.len()
+    }
+
+    /// Where each entry comes from: entry `i` of `P x` is `x[as_slice()[i]]`.
+    pub fn as_slice<'a>(&'a self) -> &'a [uint] {
+        self.idx.as_slice()
+    }
+
+    /// Swap what ends up in positions `i` and `j`, i.e. swap rows `i` and `j` of `P`.
+    pub fn swap(&mut self, i: uint, j: uint) {
+        self.idx.swap(i, j);
+    }
+
+    /// The inverse permutation, which is also the transpose of `P`.
+    pub fn inverse(&self) -> Permutation {
+        let mut inv = vec::from_elem(self.idx.len(), 0u);
+        for (i, &p) in self.idx.iter().enumerate() {
+            inv[p] = i;
+        }
+        Permutation { idx: inv }
+    }
+
+    /// The product `P Q`, which applies `Q` first. Fails if the lengths differ.
+    pub fn compose(&self, q: &Permutation) -> Permutation {
+        assert!(self.len() == q.len(), "compose: permutations must be the same length");
+        Permutation { idx: self.idx.iter().map(|&i| q.idx[i]).to_owned_vec() }
+    }
+
+    /// `P x`, so entry `i` of the result is `x[p[i]]`. Fails if the lengths differ.
+    pub fn apply_vec<T: Clone>(&self, x: &[T]) -> ~[T] {
+        assert!(x.len() == self.len(), "apply_vec: vector must be the same length");
+        self.idx.iter().map(|&i| x[i].clone()).to_owned_vec()
+    }
+
+    /// `P A`, so row `i` of the result is row `p[i]` of `A`. Fails if `A` doesn't have a row per
+    /// entry.
+    pub fn apply_rows<T: Clone>(&self, a: &Mat2<T>) -> Mat2<T> {
+        let Shape { rows: n, cols: m } = a.get_dimension();
+        assert!(n == self.len(), "apply_rows: matrix must have a row per entry");
+        Mat2::new_with(n, m, |i, j| a.at(self.idx[i], j))
+    }
+
+    /// `A Pᵀ`, so column `j` of the result is column `p[j]` of `A`. Fails if `A` doesn't have a
+    /// column per entry.
+    pub fn apply_cols<T: Clone>(&self, a: &Mat2<T>) -> Mat2<T> {
+        let Shape { rows: n, cols: m } = a.get_dimension();
+        assert!(m == self.len(), "apply_cols: matrix must have a column per entry");
+        Mat2::new_with(n, m, |i, j| a.at(i, self.idx[j]))
+    }
+
+    /// The permutation as an explicit matrix `P`.
+    pub fn to_mat<T: Zero + One>(&self) -> Mat2<T> {
+        let n = self.len();
+        Mat2::new_with(n, n, |i, j| if self.idx[i] == j { one() } else { zero() })
+    }
+}
+
+impl<T: Clone> LinearOperator<T> for Permutation {
+    fn dimensions(&self) -> (uint, uint) {
+        (self.len(), self.len())
+    }
+
+    fn apply(&self, x: &[T]) -> ~[T] {
+        self.apply_vec(x)
+    }
+
+    fn apply_transpose(&self, x: &[T]) -> Option<~[T]> {
+        Some(self.inverse().apply_vec(x))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matrix::Mat2;
+    use operator::LinearOperator;
+
+    #[test]
+    fn test_from_vec() {
+        assert!(Permutation::from_vec(~[2, 0, 1]).is_some());
+        assert!(Permutation::from_vec(~[0, 0, 1]).is_none());
+        assert!(Permutation::from_vec(~[0, 3, 1]).is_none());
+        assert_eq!(Permutation::identity(3).as_slice(), &[0, 1, 2]);
+    }
+
+    #[test]
+    fn test_apply() {
+        let p = Permutation::from_vec(~[2, 0, 1]).unwrap();
+        let q = Permutation::from_vec(~[1, 0, 2]).unwrap();
+        let a = Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6], ~[7, 8, 9]]).unwrap();
+        let pm: Mat2<int> = p.to_mat();
+        let qm: Mat2<int> = q.to_mat();
+
+        assert_eq!(p.apply_rows(&a), pm * a);
+        assert_eq!(p.apply_cols(&a), a * pm.transpose());
+        assert_eq!(p.apply_vec(&[10i, 20, 30]), ~[30, 10, 20]);
+        assert_eq!(p.compose(&q).to_mat::<int>(), pm * qm);
+        assert_eq!(p.compose(&p.inverse()), Permutation::identity(3));
+        assert_eq!(p.inverse().to_mat::<int>(), pm.transpose());
+    }
+
+    #[test]
+    fn test_permutation_operator() {
+        let p = Permutation::from_vec(~[2, 0, 1]).unwrap();
+        let pm: Mat2<int> = p.to_mat();
+        let op = &p as &LinearOperator<int>;
+        assert_eq!(op.dimensions(), (3, 3));
+        assert_eq!(p.apply(&[10i, 20, 30]), pm.apply(&[10, 20, 30]));
+        assert_eq!(p.apply_transpose(&[10i, 20, 30]), pm.apply_transpose(&[10, 20, 30]));
+    }
+}
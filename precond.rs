@@ -0,0 +1,136 @@
+//! Preconditioners for the iterative solvers. A preconditioner `M` is a cheap approximation of
+//! `A` that's easy to solve with, and the solvers in `iterative` use `M⁻¹ r` in place of the
+//! residual `r` to head in a better direction. The closer `M⁻¹ A` is to the identity, the fewer
+//! iterations they take.
+
+use std::vec;
+use matrix::{Mat2, Shape};
+use system::{solve_lower_triangular, solve_upper_triangular};
+
+/// Something that can apply `M⁻¹` for some approximation `M` of a matrix.
+pub trait Preconditioner {
+    /// Compute `M⁻¹ r`.
+    fn apply_inverse(&self, r: &[f64]) -> ~[f64];
+}
+
+/// The do-nothing preconditioner `M = I`, which is what the plain solvers use.
+pub struct IdentityPrecond;
+
+impl Preconditioner for IdentityPrecond {
+    fn apply_inverse(&self, r: &[f64]) -> ~[f64] {
+        r.to_owned()
+    }
+}
+
+/// The Jacobi preconditioner, `M = diag(A)`. It costs next to nothing, and fixes up badly scaled
+/// rows.
+#[deriving(Clone)]
+pub struct JacobiPrecond {
+    priv inv_diag: ~[f64],
+}
+
+impl JacobiPrecond {
+    /// Take the diagonal of `a`. Fails if `a` isn't square or has a zero on its diagonal.
+    pub fn new(a: &Mat2<f64>) -> JacobiPrecond {
+        let Shape { rows: n, cols: m } = a.get_dimension();
+        assert!(m == n, "JacobiPrecond::new: matrix must be square");
+        assert!(range(0, n).all(|i| *a.get(i, i) != 0.0),
+                "JacobiPrecond::new: zero on the diagonal");
+        JacobiPrecond { inv_diag: vec::from_fn(n, |i| 1.0 / *a.get(i, i)) }
+    }
+}
+
+impl Preconditioner for JacobiPrecond {
+    fn apply_inverse(&self, r: &[f64]) -> ~[f64] {
+        assert_eq!(self.inv_diag.len(), r.len());
+        self.inv_diag.iter().zip(r.iter()).map(|(d, x)| *d * *x).to_owned_vec()
+    }
+}
+
+/// The zero fill-in incomplete Cholesky preconditioner IC(0), `M = L Lᵀ`, where `L` is what
+/// Cholesky would give if it were only allowed to put nonzeros where `A` has them. For a banded
+/// or otherwise sparse symmetric positive definite `A` it's usually a much better approximation
+/// than the diagonal, and if `A` doesn't fill in at all (e.g. tridiagonal) it's exact.
+#[deriving(Clone)]
+pub struct IncompleteCholesky {
+    priv l: Mat2<f64>,
+    priv lt: Mat2<f64>,
+}
+
+impl IncompleteCholesky {
+    /// Factor `a`, reading only its lower triangle. Returns `None` if the factorization breaks
+    /// down with a pivot that isn't positive, which can happen even for some positive definite
+    /// matrices. Fails if `a` isn't square.
+    pub fn new(a: &Mat2<f64>) -> Option<IncompleteCholesky> {
+        let Shape { rows: n, cols: m } = a.get_dimension();
+        assert!(m == n, "IncompleteCholesky::new: matrix must be square");
+
+        let mut l = Mat2::new_with(n, n, |i, j| if i >= j { *a.get(i, j) } else { 0.0 });
+        for k in range(0, n) {
+            let d = *l.get(k, k);
+            // catches NaN as well
+            if !(d > 0.0) { return None; }
+            let lkk = d.sqrt();
+            l.set(k, k, lkk);
+            for i in range(k + 1, n) {
+                *l.get_mut(i, k) /= lkk;
+            }
+            for j in range(k + 1, n) {
+                for i in range(j, n) {
+                    // only update where A itself is nonzero, so there's no fill-in
+                    if *a.get(i, j) != 0.0 {
+                        let u = *l.get(i, k) * *l.get(j, k);
+                        *l.get_mut(i, j) -= u;
+                    }
+                }
+            }
+        }
+        let lt = l.transpose();
+        Some(IncompleteCholesky { l: l, lt: lt })
+    }
+
+    /// The incomplete factor `L`.
+    pub fn l<'a>(&'a self) -> &'a Mat2<f64> {
+        &self.l
+    }
+}
+
+impl Preconditioner for IncompleteCholesky {
+    fn apply_inverse(&self, r: &[f64]) -> ~[f64] {
+        // the diagonal is positive, so neither of these can fail
+        let y = solve_lower_triangular(&self.l, r).unwrap();
+        solve_upper_triangular(&self.lt, y).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matrix::Mat2;
+
+    #[test]
+    fn test_jacobi_precond() {
+        let a = Mat2::from_vec(~[~[2.0, 1.0], ~[1.0, 4.0]]).unwrap();
+        assert_eq!(JacobiPrecond::new(&a).apply_inverse(&[1.0, 1.0]), ~[0.5, 0.25]);
+        assert_eq!(IdentityPrecond.apply_inverse(&[1.0, 2.0]), ~[1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_incomplete_cholesky() {
+        // no fill-in, so it's the exact Cholesky factor
+        let t = Mat2::from_vec(~[~[4.0, -1.0, 0.0], ~[-1.0, 4.0, -1.0], ~[0.0, -1.0, 4.0]]);
+        let t = t.unwrap();
+        let ic = IncompleteCholesky::new(&t).unwrap();
+        assert_mat_eq_eps!(*ic.l(), t.cholesky().unwrap(), 1e-12);
+        let x = ic.apply_inverse(&[3.0, 2.0, 3.0]);
+        assert_vec_eq_eps!(x, ~[1.0, 1.0, 1.0], 1e-12);
+
+        // (2, 1) would fill in, and gets dropped
+        let a = Mat2::from_vec(~[~[4.0, 1.0, 1.0], ~[1.0, 4.0, 0.0], ~[1.0, 0.0, 4.0]]).unwrap();
+        let ic = IncompleteCholesky::new(&a).unwrap();
+        assert_eq!(*ic.l().get(2, 1), 0.0);
+
+        let indefinite = Mat2::from_vec(~[~[1.0, 2.0], ~[2.0, 1.0]]).unwrap();
+        assert!(IncompleteCholesky::new(&indefinite).is_none());
+    }
+}
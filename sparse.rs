@@ -0,0 +1,331 @@
+//! Sparse matrices, for when nearly every entry is zero and storing them all isn't an option.
+//! Entries are collected into a `SparseBuilder` as (row, column, value) triplets in any order,
+//! and then compressed into a `SparseMat`, which stores each row's nonzeros contiguously
+//! (compressed sparse row, or CSR).
+
+use std::vec;
+use std::num::{Zero, zero};
+use matrix::{Mat2, Shape};
+use operator::LinearOperator;
+use vector::Vector;
+
+/// Collects the entries of a sparse matrix as a list of coordinates (COO). Entries can be pushed
+/// in any order, and pushing the same position more than once adds the values together, which is
+/// what you want when assembling finite element matrices.
+#[deriving(Clone)]
+pub struct SparseBuilder<T> {
+    priv n: uint,
+    priv m: uint,
+    priv entries: ~[(uint, uint, T)],
+}
+
+impl<T> SparseBuilder<T> {
+    /// Start building an (n x m) matrix with no entries.
+    pub fn new(n: uint, m: uint) -> SparseBuilder<T> {
+        SparseBuilder { n: n, m: m, entries: ~[] }
+    }
+
+    /// Add `v` at `(i, j)`. Fails if the position is out of bounds.
+    pub fn push(&mut self, i: uint, j: uint, v: T) {
+        assert!(i < self.n && j < self.m,
+                "push: ({}, {}) is out of bounds for a {}x{} matrix", i, j, self.n, self.m);
+        self.entries.push((i, j, v));
+    }
+
+    /// How many entries have been pushed so far, counting repeats.
+    pub fn len(&self) -> uint {
+        self.entries.len()
+    }
+}
+
+impl<T: Add<T, T>> SparseBuilder<T> {
+    /// Compress the entries into a `SparseMat`, summing any that share a position.
+    pub fn build(self) -> SparseMat<T> {
+        let (n, m) = (self.n, self.m);
+        let mut entries = self.entries;
+        entries.sort_by(|&(i, j, _), &(k, l, _)| (i, j).cmp(&(k, l)));
+
+        let mut row_ptr = ~[0u];
+        let mut col_idx = ~[];
+        let mut values: ~[T] = ~[];
+        let mut row = 0;
+        for (i, j, v) in entries.move_iter() {
+            let last = values.len();
+            if last > 0 && row == i && col_idx[last - 1] == j {
+                values[last - 1] = values[last - 1] + v;
+                continue;
+            }
+            while row < i {
+                row_ptr.push(last);
+                row += 1;
+            }
+            col_idx.push(j);
+            values.push(v);
+        }
+        while row_ptr.len() <= n {
+            row_ptr.push(values.len());
+        }
+
+        SparseMat { n: n, m: m, row_ptr: row_ptr, col_idx: col_idx, values: values }
+    }
+}
+
+/// A sparse matrix in compressed sparse row form. Only the stored entries take up memory, and
+/// anything that isn't stored is zero.
+#[deriving(Clone, Eq)]
+pub struct SparseMat<T> {
+    priv n: uint,
+    priv m: uint,
+    // INVARIANT: row i's entries are at row_ptr[i] .. row_ptr[i + 1] in col_idx and values, with
+    // strictly increasing columns, and row_ptr.len() == n + 1
+    priv row_ptr: ~[uint],
+    priv col_idx: ~[uint],
+    priv values: ~[T],
+}
+
+/// An iterator over the stored entries of a `SparseMat`, as (row, column, value), row by row.
+pub struct TripletIterator<'a, T> {
+    priv mat: &'a SparseMat<T>,
+    priv row: uint,
+    priv k: uint,
+}
+
+impl<'a, T> Iterator<(uint, uint, &'a T)> for TripletIterator<'a, T> {
+    fn next(&mut self) -> Option<(uint, uint, &'a T)> {
+        if self.k >= self.mat.values.len() { return None; }
+        while self.mat.row_ptr[self.row + 1] <= self.k {
+            self.row += 1;
+        }
+        let k = self.k;
+        self.k += 1;
+        Some((self.row, self.mat.col_idx[k], &self.mat.values[k]))
+    }
+}
+
+impl<T> SparseMat<T> {
+    /// The (rows, columns) of the matrix.
+    pub fn get_dimension(&self) -> Shape {
+        Shape::new(self.n, self.m)
+    }
+
+    /// The number of stored entries.
+    pub fn nnz(&self) -> uint {
+        self.values.len()
+    }
+
+    /// Get a reference to the entry at `(i, j)`, or `None` if nothing is stored there (so it's
+    /// zero). Fails if the position is out of bounds.
+    pub fn get<'a>(&'a self, i: uint, j: uint) -> Option<&'a T> {
+        assert!(i < self.n && j < self.m, "get: index out of bounds");
+        let (lo, hi) = (self.row_ptr[i], self.row_ptr[i + 1]);
+        self.col_idx.slice(lo, hi).bsearch_elem(&j).map(|k| &self.values[lo + k])
+    }
+
+    /// Iterate over the stored entries as (row, column, value), in row-major order.
+    pub fn triplets<'a>(&'a self) -> TripletIterator<'a, T> {
+        TripletIterator { mat: self, row: 0, k: 0 }
+    }
+}
+
+impl<T: Zero + Clone> SparseMat<T> {
+    /// Copy into a dense `Mat2`, filling in the zeros.
+    pub fn to_mat(&self) -> Mat2<T> {
+        let mut a = Mat2::new_with(self.n, self.m, |_, _| zero::<T>());
+        for (i, j, v) in self.triplets() {
+            a.set(i, j, v.clone());
+        }
+        a
+    }
+
+    /// Store just the nonzero entries of a dense matrix.
+    pub fn from_mat(a: &Mat2<T>) -> SparseMat<T> {
+        let Shape { rows: n, cols: m } = a.get_dimension();
+        let mut row_ptr = ~[0u];
+        let mut col_idx = ~[];
+        let mut values = ~[];
+        for row in a.row_iter() {
+            for (j, v) in row.iter().enumerate() {
+                if !v.is_zero() {
+                    col_idx.push(j);
+                    values.push(v.clone());
+                }
+            }
+            row_ptr.push(values.len());
+        }
+        SparseMat { n: n, m: m, row_ptr: row_ptr, col_idx: col_idx, values: values }
+    }
+}
+
+impl<T: Clone> SparseMat<T> {
+    /// The transpose, still in CSR form (so this is also how to get the matrix's columns
+    /// compressed).
+    pub fn transpose(&self) -> SparseMat<T> {
+        // count the entries in each column, then drop each entry into its slot
+        let mut row_ptr = vec::from_elem(self.m + 1, 0u);
+        for &j in self.col_idx.iter() {
+            row_ptr[j + 1] += 1;
+        }
+        for j in range(0, self.m) {
+            row_ptr[j + 1] += row_ptr[j];
+        }
+        let mut next = row_ptr.slice_to(self.m).to_owned();
+        let mut slots: ~[Option<(uint, T)>] = vec::from_fn(self.nnz(), |_| None);
+        for (i, j, v) in self.triplets() {
+            slots[next[j]] = Some((i, v.clone()));
+            next[j] += 1;
+        }
+        let (col_idx, values) = vec::unzip(slots.move_iter().map(|s| s.unwrap()));
+
+        SparseMat { n: self.m, m: self.n, row_ptr: row_ptr, col_idx: col_idx, values: values }
+    }
+}
+
+impl<T: Zero + Add<T, T> + Mul<T, T>> SparseMat<T> {
+    fn mul_slice(&self, x: &[T]) -> ~[T] {
+        assert!(x.len() == self.m, "mul_vec: vector must have an entry per column");
+        vec::from_fn(self.n, |i| {
+            range(self.row_ptr[i], self.row_ptr[i + 1]).fold(zero::<T>(), |s, k| {
+                s + self.values[k] * x[self.col_idx[k]]
+            })
+        })
+    }
+
+    /// The sparse matrix-vector product `A v`, which only touches the stored entries. Fails if
+    /// `v` doesn't have an entry per column.
+    pub fn mul_vec(&self, v: &Vector<T>) -> Vector<T> {
+        Vector::new(self.mul_slice(v.as_slice()))
+    }
+}
+
+impl<T: Zero + Add<T, T> + Mul<T, T> + Clone> SparseMat<T> {
+    /// The product `A B` with a dense matrix, which is dense too. Fails if the inner dimensions
+    /// don't match.
+    pub fn mul_mat(&self, b: &Mat2<T>) -> Mat2<T> {
+        let Shape { rows: r, cols: c } = b.get_dimension();
+        assert!(r == self.m, "mul_mat: inner dimensions must match");
+        let mut out = Mat2::new_with(self.n, c, |_, _| zero::<T>());
+        for (i, k, v) in self.triplets() {
+            let row = b.get_row(k);
+            for j in range(0, c) {
+                let x = *out.get(i, j) + *v * row[j];
+                out.set(i, j, x);
+            }
+        }
+        out
+    }
+}
+
+impl<T: Zero + Add<T, T> + Mul<T, T> + Clone> Mul<SparseMat<T>, SparseMat<T>> for SparseMat<T> {
+    /// The sparse product `A B`, a row at a time (Gustavson's algorithm). Each row of the result
+    /// is accumulated in a dense scratch row, so this takes time proportional to the work done
+    /// plus the number of columns. Fails if the inner dimensions don't match.
+    fn mul(&self, o: &SparseMat<T>) -> SparseMat<T> {
+        assert!(self.m == o.n, "mul: inner dimensions must match");
+        let mut acc: ~[Option<T>] = vec::from_fn(o.m, |_| None);
+        let mut row_ptr = ~[0u];
+        let mut col_idx = ~[];
+        let mut values = ~[];
+        for i in range(0, self.n) {
+            let mut touched = ~[];
+            for a in range(self.row_ptr[i], self.row_ptr[i + 1]) {
+                let k = self.col_idx[a];
+                for b in range(o.row_ptr[k], o.row_ptr[k + 1]) {
+                    let j = o.col_idx[b];
+                    let x = self.values[a] * o.values[b];
+                    acc[j] = match acc[j].take() {
+                        Some(s) => Some(s + x),
+                        None => { touched.push(j); Some(x) }
+                    };
+                }
+            }
+            touched.sort();
+            for &j in touched.iter() {
+                col_idx.push(j);
+                values.push(acc[j].take_unwrap());
+            }
+            row_ptr.push(values.len());
+        }
+        SparseMat { n: self.n, m: o.m, row_ptr: row_ptr, col_idx: col_idx, values: values }
+    }
+}
+
+impl<T: Zero + Add<T, T> + Mul<T, T> + Clone> LinearOperator<T> for SparseMat<T> {
+    fn dimensions(&self) -> (uint, uint) {
+        (self.n, self.m)
+    }
+
+    fn apply(&self, x: &[T]) -> ~[T] {
+        self.mul_slice(x)
+    }
+
+    fn apply_transpose(&self, x: &[T]) -> Option<~[T]> {
+        assert_eq!(self.n, x.len());
+        let mut out = vec::from_fn(self.m, |_| zero::<T>());
+        for (i, j, v) in self.triplets() {
+            out[j] = out[j] + *v * x[i];
+        }
+        Some(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matrix::{Mat2, Shape};
+    use operator::LinearOperator;
+    use vector::Vector;
+
+    #[test]
+    fn test_build() {
+        let mut b = SparseBuilder::new(3, 4);
+        b.push(2, 1, 5i);
+        b.push(0, 3, 1);
+        b.push(0, 0, 2);
+        b.push(2, 1, 1);
+        assert_eq!(b.len(), 4);
+        let s = b.build();
+        assert_eq!(s.nnz(), 3);
+        assert_eq!(s.get(2, 1), Some(&6));
+        assert_eq!(s.get(0, 3), Some(&1));
+        assert_eq!(s.get(1, 1), None);
+        assert_eq!(s.triplets().map(|(i, j, v)| (i, j, *v)).to_owned_vec(),
+                   ~[(0, 0, 2), (0, 3, 1), (2, 1, 6)]);
+
+        let empty: SparseMat<int> = SparseBuilder::new(2, 2).build();
+        assert_eq!(empty.nnz(), 0);
+        assert_eq!(empty.get(1, 1), None);
+    }
+
+    #[test]
+    fn test_to_from_mat() {
+        let a = Mat2::from_vec(~[~[1i, 0, 0], ~[0, 0, 0], ~[0, 3, 4]]).unwrap();
+        let s = SparseMat::from_mat(&a);
+        assert_eq!(s.nnz(), 3);
+        assert_eq!(s.get(2, 2), Some(&4));
+        assert_eq!(s.to_mat(), a);
+    }
+
+    #[test]
+    fn test_products() {
+        let a = Mat2::from_vec(~[~[1i, 0, 2], ~[0, 0, 3], ~[4, 0, 0]]).unwrap();
+        let b = Mat2::from_vec(~[~[0i, 1], ~[5, 0], ~[0, 2]]).unwrap();
+        let (sa, sb) = (SparseMat::from_mat(&a), SparseMat::from_mat(&b));
+
+        let v = Vector::new(~[1i, 2, 3]);
+        assert_eq!(sa.mul_vec(&v), a.mul_vec(&v));
+        assert_eq!(sa.mul_mat(&b), a * b);
+        assert_eq!((sa * sb).to_mat(), a * b);
+        assert_eq!((sa * sb).nnz(), 3);
+        assert_eq!(sa.transpose().to_mat(), a.transpose());
+        assert_eq!(sb.transpose().get_dimension(), Shape::new(2, 3));
+        assert_eq!(sa.apply(&[1, 2, 3]), ~[7, 9, 4]);
+        assert_eq!(sa.apply_transpose(&[1, 1, 1]), Some(~[5, 0, 5]));
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_push_out_of_bounds() {
+        let mut b = SparseBuilder::new(2, 2);
+        b.push(2, 0, 1i);
+    }
+}
@@ -0,0 +1,372 @@
+//! The singular value decomposition, the norms that come from it, and low-rank approximation.
+
+use std::vec;
+use std::cmp::{Less, Equal, Greater};
+use matrix::{Mat2, Shape};
+use operator::LinearOperator;
+use system::solve_multi;
+use vector::{dot, norm};
+
+/// Solve the ridge-regularized least squares problem for one row of factors in `complete`: find
+/// the `x` minimizing `Σ (t_j - x · f_j)² + lambda ||x||²` over the pairs `(t_j, f_j)` in `obs`,
+/// where each `f_j` is a factor vector of length `r`.
+fn ridge_solve(obs: &[(f64, &[f64])], r: uint, lambda: f64) -> ~[f64] {
+    let mut g = Mat2::new_with(r, r, |i, j| if i == j { lambda } else { 0.0 });
+    let mut rhs = vec::from_elem(r, 0.0f64);
+    for &(t, f) in obs.iter() {
+        g.rank1_update(1.0, f, f);
+        for k in range(0, r) {
+            rhs[k] += t * f[k];
+        }
+    }
+    let b = Mat2::from_fortran_vec(r, 1, rhs).unwrap();
+    match solve_multi(&g, &b) {
+        Some(x) => x.to_fortran_vec(),
+        None => vec::from_elem(r, 0.0f64),
+    }
+}
+
+/// One-sided Jacobi (Hestenes' method): rotate pairs of the vectors in `cols` until they're all
+/// orthogonal to each other. The rotations make up an orthogonal matrix `V` with `A V = W`, where
+/// `A` and `W` have `cols` as their columns before and after, so afterwards the norms of `cols`
+/// are the singular values of `A`. The same rotations are applied to `v` unless it's empty, so if
+/// that starts out as the columns of the identity it ends up as the columns of `V`. Convergence
+/// is quadratic, so the cap on the number of sweeps is only there in case rounding keeps some
+/// pair from ever quite settling down.
+fn orthogonalize(cols: &mut [~[f64]], v: &mut [~[f64]]) {
+    let k = cols.len();
+    let mut rotated = true;
+    let mut sweeps = 0;
+    while rotated && sweeps < 100 {
+        rotated = false;
+        sweeps += 1;
+        for p in range(0, k) {
+            for q in range(p + 1, k) {
+                let alpha = dot(cols[p], cols[p]);
+                let beta = dot(cols[q], cols[q]);
+                let gamma = dot(cols[p], cols[q]);
+                if gamma.abs() <= 1e-15 * (alpha * beta).sqrt() { continue; }
+                rotated = true;
+
+                // the rotation that zeroes the (p, q) entry of the Gram matrix
+                let zeta = (beta - alpha) / (2.0 * gamma);
+                let t = zeta.signum() / (zeta.abs() + (1.0 + zeta * zeta).sqrt());
+                let c = 1.0 / (1.0 + t * t).sqrt();
+                let s = c * t;
+                for i in range(0, cols[p].len()) {
+                    let (x, y) = (cols[p][i], cols[q][i]);
+                    cols[p][i] = c * x - s * y;
+                    cols[q][i] = s * x + c * y;
+                }
+                if v.is_empty() { continue; }
+                for i in range(0, v[p].len()) {
+                    let (x, y) = (v[p][i], v[q][i]);
+                    v[p][i] = c * x - s * y;
+                    v[q][i] = s * x + c * y;
+                }
+            }
+        }
+    }
+}
+
+impl Mat2<f64> {
+    /// The singular values, biggest first. There are as many as the smaller of the number of
+    /// rows and the number of columns.
+    pub fn singular_values(&self) -> ~[f64] {
+        let Shape { rows: n, cols: m } = self.get_dimension();
+        // Aᵀ has the same singular values, so orthogonalize whichever has fewer vectors
+        let mut vecs = if m <= n {
+            vec::from_fn(m, |j| self.column_iter(j).map(|x| *x).to_owned_vec())
+        } else {
+            self.row_iter().map(|r| r.to_owned()).to_owned_vec()
+        };
+        orthogonalize(vecs.as_mut_slice(), &mut []);
+
+        let mut sigma = vecs.map(|v| norm(v.as_slice()));
+        sigma.sort_by(|a, b| if *a > *b { Less } else if *a < *b { Greater } else { Equal });
+        sigma
+    }
+
+    /// The thin singular value decomposition `A = U Σ Vᵀ`, returned as `(U, σ, Vᵀ)`, where `σ` is
+    /// the diagonal of `Σ`, biggest first. With `k` the smaller of the number of rows `n` and
+    /// columns `m`, `U` is (n x k) and `Vᵀ` is (k x m), and both have orthonormal columns (rows,
+    /// for `Vᵀ`). Computed with one-sided Jacobi, which gets even the small singular values to
+    /// high relative accuracy.
+    pub fn svd(&self) -> (Mat2<f64>, ~[f64], Mat2<f64>) {
+        let Shape { rows: n, cols: m } = self.get_dimension();
+        if m > n {
+            // A = (Aᵀ)ᵀ = (U Σ Vᵀ)ᵀ = V Σ Uᵀ
+            let (u, sigma, vt) = self.transpose().svd();
+            return (vt.transpose(), sigma, u.transpose());
+        }
+
+        let mut w = vec::from_fn(m, |j| self.column_iter(j).map(|x| *x).to_owned_vec());
+        let mut v = vec::from_fn(m, |j| vec::from_fn(m, |i| if i == j { 1.0 } else { 0.0 }));
+        orthogonalize(w.as_mut_slice(), v.as_mut_slice());
+
+        let norms = w.map(|c| norm(c.as_slice()));
+        let mut order = range(0, m).to_owned_vec();
+        order.sort_by(|&a, &b| {
+            if norms[a] > norms[b] { Less } else if norms[a] < norms[b] { Greater } else { Equal }
+        });
+        let sigma = order.map(|&j| norms[j]);
+
+        // the columns of U are the normalized columns of A V, except that the ones for zero
+        // singular values are just zero, and need making up
+        let small = if m == 0 { 0.0 } else { sigma[0] * 1e-15 * n as f64 };
+        let mut ucols = order.map(|&j| {
+            if norms[j] > small { Some(w[j].map(|x| *x / norms[j])) } else { None }
+        });
+        for k in range(0, m) {
+            if ucols[k].is_some() { continue; }
+            // the standard basis vector that's the least in the span of the columns so far,
+            // with that span projected out
+            let mut best = ~[];
+            let mut best_norm = -1.0;
+            for i in range(0, n) {
+                let mut e = vec::from_fn(n, |r| if r == i { 1.0 } else { 0.0 });
+                for _ in range(0, 2) {
+                    for c in ucols.iter() {
+                        match *c {
+                            Some(ref c) => {
+                                let d = dot(e, c.as_slice());
+                                for r in range(0, n) { e[r] -= d * c[r]; }
+                            }
+                            None => {}
+                        }
+                    }
+                }
+                let ne = norm(e);
+                if ne > best_norm {
+                    best_norm = ne;
+                    best = e.map(|x| *x / ne);
+                }
+            }
+            ucols[k] = Some(best);
+        }
+
+        let ucols = ucols.move_iter().map(|c| c.unwrap()).to_owned_vec();
+        let u = Mat2::new_with(n, m, |i, k| ucols[k][i]);
+        let vt = Mat2::new_with(m, m, |k, j| v[order[k]][j]);
+        (u, sigma, vt)
+    }
+
+    /// The Moore-Penrose pseudo-inverse `A⁺ = V Σ⁺ Uᵀ`, where `Σ⁺` inverts the singular values
+    /// that aren't negligible (compared to the biggest) and leaves the rest at zero. `A⁺ b` is
+    /// the smallest least squares solution to `A x = b`.
+    pub fn pinv(&self) -> Mat2<f64> {
+        let Shape { rows: n, cols: m } = self.get_dimension();
+        let (u, sigma, vt) = self.svd();
+        let tol = if sigma.is_empty() { 0.0 } else { sigma[0] * 2.2e-16 * n.max(&m) as f64 };
+        let inv = sigma.map(|s| if *s > tol { 1.0 / *s } else { 0.0 });
+        Mat2::new_with(m, n, |i, j| {
+            range(0, inv.len()).fold(0.0, |s, k| s + *vt.get(k, i) * inv[k] * *u.get(j, k))
+        })
+    }
+
+    /// The 2-norm (spectral norm), `max ||A x|| / ||x||`, which is the biggest singular value.
+    pub fn norm_two(&self) -> f64 {
+        let sigma = self.singular_values();
+        if sigma.is_empty() { 0.0 } else { sigma[0] }
+    }
+
+    /// Estimate the 2-norm with `iters` rounds of power iteration on `Aᵀ A`, instead of finding
+    /// all the singular values. This is much cheaper for big matrices, and a handful of iterations
+    /// is usually plenty. The estimate is never bigger than the true 2-norm.
+    pub fn norm_two_approx(&self, iters: uint) -> f64 {
+        let m = self.get_dimension().cols;
+        let mut v = vec::from_fn(m, |i| 1.0 + i as f64 / m as f64);
+        let mut sigma = 0.0;
+        for _ in range(0, iters + 1) {
+            let nv = norm(v);
+            if nv == 0.0 { break; }
+            let av = self.apply(v.map(|x| *x / nv));
+            sigma = norm(av);
+            v = self.apply_transpose(av).unwrap();
+        }
+        sigma
+    }
+
+    /// The 2-norm condition number `||A|| ||A⁺||`, the ratio of the biggest singular value to the
+    /// smallest. Roughly, solving a system with `A` can lose `log10(cond)` digits of accuracy.
+    /// It's infinite if the matrix is rank deficient.
+    pub fn cond(&self) -> f64 {
+        let sigma = self.singular_values();
+        match sigma.last_opt() {
+            None => 0.0,
+            Some(&s) if s == 0.0 => 1.0 / 0.0,
+            Some(&s) => sigma[0] / s,
+        }
+    }
+
+    /// The nuclear norm (trace norm), the sum of the singular values.
+    pub fn norm_nuclear(&self) -> f64 {
+        self.singular_values().iter().fold(0.0, |s, x| s + *x)
+    }
+
+    /// Fill in missing entries, assuming the full matrix has (about) rank `rank`. `mask` is
+    /// true at the entries that are missing; whatever the matrix holds there is ignored. This
+    /// runs `iters` rounds of alternating least squares, fitting `A ≈ U Vᵀ` to just the known
+    /// entries, and returns the matrix with the missing entries taken from `U Vᵀ`. The known
+    /// entries are kept as they are. Fails if `mask` isn't the same shape as the matrix.
+    pub fn complete(&self, mask: &Mat2<bool>, rank: uint, iters: uint) -> Mat2<f64> {
+        let Shape { rows: n, cols: m } = self.get_dimension();
+        assert!(mask.get_dimension() == self.get_dimension(),
+                "complete: mask is the wrong shape");
+        // a little regularization keeps rows with fewer than `rank` known entries solvable
+        let lambda = 1e-9;
+
+        // any start will do, as long as the columns of V aren't parallel
+        let mut u = vec::from_elem(n, vec::from_elem(rank, 0.0f64));
+        let mut v = vec::from_fn(m, |j| vec::from_fn(rank, |k| {
+            1.0 + ((j * rank + k) as f64 * 0.618034).fract()
+        }));
+
+        for _ in range(0, iters) {
+            for i in range(0, n) {
+                let obs = range(0, m).filter(|&j| !*mask.get(i, j))
+                                     .map(|j| (*self.get(i, j), v[j].as_slice())).to_owned_vec();
+                u[i] = ridge_solve(obs, rank, lambda);
+            }
+            for j in range(0, m) {
+                let obs = range(0, n).filter(|&i| !*mask.get(i, j))
+                                     .map(|i| (*self.get(i, j), u[i].as_slice())).to_owned_vec();
+                v[j] = ridge_solve(obs, rank, lambda);
+            }
+        }
+
+        Mat2::new_with(n, m, |i, j| {
+            if *mask.get(i, j) { dot(u[i], v[j]) } else { *self.get(i, j) }
+        })
+    }
+}
+
+/// Total least squares: find the `x` that makes `(A + E) x = b + r` with the smallest possible
+/// correction `||[E r]||`, for when there's noise in `A` as well as `b`. For fitting, this
+/// minimizes the perpendicular distances to the fit rather than the vertical ones that ordinary
+/// least squares does. The solution comes from the right singular vector of `[A b]` with the
+/// smallest singular value. Returns `None` if that vector has a zero last entry, in which case
+/// there's no solution. Fails if `b` isn't one entry per row of `A`.
+pub fn lstsq_total(a: &Mat2<f64>, b: &[f64]) -> Option<~[f64]> {
+    let Shape { rows: n, cols: m } = a.get_dimension();
+    assert!(b.len() == n, "lstsq_total: b must have an entry per row of A");
+
+    let mut cols = vec::from_fn(m + 1, |j| {
+        if j < m { a.column_iter(j).map(|x| *x).to_owned_vec() } else { b.to_owned() }
+    });
+    let mut v = vec::from_fn(m + 1, |j| vec::from_fn(m + 1, |i| if i == j { 1.0 } else { 0.0 }));
+    orthogonalize(cols.as_mut_slice(), v.as_mut_slice());
+
+    let smallest = range(0, m + 1).min_by(|&j| norm(cols[j])).unwrap();
+    let z = &v[smallest];
+    if z[m] == 0.0 {
+        None
+    } else {
+        Some(range(0, m).map(|i| -z[i] / z[m]).to_owned_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matrix::Mat2;
+
+    #[test]
+    fn test_singular_values() {
+        // AᵀA has eigenvalues 45 and 5
+        let a = Mat2::from_vec(~[~[3.0, 0.0], ~[4.0, 5.0]]).unwrap();
+        assert_vec_eq_eps!(a.singular_values(), ~[45.0f64.sqrt(), 5.0f64.sqrt()], 1e-12);
+
+        let low_rank = Mat2::from_vec(~[~[1.0, 2.0], ~[2.0, 4.0]]).unwrap();
+        assert_vec_eq_eps!(low_rank.singular_values(), ~[5.0, 0.0], 1e-12);
+
+        let wide = Mat2::from_vec(~[~[0.0, 2.0, 0.0], ~[0.0, 0.0, -3.0]]).unwrap();
+        assert_vec_eq_eps!(wide.singular_values(), ~[3.0, 2.0], 1e-12);
+    }
+
+    #[test]
+    fn test_complete() {
+        let u = [1.0, 2.0, -1.0, 0.5];
+        let v = [3.0, 1.0, 2.0, -2.0];
+        let full = Mat2::new_with(4, 4, |i, j| u[i] * v[j]);
+        let mask = Mat2::from_vec(
+            ~[
+                ~[false, true, false, false],
+                ~[false, false, false, true],
+                ~[true, false, false, false],
+                ~[false, false, true, false]
+            ]).unwrap();
+        // the values at the missing entries shouldn't matter
+        let gappy = Mat2::new_with(4, 4, |i, j| {
+            if *mask.get(i, j) { 1e9 } else { *full.get(i, j) }
+        });
+        let filled = gappy.complete(&mask, 1, 50);
+        assert_mat_eq_eps!(filled, full, 1e-6);
+    }
+
+    fn check_svd(a: &Mat2<f64>) {
+        let (u, sigma, vt) = a.svd();
+        let k = sigma.len();
+        let eye = Mat2::new_with(k, k, |i, j| if i == j { 1.0 } else { 0.0 });
+        assert_mat_eq_eps!(u.transpose() * u, eye, 1e-12);
+        assert_mat_eq_eps!(vt * vt.transpose(), eye, 1e-12);
+        let mut us = u.clone();
+        us.scale_cols_by(sigma);
+        assert_mat_eq_eps!(us * vt, *a, 1e-12);
+        assert_vec_eq_eps!(sigma, a.singular_values(), 1e-12);
+    }
+
+    #[test]
+    fn test_svd() {
+        check_svd(&Mat2::from_vec(~[~[3.0, 0.0], ~[4.0, 5.0], ~[1.0, -1.0]]).unwrap());
+        check_svd(&Mat2::from_vec(~[~[1.0, 2.0, 3.0], ~[0.0, -1.0, 4.0]]).unwrap());
+        // rank deficient, so U has to be filled out
+        check_svd(&Mat2::from_vec(~[~[1.0, 2.0], ~[2.0, 4.0], ~[0.0, 0.0]]).unwrap());
+    }
+
+    #[test]
+    fn test_pinv() {
+        let a = Mat2::from_vec(~[~[1.0, 2.0], ~[2.0, 4.0]]).unwrap();
+        let mut e = a.clone();
+        e.scale_rows_by(&[1.0 / 25.0, 1.0 / 25.0]);
+        assert_mat_eq_eps!(a.pinv(), e, 1e-12);
+
+        let tall = Mat2::from_vec(~[~[1.0, 0.0], ~[1.0, 1.0], ~[1.0, 2.0]]).unwrap();
+        assert_mat_eq_eps!(tall.pinv() * tall,
+                           Mat2::new_with(2, 2, |i, j| if i == j { 1.0 } else { 0.0 }), 1e-12);
+    }
+
+    #[test]
+    fn test_lstsq_total() {
+        // fitting y = q t to (2, 1) and (1, 2): by symmetry the best line is y = t, whereas
+        // ordinary least squares would give q = 0.8
+        let t = Mat2::from_vec(~[~[2.0], ~[1.0]]).unwrap();
+        let q = lstsq_total(&t, &[1.0, 2.0]).unwrap();
+        assert_vec_eq_eps!(q, ~[1.0], 1e-12);
+
+        // consistent systems are solved exactly
+        let a = Mat2::from_vec(~[~[1.0, 2.0], ~[3.0, -1.0], ~[0.0, 1.0]]).unwrap();
+        let x = lstsq_total(&a, &[4.0, 5.0, 1.0]).unwrap();
+        assert_vec_eq_eps!(x, ~[2.0, 1.0], 1e-12);
+    }
+
+    #[test]
+    fn test_norms() {
+        let a = Mat2::from_vec(~[~[3.0, 0.0], ~[4.0, 5.0]]).unwrap();
+        assert!((a.norm_two() - 45.0f64.sqrt()).abs() < 1e-12);
+        assert!((a.norm_nuclear() - 45.0f64.sqrt() - 5.0f64.sqrt()).abs() < 1e-12);
+        let approx = a.norm_two_approx(30);
+        assert!(approx <= a.norm_two() + 1e-12);
+        assert!((approx - a.norm_two()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cond() {
+        let d = Mat2::from_vec(~[~[2.0, 0.0], ~[0.0, 1e-3]]).unwrap();
+        assert!((d.cond() - 2e3).abs() < 1e-9);
+        let q = Mat2::from_vec(~[~[0.6, -0.8], ~[0.8, 0.6]]).unwrap();
+        assert!((q.cond() - 1.0).abs() < 1e-12);
+        let s = Mat2::from_vec(~[~[1.0, 2.0], ~[2.0, 4.0]]).unwrap();
+        assert!(s.cond() > 1e15);
+    }
+}
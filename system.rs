@@ -1,22 +1,825 @@
 //! Doing operations on a matrix as if it were a system of linear equations
 
-use std::num::{Zero, zero};
-use matrix::Mat2;
+use std::vec;
+use std::cmp::{Less, Equal, Greater};
+use std::num::{Zero, zero, one};
+use matrix::{Mat2, Shape};
+use decomp::LU;
+use permutation::Permutation;
+use operator::{LinearOperator, Tridiagonal};
+use vector::dot;
+
+/// How to choose pivots during elimination.
+#[deriving(Eq, Clone)]
+pub enum Pivoting {
+    /// Use the diagonal entry as is. Only safe when it's known to be nonzero, e.g. for diagonally
+    /// dominant matrices.
+    NoPivoting,
+    /// Swap in the row with the largest entry (in absolute value) in the pivot column.
+    PartialPivoting,
+    /// Swap in the largest entry of everything that's left to eliminate, swapping columns as
+    /// well as rows (which reorders the unknowns). About twice the work of `PartialPivoting` for
+    /// a little more stability; it's mostly worth it for matrices that are close to singular.
+    FullPivoting,
+    /// Look for an entry that's the largest in both its row and its column, by alternately
+    /// searching the current column and row from the top left. That's nearly as stable as
+    /// `FullPivoting`, and usually only a few searches of a row or column each step.
+    RookPivoting,
+}
+
+impl Pivoting {
+    /// Pick the pivot for a step of elimination out of rows `r .. n` and columns `c .. m` of a
+    /// matrix whose entries `get` returns, as `(row, column)`. `NoPivoting` always picks
+    /// `(r, c)`, and `PartialPivoting` only looks down column `c`.
+    pub fn choose<T: Signed + Ord>(&self, r: uint, c: uint, n: uint, m: uint,
+                                   get: |uint, uint| -> T) -> (uint, uint) {
+        let (mut bi, mut bj) = (r, c);
+        let mut best = get(r, c).abs();
+        match *self {
+            NoPivoting => {}
+            PartialPivoting => {
+                for i in range(r + 1, n) {
+                    let x = get(i, c).abs();
+                    if x > best { best = x; bi = i; }
+                }
+            }
+            FullPivoting => {
+                for i in range(r, n) {
+                    for j in range(c, m) {
+                        let x = get(i, j).abs();
+                        if x > best { best = x; bi = i; bj = j; }
+                    }
+                }
+            }
+            RookPivoting => {
+                // each move is to something strictly bigger, so this stops; once a search
+                // after the first doesn't move, the entry is the biggest in its row and column
+                let mut searches = 0;
+                loop {
+                    let mut moved = false;
+                    if searches % 2 == 0 {
+                        for i in range(r, n) {
+                            let x = get(i, bj).abs();
+                            if x > best { best = x; bi = i; moved = true; }
+                        }
+                    } else {
+                        for j in range(c, m) {
+                            let x = get(bi, j).abs();
+                            if x > best { best = x; bj = j; moved = true; }
+                        }
+                    }
+                    searches += 1;
+                    if !moved && searches >= 2 { break; }
+                }
+            }
+        }
+        (bi, bj)
+    }
+}
+
+/// Knobs for the solvers that take a `SolveOpts`. The iterative solvers and `irls` look at `tol`
+/// and `max_iter`. `solve_opts`, `solve_multi_opts` and `Mat2::inverse_opts` look at
+/// `pivot_strategy` and `pivot_tol`, and `solve_refined` at those and `refine`. The other direct
+/// solvers (`solve_lu`, `solve_cholesky`, `solve_tridiag`, `lstsq`) have nothing here to tune and
+/// don't take one. `T` is the element type of the matrix, which `pivot_tol` is compared against.
+/// Start from `Default::default()` and override what you need, e.g.
+/// `SolveOpts { tol: 1e-6, ..Default::default() }`.
+#[deriving(Clone)]
+pub struct SolveOpts<T> {
+    /// Stop iterating once `||b - A x|| <= tol * ||b||`. Defaults to 1e-10.
+    tol: f64,
+    /// Give up iterating after this many iterations. Defaults to 1000.
+    max_iter: uint,
+    /// How to pick pivots in direct solves. Defaults to `PartialPivoting`.
+    pivot_strategy: Pivoting,
+    /// Pivots no bigger than this (in absolute value) count as zero in the direct solvers. Leave
+    /// it at 0 for exact element types; for floats, something like `1e-12` times the size of the
+    /// biggest entry catches matrices that are only nonsingular because of rounding. Defaults to
+    /// 0.
+    pivot_tol: T,
+    /// How many rounds of iterative refinement `solve_refined` does after its direct solve.
+    /// Defaults to 0.
+    refine: uint,
+}
+
+impl<T: Zero> Default for SolveOpts<T> {
+    fn default() -> SolveOpts<T> {
+        SolveOpts {
+            tol: 1e-10,
+            max_iter: 1000,
+            pivot_strategy: PartialPivoting,
+            pivot_tol: zero(),
+            refine: 0,
+        }
+    }
+}
 
 pub fn substitute<T: Clone + Zero + Mul<T, T> + Add<T, T>>
        (matrix: &Mat2<T>, values: &[T]) -> Mat2<T> {
 
-    let (_, n) = matrix.get_dimension();
-    assert_eq!(n, values.len());
-    Mat2::new_with(n, 1, |_,n| matrix.get_row(n).iter()
+    let Shape { rows: n, cols: m } = matrix.get_dimension();
+    assert_eq!(m, values.len());
+    Mat2::new_with(n, 1, |n,_| matrix.get_row(n).iter()
                                .enumerate()
                                .fold(zero::<T>(), |a, (i, b)| a + values[i]*(*b).clone()))
 }
 
+/// The augmented matrix `[A | b]`: `A` with `b` as an extra column on the right. Fails if `b`
+/// doesn't have an entry per row of `A`.
+pub fn augment<T: Clone>(a: &Mat2<T>, b: &[T]) -> Mat2<T> {
+    let Shape { rows: n, cols: m } = a.get_dimension();
+    assert!(b.len() == n, "augment: b must have an entry per row of A");
+    Mat2::new_with(n, m + 1, |i, j| if j < m { a.at(i, j) } else { b[i].clone() })
+}
+
+/// Split an augmented matrix `[A | b]` back into `A` and its last column `b`. Fails if the
+/// matrix has no columns.
+pub fn split_augmented<T: Clone>(aug: &Mat2<T>) -> (Mat2<T>, ~[T]) {
+    let Shape { rows: n, cols: m } = aug.get_dimension();
+    assert!(m > 0, "split_augmented: matrix has no columns");
+    let b = aug.column_iter(m - 1).map(|x| x.clone()).to_owned_vec();
+    (aug.submatrix(range(0, n), range(0, m - 1)), b)
+}
+
+/// What the solutions of `A x = b` look like. Made by `solve`.
+#[deriving(Clone, Eq)]
+pub enum Solution<T> {
+    /// There's exactly one solution.
+    Unique(~[T]),
+    /// The solutions are `particular` plus any combination of the `null_basis` vectors, which are
+    /// a basis of the null space of `A`: one per free variable, with a one in that variable's
+    /// entry and zeros in the other free ones.
+    Infinite { particular: ~[T], null_basis: ~[~[T]] },
+    /// There's no solution at all.
+    Inconsistent,
+}
+
+impl<T> Solution<T> {
+    /// The solution if there's exactly one, for when anything else is as good as a failure.
+    pub fn unique(self) -> Option<~[T]> {
+        match self {
+            Unique(x) => Some(x),
+            _ => None,
+        }
+    }
+}
+
+/// Solve `A x = b` for any shape of `A` with the default `SolveOpts`, and describe all of its
+/// solutions. See `solve_opts`.
+pub fn solve<T: Num + Signed + Ord + Clone>(a: &Mat2<T>, b: &[T]) -> Solution<T> {
+    solve_opts(a, b, &Default::default())
+}
+
+/// Solve `A x = b` for any shape of `A`, by reducing `[A | b]` to Reduced Row-Echelon Form with
+/// `Mat2::to_rref_opts`, passing on `opts.pivot_strategy` and `opts.pivot_tol`, and describe all
+/// of its solutions. A pivot in the `b` column means the system is inconsistent; otherwise the
+/// pivot columns are the basic variables and the rest are free, and the particular solution sets
+/// the free ones to zero. The same caveat as `to_rref` applies to floats: unless `pivot_tol` is
+/// big enough, rounding can turn an entry that should be zero into a pivot, so a singular system
+/// can come out `Unique` (with huge entries), or a consistent one `Inconsistent`. A nearly
+/// singular `A` gives an answer whose error can be as big as `Mat2::cond` (or `cond_one`) times
+/// the rounding error, so check that first when in doubt. Fails if `b` doesn't have an entry per
+/// row of `A`.
+pub fn solve_opts<T: Num + Signed + Ord + Clone>(a: &Mat2<T>, b: &[T],
+                                                 opts: &SolveOpts<T>) -> Solution<T> {
+    let Shape { rows: n, cols: m } = a.get_dimension();
+    assert!(b.len() == n, "solve: b must have an entry per row of A");
+
+    let mut aug = augment(a, b);
+    let pivots = aug.to_rref_opts(opts.pivot_strategy, opts.pivot_tol.clone());
+    if pivots.last_opt() == Some(&m) { return Inconsistent; }
+
+    let mut particular = vec::from_elem(m, zero::<T>());
+    for (r, &j) in pivots.iter().enumerate() {
+        particular[j] = aug.at(r, m);
+    }
+    if pivots.len() == m { return Unique(particular); }
+
+    let free = range(0, m).filter(|j| pivots.bsearch_elem(j).is_none()).to_owned_vec();
+    let null_basis = free.iter().map(|&f| {
+        let mut v = vec::from_elem(m, zero::<T>());
+        v[f] = one();
+        for (r, &j) in pivots.iter().enumerate() {
+            v[j] = -aug.at(r, f);
+        }
+        v
+    }).to_owned_vec();
+    Infinite { particular: particular, null_basis: null_basis }
+}
+
+/// Solve `A x = b` exactly, by Gauss-Jordan elimination that pivots on the first nonzero entry
+/// it finds rather than the biggest one. That's the right choice for exact element types like
+/// `extra::rational::Ratio`, where there's no rounding error for pivoting to keep small, and it
+/// means `T` doesn't need to be `Signed` or `Ord`. Don't use it for floats. Returns `None` if `A`
+/// is singular. Fails if `A` isn't square or `b` has the wrong length.
+pub fn solve_exact<T: Num + Clone>(a: &Mat2<T>, b: &[T]) -> Option<~[T]> {
+    let Shape { rows: n, cols: m } = a.get_dimension();
+    assert!(m == n && b.len() == n, "solve_exact: A must be square with as many rows as b");
+
+    let mut rows = a.row_iter().zip(b.iter()).map(|(r, x)| {
+        let mut row = r.to_owned();
+        row.push(x.clone());
+        row
+    }).to_owned_vec();
+
+    for j in range(0, n) {
+        let p = match range(j, n).find(|&i| rows[i][j] != zero()) {
+            Some(p) => p,
+            None => return None,
+        };
+        rows.swap(j, p);
+
+        let s = one::<T>() / rows[j][j];
+        for c in range(j, n + 1) {
+            rows[j][c] = rows[j][c] * s;
+        }
+        for i in range(0, n) {
+            if i != j && rows[i][j] != zero() {
+                let f = rows[i][j].clone();
+                for c in range(j, n + 1) {
+                    rows[i][c] = rows[i][c] - f * rows[j][c];
+                }
+            }
+        }
+    }
+    Some(rows.move_iter().map(|r| r[n].clone()).to_owned_vec())
+}
+
+/// Solve `A x = b` by Cramer's rule: `x_i = det(A_i) / det(A)`, where `A_i` is `A` with column
+/// `i` replaced by `b`. The determinants come from `Mat2::det_exact`, so with rationals the
+/// answer is exact (with integers the divisions truncate, unless the answer happens to be
+/// integral). That takes n + 1 determinants, so it's only sensible for small systems, but it's
+/// a nice closed form for 2x2 and 3x3 ones. Returns `None` if `A` is singular. Fails if `A`
+/// isn't square or `b` has the wrong length.
+pub fn solve_cramer<T: Num + Clone>(a: &Mat2<T>, b: &[T]) -> Option<~[T]> {
+    let Shape { rows: n, cols: m } = a.get_dimension();
+    assert!(m == n && b.len() == n, "solve_cramer: A must be square with as many rows as b");
+    let d = a.det_exact();
+    if d == zero() { return None; }
+
+    Some(vec::from_fn(n, |i| {
+        let ai = Mat2::new_with(n, n, |r, c| if c == i { b[r].clone() } else { a.at(r, c) });
+        ai.det_exact() / d
+    }))
+}
+
+/// Solve `A x = b` using an LU factorization of `A` from `Mat2::lu` or `Mat2::lu_opts`.
+/// Factoring is the expensive part, so this is the way to solve lots of systems with the same
+/// `A`. Returns `None` if `A` is singular. Fails if `b` has the wrong length.
+pub fn solve_lu<T: Num + Clone>(lu: &LU<T>, b: &[T]) -> Option<~[T]> {
+    let (a, perm) = (lu.packed(), lu.perm());
+    let n = perm.len();
+    assert!(b.len() == n, "solve_lu: b must have an entry per row");
+    if range(0, n).any(|i| *a.get(i, i) == zero()) { return None; }
+
+    // L y = P b, then U x = y, both in place in x
+    let mut x = perm.iter().map(|&i| b[i].clone()).to_owned_vec();
+    for i in range(0, n) {
+        let s = range(0, i).fold(x[i].clone(), |s, j| s - *a.get(i, j) * x[j]);
+        x[i] = s;
+    }
+    let mut i = n;
+    while i > 0 {
+        i -= 1;
+        let s = range(i + 1, n).fold(x[i].clone(), |s, j| s - *a.get(i, j) * x[j]);
+        x[i] = s / *a.get(i, i);
+    }
+    // that solved for Q x, so undo the column permutation
+    Some(lu.col_permutation().inverse().apply_vec(x))
+}
+
+// Knuth's two-sum and Dekker's two-product: a rounded result along with its exact rounding error.
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let s = a + b;
+    let z = s - a;
+    (s, (a - (s - z)) + (b - z))
+}
+
+fn two_prod(a: f64, b: f64) -> (f64, f64) {
+    // splitting factor 2^27 + 1, so each half has at most 26 bits and their products are exact
+    fn split(a: f64) -> (f64, f64) {
+        let c = 134217729.0 * a;
+        let h = c - (c - a);
+        (h, a - h)
+    }
+    let p = a * b;
+    let ((ah, al), (bh, bl)) = (split(a), split(b));
+    (p, al * bl - (((p - ah * bh) - al * bh) - ah * bl))
+}
+
+// b - A x, with each entry accumulated as if in twice the working precision (Ogita, Rump and
+// Oishi's Dot2). This is the "higher precision" residual that makes refinement worth doing.
+fn residual_dot2(a: &Mat2<f64>, x: &[f64], b: &[f64]) -> ~[f64] {
+    a.row_iter().zip(b.iter()).map(|(row, &bi)| {
+        let (mut s, mut c) = (bi, 0.0);
+        for (aij, xj) in row.iter().zip(x.iter()) {
+            let (p, ep) = two_prod(*aij, *xj);
+            let (t, es) = two_sum(s, -p);
+            s = t;
+            c += es - ep;
+        }
+        s + c
+    }).to_owned_vec()
+}
+
+/// Improve a solution `x` of `A x = b`, in place, by `rounds` rounds of iterative refinement:
+/// work out the residual `r = b - A x` in extra precision, solve `A d = r` with the LU
+/// factorization `lu` of `A`, and add `d` to `x`. Each round gains roughly as many digits as the
+/// solve itself gets right, so a couple of rounds get a moderately ill-conditioned system
+/// accurate to working precision. Stops early if the correction comes out as zero, or if `lu`
+/// is singular. Fails if the lengths don't match `A`.
+pub fn refine(a: &Mat2<f64>, lu: &LU<f64>, b: &[f64], x: &mut [f64], rounds: uint) {
+    let Shape { rows: n, cols: m } = a.get_dimension();
+    assert!(b.len() == n && x.len() == m, "refine: b and x must match A");
+    for _ in range(0, rounds) {
+        let d = match solve_lu(lu, residual_dot2(a, x, b)) {
+            Some(d) => d,
+            None => return,
+        };
+        if d.iter().all(|e| *e == 0.0) { return; }
+        for (xi, di) in x.mut_iter().zip(d.iter()) {
+            *xi += *di;
+        }
+    }
+}
+
+/// Solve `A x = b` by LU, pivoting according to `opts.pivot_strategy` and `opts.pivot_tol`, and
+/// then do `opts.refine` rounds of `refine` on the answer. Returns `None` if `A` is singular.
+/// Fails if `A` isn't square or `b` has the wrong length.
+pub fn solve_refined(a: &Mat2<f64>, b: &[f64], opts: &SolveOpts<f64>) -> Option<~[f64]> {
+    let Shape { rows: n, cols: m } = a.get_dimension();
+    assert!(m == n && b.len() == n, "solve_refined: A must be square with as many rows as b");
+    let lu = a.lu_opts(opts.pivot_strategy, opts.pivot_tol);
+    solve_lu(&lu, b).map(|mut x| {
+        refine(a, &lu, b, x.as_mut_slice(), opts.refine);
+        x
+    })
+}
+
+/// Solve `A x = b` for symmetric positive definite `A`, given its Cholesky factor `L` from
+/// `Mat2::cholesky`, by solving `L y = b` and then `Lᵀ x = y`. This is about twice as fast as LU.
+/// Fails if `b` has the wrong length.
+pub fn solve_cholesky(l: &Mat2<f64>, b: &[f64]) -> ~[f64] {
+    let n = l.get_dimension().rows;
+    assert!(b.len() == n, "solve_cholesky: b must have an entry per row");
+    let mut y = Mat2::from_fortran_vec(n, 1, b.to_owned()).unwrap();
+    solve_triangular(l, &mut y, Left, Lower);
+    // Lᵀ x = y is the same as xᵀ L = yᵀ
+    let mut x = y.transpose();
+    solve_triangular(l, &mut x, Right, Lower);
+    x.to_fortran_vec()
+}
+
+/// Solve `T x = b` for tridiagonal `T` with the Thomas algorithm, which is elimination that knows
+/// there's only one entry below each pivot, so it takes O(n) time and memory instead of O(n³).
+/// There's no pivoting, so it's meant for the diagonally dominant or positive definite systems
+/// that discretized 1D PDEs produce. Returns `None` if it hits a zero pivot. Fails if `b` has the
+/// wrong length.
+pub fn solve_tridiag<T: Num + Clone>(t: &Tridiagonal<T>, b: &[T]) -> Option<~[T]> {
+    let (sub, diag, sup) = (t.sub(), t.diag(), t.sup());
+    let n = diag.len();
+    assert!(b.len() == n, "solve_tridiag: b must have an entry per row");
+    if n == 0 { return Some(~[]); }
+
+    // eliminate the subdiagonal top down, keeping the new superdiagonal in c and b in x
+    let mut c = vec::with_capacity(n - 1);
+    let mut x = vec::with_capacity(n);
+    for i in range(0, n) {
+        let (piv, rhs) = if i == 0 {
+            (diag[0].clone(), b[0].clone())
+        } else {
+            (diag[i] - sub[i - 1] * c[i - 1], b[i] - sub[i - 1] * x[i - 1])
+        };
+        if piv == zero() { return None; }
+        if i + 1 < n { c.push(sup[i] / piv); }
+        x.push(rhs / piv);
+    }
+
+    let mut i = n - 1;
+    while i > 0 {
+        i -= 1;
+        x[i] = x[i] - c[i] * x[i + 1];
+    }
+    Some(x)
+}
+
+/// Solve `A X = B` for every column of `B` at once, with the default `SolveOpts`. See
+/// `solve_multi_opts`.
+pub fn solve_multi<T: Num + Signed + Ord + Clone>(a: &Mat2<T>, b: &Mat2<T>) -> Option<Mat2<T>> {
+    solve_multi_opts(a, b, &Default::default())
+}
+
+/// Solve `A X = B`, where each column of `B` is a right-hand side, by Gauss-Jordan elimination on
+/// `[A | B]`. All the columns ride along through a single elimination, which is much cheaper than
+/// solving for each of them separately. Pivots are chosen according to `opts.pivot_strategy`, and
+/// count as zero if they're no bigger than `opts.pivot_tol`. Returns `None` if `A` is singular
+/// (or if a pivot is zero under `NoPivoting`). Fails if `A`
+/// isn't square or `B` has a different number of rows.
+pub fn solve_multi_opts<T: Num + Signed + Ord + Clone>
+       (a: &Mat2<T>, b: &Mat2<T>, opts: &SolveOpts<T>) -> Option<Mat2<T>> {
+    solve_multi_ws(a, b, opts, &mut Workspace::new())
+}
+
+/// Scratch memory for the direct solvers and factorizations (`solve_multi_ws`, `Mat2::qr_into`,
+/// `Mat2::eigenvalues_ws`). Solving lots of same-sized problems one after another with the same
+/// `Workspace` reuses its buffers instead of allocating and freeing them each time.
+pub struct Workspace<T> {
+    priv rows: ~[~[T]],
+    priv vec: ~[T],
+}
+
+impl<T: Clone> Workspace<T> {
+    /// Create an empty workspace. It grows to fit the first problem it's used for.
+    pub fn new() -> Workspace<T> {
+        Workspace { rows: ~[], vec: ~[] }
+    }
+
+    /// Copy the rows of `a` into the scratch rows, reusing their existing allocations, and
+    /// return them.
+    pub fn load_rows<'a>(&'a mut self, a: &Mat2<T>) -> &'a mut [~[T]] {
+        let n = a.get_dimension().rows;
+        self.rows.truncate(n);
+        for (i, r) in a.row_iter().enumerate() {
+            if i == self.rows.len() { self.rows.push(~[]); }
+            let row = &mut self.rows[i];
+            row.truncate(0);
+            row.push_all(r);
+        }
+        self.rows.as_mut_slice()
+    }
+
+    /// A scratch vector of `len` copies of `x`, reusing the existing allocation.
+    pub fn scratch<'a>(&'a mut self, len: uint, x: T) -> &'a mut [T] {
+        self.vec.truncate(0);
+        self.vec.grow(len, &x);
+        self.vec.as_mut_slice()
+    }
+
+    /// Copy `[A | B]` into the scratch rows, reusing their existing allocations.
+    fn load(&mut self, a: &Mat2<T>, b: &Mat2<T>) {
+        let rows = self.load_rows(a);
+        for (i, row) in rows.mut_iter().enumerate() {
+            row.push_all(b.get_row(i));
+        }
+    }
+}
+
+/// `solve_multi_opts`, doing its elimination in the scratch space of `ws`.
+pub fn solve_multi_ws<T: Num + Signed + Ord + Clone>
+       (a: &Mat2<T>, b: &Mat2<T>, opts: &SolveOpts<T>,
+        ws: &mut Workspace<T>) -> Option<Mat2<T>> {
+
+    let Shape { rows: n, cols: m } = a.get_dimension();
+    let Shape { rows: bn, cols: k } = b.get_dimension();
+    assert!(m == n && bn == n, "solve_multi: A must be square with as many rows as B");
+
+    ws.load(a, b);
+    let aug = &mut ws.rows;
+    let w = n + k;
+    // which unknown each of the first n columns stands for, once column swaps have moved them
+    let mut cols = Permutation::identity(n);
+
+    for j in range(0, n) {
+        let (p, pc) = opts.pivot_strategy.choose(j, j, n, n, |i, c| aug[i][c].clone());
+        if aug[p][pc].abs() <= opts.pivot_tol { return None; }
+
+        aug.swap(j, p);
+        if pc != j {
+            for row in aug.mut_iter() {
+                row.swap(j, pc);
+            }
+            cols.swap(j, pc);
+        }
+        let s = one::<T>() / aug[j][j];
+        for c in range(j, w) {
+            aug[j][c] = aug[j][c] * s;
+        }
+        for i in range(0, n) {
+            if i != j && aug[i][j] != zero() {
+                let f = aug[i][j].clone();
+                for c in range(j, w) {
+                    aug[i][c] = aug[i][c] - f * aug[j][c];
+                }
+            }
+        }
+    }
+
+    // row i holds the unknown cols[i]
+    let inv = cols.inverse();
+    Some(Mat2::new_with(n, k, |i, c| aug[inv.as_slice()[i]][n + c].clone()))
+}
+
+/// Which side of the unknowns a matrix multiplies them from, as in `T X = B` vs `X T = B`.
+#[deriving(Eq, Clone)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+/// Which triangle of a triangular matrix holds its entries.
+#[deriving(Eq, Clone)]
+pub enum Uplo {
+    Upper,
+    Lower,
+}
+
+/// Solve `T X = B` (with `side` = `Left`) or `X T = B` (with `Right`) for `X`, where `T` is
+/// upper or lower triangular as given by `uplo` (BLAS's `trsm`). `X` overwrites `B`. Only the
+/// triangle of `T` named by `uplo` is read. Returns false, leaving `B` alone, if there's a zero on
+/// the diagonal of `T`. Fails if the shapes don't fit.
+pub fn solve_triangular<T: Num + Clone>(tri: &Mat2<T>, b: &mut Mat2<T>, side: Side,
+                                        uplo: Uplo) -> bool {
+    let Shape { rows: n, cols: m } = tri.get_dimension();
+    let Shape { rows: bn, cols: bm } = b.get_dimension();
+    assert!(m == n, "solve_triangular: matrix must be square");
+    let k = match side {
+        Left => { assert!(bn == n, "solve_triangular: B must have a row per row of T"); bm }
+        Right => { assert!(bm == n, "solve_triangular: B must have a column per row of T"); bn }
+    };
+    if range(0, n).any(|i| *tri.get(i, i) == zero()) { return false; }
+
+    // The unknowns are solved for in an order where each only depends on ones that came before:
+    // top down for T X = B with lower triangular T, bottom up for upper triangular, and the
+    // other way around for X T = B, since that's Tᵀ Xᵀ = Bᵀ.
+    let forward = match (side, uplo) {
+        (Left, Lower) | (Right, Upper) => true,
+        _ => false,
+    };
+    let order = vec::from_fn(n, |i| if forward { i } else { n - 1 - i });
+
+    let mut x = b.view_mut();
+    for (p, &i) in order.iter().enumerate() {
+        for r in range(0, k) {
+            let (bi, bj) = match side { Left => (i, r), Right => (r, i) };
+            let mut s = x.get(bi, bj).clone();
+            for &j in order.slice_to(p).iter() {
+                let (t, xj) = match side {
+                    Left => (tri.get(i, j), x.get(j, r)),
+                    Right => (tri.get(j, i), x.get(r, j)),
+                };
+                s = s - *t * *xj;
+            }
+            *x.get_mut(bi, bj) = s / *tri.get(i, i);
+        }
+    }
+    true
+}
+
+/// Solve `L x = b` for lower triangular `L` by forward substitution, working out `x` from the
+/// top down. Only the lower triangle of `L` is read. Returns `None` if there's a zero on its
+/// diagonal. Fails if `L` isn't square or `b` has the wrong length.
+pub fn solve_lower_triangular<T: Num + Clone>(l: &Mat2<T>, b: &[T]) -> Option<~[T]> {
+    let Shape { rows: n, cols: m } = l.get_dimension();
+    assert!(m == n && b.len() == n, "solve_lower_triangular: L must be square and match b");
+    if range(0, n).any(|i| *l.get(i, i) == zero()) { return None; }
+
+    let mut x: ~[T] = vec::with_capacity(n);
+    for i in range(0, n) {
+        let row = l.get_row(i);
+        let s = range(0, i).fold(b[i].clone(), |s, j| s - row[j] * x[j]);
+        x.push(s / row[i]);
+    }
+    Some(x)
+}
+
+/// Solve `U x = b` for upper triangular `U` by back substitution, working out `x` from the
+/// bottom up. Only the upper triangle of `U` is read. Returns `None` if there's a zero on its
+/// diagonal. Fails if `U` isn't square or `b` has the wrong length.
+pub fn solve_upper_triangular<T: Num + Clone>(u: &Mat2<T>, b: &[T]) -> Option<~[T]> {
+    let Shape { rows: n, cols: m } = u.get_dimension();
+    assert!(m == n && b.len() == n, "solve_upper_triangular: U must be square and match b");
+    if range(0, n).any(|i| *u.get(i, i) == zero()) { return None; }
+
+    let mut x = b.to_owned();
+    let mut i = n;
+    while i > 0 {
+        i -= 1;
+        let row = u.get_row(i);
+        let s = range(i + 1, n).fold(x[i].clone(), |s, j| s - row[j] * x[j]);
+        x[i] = s / row[i];
+    }
+    Some(x)
+}
+
+/// `Aᵀ (b - A x)`, which is minus half the gradient of `||A x - b||²`.
+fn ls_gradient(a: &Mat2<f64>, b: &[f64], x: &[f64]) -> ~[f64] {
+    let r = a.apply(x).iter().zip(b.iter()).map(|(ax, b)| *b - *ax).to_owned_vec();
+    a.apply_transpose(r).unwrap()
+}
+
+/// Least squares using only the columns of `a` listed in `cols`: minimize `||A_cols y - b||` by
+/// solving the normal equations. Returns `None` if those columns are linearly dependent.
+fn lstsq_cols(a: &Mat2<f64>, b: &[f64], cols: &[uint]) -> Option<~[f64]> {
+    let k = cols.len();
+    let c = vec::from_fn(k, |j| a.column_iter(cols[j]).map(|x| *x).to_owned_vec());
+    let g = Mat2::new_with(k, k, |i, j| dot(c[i], c[j]));
+    let rhs = Mat2::new_with(k, 1, |i, _| dot(c[i], b));
+    solve_multi(&g, &rhs).map(|y| y.to_fortran_vec())
+}
+
+/// Least squares: find the `x` that minimizes `||A x - b||` for an (n x m) `A` with n >= m, and
+/// return it along with the residual norm `||A x - b||`. This goes through the QR factorization,
+/// `R x = Qᵀ b`, which loses much less accuracy than the normal equations when `A` is badly
+/// conditioned. Returns `None` if the columns of `A` are (numerically) linearly dependent, in
+/// which case there's no unique solution; `Mat2::pinv` gives the smallest one. Fails if `A` is
+/// wider than it is tall, or `b` isn't one entry per row of `A`.
+pub fn lstsq(a: &Mat2<f64>, b: &[f64]) -> Option<(~[f64], f64)> {
+    let Shape { rows: n, cols: m } = a.get_dimension();
+    assert!(n >= m, "lstsq: A can't have more columns than rows");
+    assert!(b.len() == n, "lstsq: b must have an entry per row of A");
+
+    let (q, r) = a.qr();
+    let biggest = range(0, m).fold(0.0, |s, i| s.max(&r.get(i, i).abs()));
+    if range(0, m).any(|i| r.get(i, i).abs() <= 1e-12 * biggest) { return None; }
+
+    let qtb = q.apply_transpose(b).unwrap();
+    let x = solve_upper_triangular(&r.submatrix(range(0, m), range(0, m)), qtb.slice_to(m));
+    // the rest of Qᵀ b is the part of b that no combination of the columns can reach
+    let res = dot(qtb.slice_from(m), qtb.slice_from(m)).sqrt();
+    x.map(|x| (x, res))
+}
+
+/// Non-negative least squares: minimize `||A x - b||` subject to every `x_i >= 0`, by the
+/// Lawson-Hanson active set method. Variables are freed one at a time, most promising first,
+/// and pinned back to zero whenever the unconstrained solution for the free ones would make one
+/// of them negative. Fails if `b` isn't one entry per row of `A`.
+pub fn nnls(a: &Mat2<f64>, b: &[f64]) -> ~[f64] {
+    let Shape { rows: n, cols: m } = a.get_dimension();
+    assert!(b.len() == n, "nnls: b must have an entry per row of A");
+
+    let mut x = vec::from_elem(m, 0.0f64);
+    let tol = 1e-10 * ls_gradient(a, b, x).iter().fold(1.0, |s, w| s.max(&w.abs()));
+    let mut free = vec::from_elem(m, false);
+    // columns that turned out to be dependent on the free ones, which never need freeing
+    let mut useless = vec::from_elem(m, false);
+
+    // each pass frees a variable, so this is plenty unless things are going badly wrong
+    for _ in range(0, 3 * m + 1) {
+        let w = ls_gradient(a, b, x);
+        let next = range(0, m).filter(|&j| !free[j] && !useless[j] && w[j] > tol)
+                              .max_by(|&j| w[j]);
+        let t = match next {
+            Some(t) => t,
+            None => break,
+        };
+        free[t] = true;
+
+        loop {
+            let cols = range(0, m).filter(|&j| free[j]).to_owned_vec();
+            let mut s = vec::from_elem(m, 0.0f64);
+            match lstsq_cols(a, b, cols) {
+                Some(y) => {
+                    for (&j, v) in cols.iter().zip(y.move_iter()) { s[j] = v; }
+                }
+                None => {
+                    free[t] = false;
+                    useless[t] = true;
+                    break;
+                }
+            }
+
+            if cols.iter().all(|&j| s[j] > 0.0) {
+                x = s;
+                break;
+            }
+
+            // step from x towards s as far as we can without leaving the feasible region, then
+            // pin whatever hit zero
+            let alpha = cols.iter().filter(|&&j| s[j] <= 0.0)
+                            .fold(1.0f64, |al, &j| al.min(&(x[j] / (x[j] - s[j]))));
+            for j in range(0, m) {
+                x[j] += alpha * (s[j] - x[j]);
+                if free[j] && x[j] <= tol {
+                    free[j] = false;
+                    x[j] = 0.0;
+                }
+            }
+        }
+    }
+    x
+}
+
+/// Equality-constrained least squares: minimize `||A x - b||` subject to `C x = d`. This solves
+/// the KKT system `[AᵀA Cᵀ; C 0] [x; λ] = [Aᵀb; d]`, where `λ` are the Lagrange multipliers.
+/// Returns `None` if the problem doesn't have a unique solution, i.e. if the rows of `C` are
+/// linearly dependent, or `A` isn't full rank on the null space of `C`. Fails if the shapes don't
+/// fit.
+pub fn lstsq_constrained(a: &Mat2<f64>, b: &[f64], c: &Mat2<f64>, d: &[f64]) -> Option<~[f64]> {
+    let Shape { rows: n, cols: m } = a.get_dimension();
+    let Shape { rows: p, cols: cm } = c.get_dimension();
+    assert!(b.len() == n, "lstsq_constrained: b must have an entry per row of A");
+    assert!(cm == m && d.len() == p, "lstsq_constrained: C and d don't match A");
+
+    let cols = vec::from_fn(m, |j| a.column_iter(j).map(|x| *x).to_owned_vec());
+    let kkt = Mat2::new_with(m + p, m + p, |i, j| {
+        if i < m && j < m {
+            dot(cols[i], cols[j])
+        } else if i < m {
+            *c.get(j - m, i)
+        } else if j < m {
+            *c.get(i - m, j)
+        } else {
+            0.0
+        }
+    });
+    let rhs = Mat2::new_with(m + p, 1, |i, _| if i < m { dot(cols[i], b) } else { d[i - m] });
+
+    solve_multi(&kkt, &rhs).map(|sol| sol.to_fortran_vec().slice_to(m).to_owned())
+}
+
+/// Weighted least squares: minimize `Σ w_i (A x - b)_i²`, by solving the normal equations
+/// `Aᵀ W A x = Aᵀ W b`. The weights should be nonnegative. Returns `None` if the columns of `A`
+/// are linearly dependent (counting only the rows with nonzero weight). Fails if `b` or `w` isn't
+/// one entry per row of `A`.
+pub fn lstsq_weighted(a: &Mat2<f64>, b: &[f64], w: &[f64]) -> Option<~[f64]> {
+    let Shape { rows: n, cols: m } = a.get_dimension();
+    assert!(b.len() == n && w.len() == n, "lstsq_weighted: b and w must have an entry per row");
+
+    let mut g = Mat2::new_with(m, m, |_, _| 0.0);
+    let mut rhs = vec::from_elem(m, 0.0f64);
+    for (i, row) in a.row_iter().enumerate() {
+        g.rank1_update(w[i], row, row);
+        for j in range(0, m) {
+            rhs[j] += w[i] * b[i] * row[j];
+        }
+    }
+    solve_multi(&g, &Mat2::from_fortran_vec(m, 1, rhs).unwrap()).map(|x| x.to_fortran_vec())
+}
+
+/// How `irls` weighs down large residuals. The parameter is the tuning constant `k`, in units
+/// of the (robustly estimated) standard deviation of the residuals.
+#[deriving(Eq, Clone)]
+pub enum RobustLoss {
+    /// Quadratic for residuals up to `k`, linear beyond, so outliers still count but only a
+    /// bit. `k = 1.345` is the usual choice.
+    Huber(f64),
+    /// Tukey's biweight, which ignores residuals beyond `k` entirely. `k = 4.685` is the usual
+    /// choice.
+    Tukey(f64),
+}
+
+impl RobustLoss {
+    /// The weight given to a residual of `u` standard deviations.
+    fn weight(&self, u: f64) -> f64 {
+        match *self {
+            Huber(k) => if u.abs() <= k { 1.0 } else { k / u.abs() },
+            Tukey(k) => if u.abs() < k {
+                let t = 1.0 - (u / k) * (u / k);
+                t * t
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+fn median(mut v: ~[f64]) -> f64 {
+    v.sort_by(|a, b| if *a < *b { Less } else if *a > *b { Greater } else { Equal });
+    let n = v.len();
+    if n % 2 == 1 { v[n / 2] } else { (v[n / 2 - 1] + v[n / 2]) / 2.0 }
+}
+
+/// Robust regression by iteratively reweighted least squares: minimize `||A x - b||` with
+/// outliers downweighted according to `loss`. Starts from the ordinary least squares solution,
+/// then alternates between weighing each row by how far out its residual is and solving the
+/// weighted problem with `lstsq_weighted`. The residuals are scaled by their median absolute
+/// deviation, so the tuning constant doesn't depend on the units of `b`. Stops once no entry of
+/// `x` changes by more than `opts.tol` (relative to the biggest), or after `opts.max_iter`
+/// iterations. Returns `None` if one of the least squares problems has no unique solution.
+pub fn irls(a: &Mat2<f64>, b: &[f64], loss: RobustLoss,
+            opts: &SolveOpts<f64>) -> Option<~[f64]> {
+    let n = a.get_dimension().rows;
+    let mut x = match lstsq_weighted(a, b, vec::from_elem(n, 1.0)) {
+        Some(x) => x,
+        None => return None,
+    };
+
+    for _ in range(0, opts.max_iter) {
+        let r = a.apply(x).iter().zip(b.iter()).map(|(ax, b)| *b - *ax).to_owned_vec();
+        let scale = median(r.map(|r| r.abs())) / 0.6745;
+        // most of the points fit exactly, so there's nothing left to do
+        if scale == 0.0 { break; }
+
+        let w = r.map(|r| loss.weight(*r / scale));
+        let next = match lstsq_weighted(a, b, w) {
+            Some(x) => x,
+            None => return None,
+        };
+        let size = x.iter().fold(0.0, |s, v| s.max(&v.abs()));
+        let change = x.iter().zip(next.iter()).fold(0.0, |s, (p, q)| s.max(&(*p - *q).abs()));
+        x = next;
+        if change <= opts.tol * size { break; }
+    }
+    Some(x)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use matrix::Mat2;
+    use std::vec;
+    use matrix::{Mat2, mul_into, transpose_into};
+    use operator::Tridiagonal;
+    use extra::rational::Ratio;
 
     #[test]
     fn test_substitute() {
@@ -24,5 +827,342 @@ mod test {
         let r = substitute(&m, &[2i, 3i]);
         let m2 = Mat2::from_vec(~[~[5], ~[5]]).unwrap();
         assert_eq!(r, m2);
+
+        let m = Mat2::from_vec(~[~[1i, 2, 3], ~[0, 1, 0]]).unwrap();
+        let r = substitute(&m, &[1i, 1, 2]);
+        assert_eq!(r, Mat2::from_vec(~[~[9], ~[1]]).unwrap());
+    }
+
+    #[test]
+    fn test_augment() {
+        let a = Mat2::from_vec(~[~[1i, 2], ~[3, 4]]).unwrap();
+        let aug = augment(&a, &[5, 6]);
+        assert_eq!(aug, Mat2::from_vec(~[~[1, 2, 5], ~[3, 4, 6]]).unwrap());
+        assert_eq!(split_augmented(&aug), (a, ~[5, 6]));
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_augment_wrong_length() {
+        let a = Mat2::from_vec(~[~[1i, 2], ~[3, 4]]).unwrap();
+        augment(&a, &[5]);
+    }
+
+    #[test]
+    fn test_solve() {
+        let a = Mat2::from_vec(~[~[0.0, 2.0, 1.0], ~[1.0, 1.0, 1.0], ~[2.0, 1.0, -1.0]]).unwrap();
+        let x = solve(&a, &[4.0, 4.0, 1.0]).unique().unwrap();
+        assert_vec_eq_eps!(x, ~[1.0, 1.0, 2.0], 1e-12);
+
+        let m = Mat2::from_vec(~[~[2.0, 1.0], ~[4.0, 3.0]]).unwrap();
+        assert_eq!(solve(&m, &[3.0, 7.0]), Unique(~[1.0, 1.0]));
+
+        let singular = Mat2::from_vec(~[~[1.0, 2.0], ~[2.0, 4.0]]).unwrap();
+        assert_eq!(solve(&singular, &[1.0, 3.0]), Inconsistent);
+        assert!(solve(&singular, &[1.0, 3.0]).unique().is_none());
+    }
+
+    #[test]
+    fn test_solve_opts() {
+        // with a tolerance, the rounding error in the second row doesn't make a pivot
+        let near = Mat2::from_vec(~[~[1.0, 2.0], ~[2.0, 4.0 + 1e-14]]).unwrap();
+        assert!(solve(&near, &[1.0, 2.0]).unique().is_some());
+        let opts = SolveOpts { pivot_tol: 1e-10, ..Default::default() };
+        match solve_opts(&near, &[1.0, 2.0], &opts) {
+            Infinite { null_basis, .. } => assert_eq!(null_basis.len(), 1),
+            _ => fail!("expected a one dimensional family of solutions"),
+        }
+
+        // exact types can skip the search for the biggest pivot
+        let r = |a: int| Ratio::from_integer(a);
+        let a = Mat2::from_vec(~[~[r(0), r(2)], ~[r(3), r(1)]]).unwrap();
+        let opts = SolveOpts { pivot_strategy: NoPivoting, ..Default::default() };
+        assert_eq!(solve_opts(&a, &[r(2), r(4)], &opts), Unique(~[r(1), r(1)]));
+    }
+
+    #[test]
+    fn test_solve_underdetermined() {
+        // x + 2y + z = 4 and z = 2, so x = 2 - 2y for any y
+        let a = Mat2::from_vec(~[~[1i, 2, 1], ~[0, 0, 1]]).unwrap();
+        let e = Infinite { particular: ~[2, 0, 2], null_basis: ~[~[-2, 1, 0]] };
+        assert_eq!(solve(&a, &[4, 2]), e);
+
+        let singular = Mat2::from_vec(~[~[1.0, 2.0], ~[2.0, 4.0]]).unwrap();
+        let e = Infinite { particular: ~[1.0, 0.0], null_basis: ~[~[-2.0, 1.0]] };
+        assert_eq!(solve(&singular, &[1.0, 2.0]), e);
+
+        // overdetermined, but consistent
+        let tall = Mat2::from_vec(~[~[1.0, 0.0], ~[0.0, 1.0], ~[1.0, 1.0]]).unwrap();
+        assert_eq!(solve(&tall, &[1.0, 2.0, 3.0]), Unique(~[1.0, 2.0]));
+        assert_eq!(solve(&tall, &[1.0, 2.0, 4.0]), Inconsistent);
+    }
+
+    #[test]
+    fn test_solve_exact() {
+        let r = |a: int, b: int| Ratio::new(a, b);
+        let a = Mat2::from_vec(~[~[r(2, 1), r(1, 1)], ~[r(1, 1), r(3, 1)]]).unwrap();
+        assert_eq!(solve_exact(&a, &[r(1, 1), r(2, 1)]), Some(~[r(1, 5), r(3, 5)]));
+
+        // the Hilbert matrix is the classic one that floats get wrong
+        let h = Mat2::new_with(3, 3, |i, j| r(1, (i + j + 1) as int));
+        let one = r(1, 1);
+        assert_eq!(solve_exact(&h, &[one, one, one]), Some(~[r(3, 1), r(-24, 1), r(30, 1)]));
+
+        // a zero column is singular, and a zero pivot with something below it needs a swap
+        let s = Mat2::from_vec(~[~[r(0, 1), r(1, 1)], ~[r(0, 1), r(2, 1)]]).unwrap();
+        assert!(solve_exact(&s, &[one, one]).is_none());
+        let p = Mat2::from_vec(~[~[r(0, 1), r(1, 1)], ~[r(1, 1), r(0, 1)]]).unwrap();
+        assert_eq!(solve_exact(&p, &[r(1, 2), one]), Some(~[one, r(1, 2)]));
+    }
+
+    #[test]
+    fn test_solve_cramer() {
+        let r = |a: int, b: int| Ratio::new(a, b);
+        let a = Mat2::from_vec(~[~[r(2, 1), r(1, 1)], ~[r(1, 1), r(3, 1)]]).unwrap();
+        assert_eq!(solve_cramer(&a, &[r(1, 1), r(2, 1)]), Some(~[r(1, 5), r(3, 5)]));
+
+        let b = Mat2::from_vec(~[~[2i, 0, 1], ~[1, 3, 0], ~[0, 1, 4]]).unwrap();
+        assert_eq!(solve_cramer(&b, &[3, 4, 5]), Some(~[1, 1, 1]));
+        let singular = Mat2::from_vec(~[~[1i, 2], ~[2, 4]]).unwrap();
+        assert_eq!(solve_cramer(&singular, &[1, 2]), None);
+    }
+
+    #[test]
+    fn test_solve_lu() {
+        let a = Mat2::from_vec(~[~[0.0, 2.0, 1.0], ~[1.0, 1.0, 1.0], ~[2.0, 1.0, -1.0]]).unwrap();
+        let lu = a.lu();
+        let x = solve_lu(&lu, &[4.0, 4.0, 1.0]).unwrap();
+        assert_vec_eq_eps!(x, ~[1.0, 1.0, 2.0], 1e-12);
+        let x = solve_lu(&lu, &[3.0, 2.0, 0.0]).unwrap();
+        assert_vec_eq_eps!(x, ~[0.0, 1.0, 1.0], 1e-12);
+
+        let singular = Mat2::from_vec(~[~[1.0, 2.0], ~[2.0, 4.0]]).unwrap();
+        assert!(solve_lu(&singular.lu(), &[1.0, 2.0]).is_none());
+
+        // full pivoting reorders the unknowns, which solve_lu has to put back
+        let x = solve_lu(&a.lu_opts(FullPivoting, 0.0), &[4.0, 4.0, 1.0]).unwrap();
+        assert_vec_eq_eps!(x, ~[1.0, 1.0, 2.0], 1e-12);
+        let near = Mat2::from_vec(~[~[1.0, 2.0], ~[2.0, 4.0 + 1e-14]]).unwrap();
+        let opts = SolveOpts { pivot_tol: 1e-10, ..Default::default() };
+        assert!(solve_refined(&near, &[1.0, 2.0], &opts).is_none());
+    }
+
+    #[test]
+    fn test_solve_refined() {
+        // a scaled Hilbert matrix, which has integer entries but a condition number around 1e9,
+        // and b = A [1 ... 1] exactly
+        let a = Mat2::new_with(7, 7, |i, j| 360360.0 / (i + j + 1) as f64);
+        let b = a.row_iter().map(|r| r.iter().fold(0.0f64, |s, x| s + *x)).to_owned_vec();
+        let err = |x: ~[f64]| x.iter().fold(0.0f64, |s, e| s.max(&(*e - 1.0).abs()));
+
+        let plain = err(solve_refined(&a, b, &Default::default()).unwrap());
+        let refined = err(solve_refined(&a, b, &SolveOpts { refine: 3, ..Default::default() })
+                          .unwrap());
+        assert!(refined < 1e-12);
+        assert!(refined < plain);
+
+        let singular = Mat2::from_vec(~[~[1.0, 2.0], ~[2.0, 4.0]]).unwrap();
+        assert!(solve_refined(&singular, &[1.0, 2.0], &Default::default()).is_none());
+    }
+
+    #[test]
+    fn test_solve_cholesky() {
+        let a = Mat2::from_vec(~[~[4.0, 2.0, 0.0], ~[2.0, 5.0, 1.0], ~[0.0, 1.0, 3.0]]).unwrap();
+        let l = a.cholesky().unwrap();
+        let x = solve_cholesky(&l, &[6.0, 8.0, 4.0]);
+        assert_vec_eq_eps!(x, ~[1.0, 1.0, 1.0], 1e-12);
+    }
+
+    #[test]
+    fn test_solve_tridiag() {
+        // the 1D Poisson matrix
+        let t = Tridiagonal::new(~[-1.0, -1.0, -1.0], ~[2.0, 2.0, 2.0, 2.0], ~[-1.0, -1.0, -1.0]);
+        let x = solve_tridiag(&t, &[1.0, 0.0, 0.0, 1.0]).unwrap();
+        assert_vec_eq_eps!(x, ~[1.0, 1.0, 1.0, 1.0], 1e-12);
+        let b = &[3.0, -1.0, 2.0, 5.0];
+        let y = solve_tridiag(&t, b).unwrap();
+        assert_vec_eq_eps!(solve(&t.to_mat(), b).unique().unwrap(), y, 1e-12);
+
+        let zero_pivot = Tridiagonal::new(~[1.0], ~[0.0, 1.0], ~[1.0]);
+        assert!(solve_tridiag(&zero_pivot, &[1.0, 1.0]).is_none());
+    }
+
+    #[test]
+    fn test_solve_multi() {
+        let a = Mat2::from_vec(~[~[2.0, 1.0], ~[1.0, 3.0]]).unwrap();
+        let b = Mat2::from_vec(~[~[3.0, 1.0], ~[4.0, 2.0]]).unwrap();
+        let x = solve_multi(&a, &b).unwrap();
+        let e = Mat2::from_vec(~[~[1.0, 0.2], ~[1.0, 0.6]]).unwrap();
+        assert_mat_eq_eps!(x, e, 1e-12);
+
+        let singular = Mat2::from_vec(~[~[1.0, 2.0], ~[2.0, 4.0]]).unwrap();
+        assert!(solve_multi(&singular, &b).is_none());
+
+        // needs a row swap to get going
+        let a = Mat2::from_vec(~[~[0.0, 1.0], ~[1.0, 0.0]]).unwrap();
+        let x = solve_multi(&a, &b).unwrap();
+        assert_eq!(x, Mat2::from_vec(~[~[4.0, 2.0], ~[3.0, 1.0]]).unwrap());
+        let opts = SolveOpts { pivot_strategy: NoPivoting, ..Default::default() };
+        assert!(solve_multi_opts(&a, &b, &opts).is_none());
+
+        let a = Mat2::from_vec(~[~[1.0, 2.0, 0.0], ~[3.0, 1.0, 4.0], ~[2.0, -2.0, 1.0]]).unwrap();
+        let b = Mat2::from_vec(~[~[3.0], ~[8.0], ~[1.0]]).unwrap();
+        let opts = SolveOpts { pivot_strategy: FullPivoting, ..Default::default() };
+        let x = solve_multi_opts(&a, &b, &opts).unwrap();
+        assert_mat_eq_eps!(x, Mat2::from_vec(~[~[1.0], ~[1.0], ~[1.0]]).unwrap(), 1e-12);
+        let opts = SolveOpts { pivot_strategy: RookPivoting, ..Default::default() };
+        let x = solve_multi_opts(&a, &b, &opts).unwrap();
+        assert_mat_eq_eps!(x, Mat2::from_vec(~[~[1.0], ~[1.0], ~[1.0]]).unwrap(), 1e-12);
+
+        // singular up to rounding, which only a tolerance notices
+        let near = Mat2::from_vec(~[~[1.0, 2.0], ~[2.0, 4.0 + 1e-14]]).unwrap();
+        let b = Mat2::from_vec(~[~[1.0], ~[2.0]]).unwrap();
+        assert!(solve_multi(&near, &b).is_some());
+        let opts = SolveOpts { pivot_tol: 1e-10, ..Default::default() };
+        assert!(solve_multi_opts(&near, &b, &opts).is_none());
+    }
+
+    #[test]
+    fn test_solve_triangular() {
+        fn check(t: &Mat2<f64>, uplo: Uplo) {
+            let b = Mat2::from_vec(~[~[2.0, 4.0], ~[3.0, 5.0], ~[1.0, -2.0]]).unwrap();
+            let mut x = b.clone();
+            assert!(solve_triangular(t, &mut x, Left, uplo));
+            let mut tx = Mat2::new(3, 2);
+            mul_into(t, &x, &mut tx);
+            assert_mat_eq_eps!(tx, b, 1e-12);
+
+            let b = Mat2::from_vec(~[~[2.0, 3.0, 1.0], ~[4.0, 5.0, -2.0]]).unwrap();
+            let mut x = b.clone();
+            assert!(solve_triangular(t, &mut x, Right, uplo));
+            let mut xt = Mat2::new(2, 3);
+            mul_into(&x, t, &mut xt);
+            assert_mat_eq_eps!(xt, b, 1e-12);
+        }
+
+        let lower = Mat2::from_vec(
+            ~[
+                ~[2.0, 0.0, 0.0],
+                ~[1.0, 1.0, 0.0],
+                ~[-1.0, 3.0, 4.0]
+            ]).unwrap();
+        let mut upper = Mat2::new(3, 3);
+        transpose_into(&lower, &mut upper);
+        check(&lower, Lower);
+        check(&upper, Upper);
+
+        let singular = Mat2::from_vec(~[~[1.0, 0.0], ~[5.0, 0.0]]).unwrap();
+        let mut x = Mat2::from_vec(~[~[1.0], ~[1.0]]).unwrap();
+        assert!(!solve_triangular(&singular, &mut x, Left, Lower));
+        assert_eq!(x, Mat2::from_vec(~[~[1.0], ~[1.0]]).unwrap());
+    }
+
+    #[test]
+    fn test_solve_lower_upper_triangular() {
+        let l = Mat2::from_vec(~[~[2.0, 9.0, 9.0], ~[1.0, 1.0, 9.0], ~[3.0, -1.0, 4.0]]).unwrap();
+        // (the 9s are above the diagonal, so they're ignored)
+        assert_eq!(solve_lower_triangular(&l, &[2.0, 3.0, 9.0]), Some(~[1.0, 2.0, 2.0]));
+        let u = l.transpose();
+        assert_eq!(solve_upper_triangular(&u, &[9.0, -1.0, 8.0]), Some(~[1.0, 1.0, 2.0]));
+        let singular = Mat2::from_vec(~[~[1.0, 0.0], ~[1.0, 0.0]]).unwrap();
+        assert!(solve_lower_triangular(&singular, &[1.0, 1.0]).is_none());
+        assert!(solve_upper_triangular(&singular, &[1.0, 1.0]).is_none());
+    }
+
+    #[test]
+    fn test_lstsq() {
+        // fit y = c0 + c1 t to points that are exactly on y = 1 + 2t, then nudge them off it
+        let t = [0.0, 1.0, 2.0, 3.0];
+        let a = Mat2::new_with(4, 2, |i, j| if j == 0 { 1.0 } else { t[i] });
+        let (x, res) = lstsq(&a, [1.0, 3.0, 5.0, 7.0]).unwrap();
+        assert_vec_eq_eps!(x, ~[1.0, 2.0], 1e-12);
+        assert!(res < 1e-12);
+
+        let b = [1.1, 2.9, 5.1, 6.9];
+        let (x, res) = lstsq(&a, b).unwrap();
+        assert_vec_eq_eps!(x, ~[1.06, 1.96], 1e-12);
+        let r = a.apply(x).iter().zip(b.iter()).fold(0.0, |s, (p, q)| s + (*p - *q) * (*p - *q));
+        assert!((res - r.sqrt()).abs() < 1e-12);
+
+        let dependent = Mat2::new_with(3, 2, |i, _| i as f64);
+        assert!(lstsq(&dependent, [1.0, 2.0, 3.0]).is_none());
+    }
+
+    #[test]
+    fn test_nnls() {
+        // the unconstrained solution is (2, -1)
+        let a = Mat2::from_vec(~[~[1.0, 0.0], ~[0.0, 1.0], ~[1.0, 1.0]]).unwrap();
+        let x = nnls(&a, &[2.0, -1.0, 1.0]);
+        assert_vec_eq_eps!(x, ~[1.5, 0.0], 1e-12);
+
+        // when the unconstrained solution is already nonnegative, that's the answer
+        let x = nnls(&a, &[1.0, 2.0, 3.0]);
+        assert_vec_eq_eps!(x, ~[1.0, 2.0], 1e-12);
+
+        let x = nnls(&a, &[-1.0, -1.0, -1.0]);
+        assert_eq!(x, ~[0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_lstsq_constrained() {
+        // the closest point to (1, 2) on the line x + y = 1
+        let eye = Mat2::new_with(2, 2, |i, j| if i == j { 1.0 } else { 0.0 });
+        let c = Mat2::from_vec(~[~[1.0, 1.0]]).unwrap();
+        let x = lstsq_constrained(&eye, &[1.0, 2.0], &c, &[1.0]).unwrap();
+        assert_vec_eq_eps!(x, ~[0.0, 1.0], 1e-12);
+
+        // fit y = p + q t through the origin: the constraint pins p to 0
+        let a = Mat2::from_vec(~[~[1.0, 1.0], ~[1.0, 2.0], ~[1.0, 3.0]]).unwrap();
+        let c = Mat2::from_vec(~[~[1.0, 0.0]]).unwrap();
+        let x = lstsq_constrained(&a, &[2.0, 4.1, 5.9], &c, &[0.0]).unwrap();
+        assert_vec_eq_eps!(x, ~[0.0, (2.0 + 8.2 + 17.7) / 14.0], 1e-12);
+
+        // the same constraint twice over
+        let c = Mat2::from_vec(~[~[1.0, 0.0], ~[2.0, 0.0]]).unwrap();
+        assert!(lstsq_constrained(&a, &[2.0, 4.1, 5.9], &c, &[0.0, 0.0]).is_none());
+    }
+
+    #[test]
+    fn test_lstsq_weighted() {
+        let a = Mat2::from_vec(~[~[1.0], ~[1.0], ~[1.0]]).unwrap();
+        let x = lstsq_weighted(&a, &[1.0, 2.0, 6.0], &[1.0, 1.0, 1.0]).unwrap();
+        assert_vec_eq_eps!(x, ~[3.0], 1e-12);
+        let x = lstsq_weighted(&a, &[1.0, 2.0, 6.0], &[1.0, 3.0, 0.0]).unwrap();
+        assert_vec_eq_eps!(x, ~[1.75], 1e-12);
+    }
+
+    #[test]
+    fn test_irls() {
+        fn err(x: &[f64]) -> f64 { (x[0] - 1.0).abs().max(&(x[1] - 2.0).abs()) }
+
+        // y = 1 + 2 t, except for one wild point
+        let a = Mat2::new_with(8, 2, |i, j| if j == 0 { 1.0 } else { i as f64 });
+        let mut b = vec::from_fn(8, |i| 1.0 + 2.0 * i as f64 + 0.01 * (i % 3) as f64);
+        b[5] += 50.0;
+        let opts = SolveOpts { tol: 1e-12, max_iter: 100, ..Default::default() };
+
+        let ols = lstsq_weighted(&a, b, vec::from_elem(8, 1.0)).unwrap();
+        let huber = irls(&a, b, Huber(1.345), &opts).unwrap();
+        let tukey = irls(&a, b, Tukey(4.685), &opts).unwrap();
+        assert!(err(ols) > 1.0);
+        assert!(err(huber) < err(ols) / 10.0);
+        assert!(err(tukey) < 0.05);
+    }
+
+    #[test]
+    fn test_solve_multi_ws() {
+        let mut ws = Workspace::new();
+        let opts = Default::default();
+        let b = Mat2::from_vec(~[~[1.0], ~[2.0]]).unwrap();
+        for k in range(1, 5) {
+            let a = Mat2::from_vec(~[~[k as f64, 0.0], ~[0.0, 2.0]]).unwrap();
+            let x = solve_multi_ws(&a, &b, &opts, &mut ws).unwrap();
+            assert_eq!(x, Mat2::from_vec(~[~[1.0 / k as f64], ~[1.0]]).unwrap());
+        }
+
+        // a bigger problem after a smaller one just grows the workspace
+        let a = Mat2::new_with(3, 3, |i, j| if i == j { 1.0 } else { 0.0 });
+        let b = Mat2::new_with(3, 2, |i, j| (i + j) as f64);
+        assert_eq!(solve_multi_ws(&a, &b, &opts, &mut ws), Some(b.clone()));
     }
 }
@@ -0,0 +1,203 @@
+//! Vectors, for when an (n x 1) `Mat2` is an awkward stand-in for one.
+
+use std::vec;
+use std::num::zero;
+use matrix::{Mat2, Shape};
+
+/// A column vector, stored as a plain vector of its entries.
+#[deriving(Clone, Eq)]
+pub struct Vector<T> {
+    priv data: ~[T],
+}
+
+impl<T> Vector<T> {
+    /// Create a vector from its entries.
+    pub fn new(data: ~[T]) -> Vector<T> {
+        Vector { data: data }
+    }
+
+    /// Create a vector of length `n`, using `f` to create the entry at each index.
+    pub fn from_fn(n: uint, f: |uint| -> T) -> Vector<T> {
+        Vector { data: vec::from_fn(n, f) }
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> uint {
+        self.data.len()
+    }
+
+    /// Get a reference to entry `i`. Fails if `i` is out of bounds.
+    pub fn get<'a>(&'a self, i: uint) -> &'a T {
+        assert!(i < self.data.len(), "Vector::get: index out of bounds");
+        &self.data[i]
+    }
+
+    /// The entries, as a slice.
+    pub fn as_slice<'a>(&'a self) -> &'a [T] {
+        self.data.as_slice()
+    }
+
+    /// Iterate over the entries.
+    pub fn iter<'a>(&'a self) -> vec::VecIterator<'a, T> {
+        self.data.iter()
+    }
+
+    /// Give back the entries as a plain vector.
+    pub fn into_vec(self) -> ~[T] {
+        self.data
+    }
+
+    /// A new vector with `f` applied to each entry.
+    pub fn map<U>(&self, f: |&T| -> U) -> Vector<U> {
+        Vector { data: self.data.map(f) }
+    }
+}
+
+impl<T: Clone> Vector<T> {
+    /// The vector as an (n x 1) matrix.
+    pub fn to_mat(&self) -> Mat2<T> {
+        Mat2::new_with(self.data.len(), 1, |i, _| self.data[i].clone())
+    }
+
+    /// The vector held in an (n x 1) matrix, e.g. what `system::substitute` returns. Returns
+    /// `None` if the matrix has more than one column.
+    pub fn from_mat(a: &Mat2<T>) -> Option<Vector<T>> {
+        let Shape { rows: n, cols: m } = a.get_dimension();
+        if m != 1 { return None; }
+        Some(Vector::from_fn(n, |i| a.at(i, 0)))
+    }
+}
+
+impl<T: Zero + Add<T, T> + Mul<T, T>> Vector<T> {
+    /// The dot product. Fails if the vectors aren't the same length.
+    pub fn dot(&self, other: &Vector<T>) -> T {
+        assert!(self.len() == other.len(), "dot: vectors must be the same length");
+        self.data.iter().zip(other.data.iter()).fold(zero(), |s, (x, y)| s + *x * *y)
+    }
+}
+
+impl<T: Mul<T, T>> Vector<T> {
+    /// Multiply every entry by `a`.
+    pub fn scale(&self, a: T) -> Vector<T> {
+        self.map(|x| *x * a)
+    }
+}
+
+impl<T: Sub<T, T> + Mul<T, T>> Vector<T> {
+    /// The cross product `self × other` of two 3-vectors. Fails if either isn't of length 3.
+    pub fn cross(&self, other: &Vector<T>) -> Vector<T> {
+        assert!(self.len() == 3 && other.len() == 3, "cross: vectors must be of length 3");
+        let (a, b) = (self.as_slice(), other.as_slice());
+        Vector::new(~[a[1] * b[2] - a[2] * b[1],
+                      a[2] * b[0] - a[0] * b[2],
+                      a[0] * b[1] - a[1] * b[0]])
+    }
+}
+
+impl Vector<f64> {
+    /// The Euclidean length.
+    pub fn norm(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    /// A unit vector in the same direction. The zero vector is returned as is, since it doesn't
+    /// have a direction.
+    pub fn normalize(&self) -> Vector<f64> {
+        let n = self.norm();
+        if n == 0.0 { self.clone() } else { self.map(|x| *x / n) }
+    }
+}
+
+impl<T: Add<T, T>> Add<Vector<T>, Vector<T>> for Vector<T> {
+    /// Entrywise sum. Fails if the vectors aren't the same length.
+    fn add(&self, other: &Vector<T>) -> Vector<T> {
+        assert!(self.len() == other.len(), "add: vectors must be the same length");
+        Vector::from_fn(self.len(), |i| self.data[i] + other.data[i])
+    }
+}
+
+impl<T: Sub<T, T>> Sub<Vector<T>, Vector<T>> for Vector<T> {
+    /// Entrywise difference. Fails if the vectors aren't the same length.
+    fn sub(&self, other: &Vector<T>) -> Vector<T> {
+        assert!(self.len() == other.len(), "sub: vectors must be the same length");
+        Vector::from_fn(self.len(), |i| self.data[i] - other.data[i])
+    }
+}
+
+impl<T: Neg<T>> Neg<Vector<T>> for Vector<T> {
+    fn neg(&self) -> Vector<T> {
+        self.map(|x| -*x)
+    }
+}
+
+impl<T: Zero + Add<T, T> + Mul<T, T>> Mat2<T> {
+    /// The matrix-vector product `A v`. Fails if `v` doesn't have an entry per column.
+    pub fn mul_vec(&self, v: &Vector<T>) -> Vector<T> {
+        let Shape { rows: n, cols: m } = self.get_dimension();
+        assert!(v.len() == m, "mul_vec: vector must have an entry per column");
+        Vector::from_fn(n, |i| {
+            self.get_row(i).iter().zip(v.iter()).fold(zero(), |s, (a, x)| s + *a * *x)
+        })
+    }
+}
+
+/// The dot product of two plain slices, for code that works on `~[f64]` rather than `Vector`.
+/// Stops at the end of the shorter one.
+pub fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b.iter()).fold(0.0, |s, (x, y)| s + *x * *y)
+}
+
+/// The Euclidean length of a plain slice.
+pub fn norm(a: &[f64]) -> f64 {
+    dot(a, a).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matrix::Mat2;
+
+    #[test]
+    fn test_ops() {
+        let u = Vector::new(~[1i, 2, 3]);
+        let v = Vector::new(~[4i, 5, 6]);
+        assert_eq!(u.dot(&v), 32);
+        assert_eq!(u.cross(&v), Vector::new(~[-3, 6, -3]));
+        assert_eq!(u.cross(&v).dot(&u), 0);
+        assert_eq!(u + v, Vector::new(~[5, 7, 9]));
+        assert_eq!(v - u, Vector::new(~[3, 3, 3]));
+        assert_eq!(-u, Vector::new(~[-1, -2, -3]));
+        assert_eq!(u.scale(2), Vector::new(~[2, 4, 6]));
+    }
+
+    #[test]
+    fn test_norm() {
+        let v = Vector::new(~[3.0, 4.0]);
+        assert_eq!(v.norm(), 5.0);
+        assert_eq!(v.normalize(), Vector::new(~[0.6, 0.8]));
+        let z = Vector::new(~[0.0, 0.0]);
+        assert_eq!(z.normalize(), z);
+    }
+
+    #[test]
+    fn test_mul_vec() {
+        let a = Mat2::from_vec(~[~[1i, 2, 3], ~[4, 5, 6]]).unwrap();
+        let v = Vector::new(~[1i, 0, -1]);
+        assert_eq!(a.mul_vec(&v), Vector::new(~[-2, -2]));
+        assert_eq!(Vector::from_mat(&v.to_mat()), Some(v));
+        assert_eq!(Vector::from_mat(&a), None);
+    }
+
+    #[test]
+    #[should_fail]
+    fn test_cross_wrong_length() {
+        Vector::new(~[1i, 2]).cross(&Vector::new(~[3i, 4]));
+    }
+
+    #[test]
+    fn test_slice_helpers() {
+        assert_eq!(dot(&[1.0, 2.0, 3.0], &[4.0, -5.0, 6.0]), 12.0);
+        assert_eq!(norm(&[3.0, 4.0]), 5.0);
+        assert_eq!(norm(&[]), 0.0);
+    }
+}